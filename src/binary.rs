@@ -0,0 +1,726 @@
+//! A minimal reader for the WebAssembly binary format.
+//!
+//! WasmEdge does not expose the module structure through its C API, so the crate reads the few
+//! sections it needs for introspection itself. Errors use the codes WasmEdge reports for the same
+//! problems in the loading phase.
+
+use crate::context::ast::{
+    CustomSection, ExportType, ExternType, FuncType, GlobalType, ImportType, Limits, MemoryType,
+    NameSection, ProducerField, TableType,
+};
+use crate::error::{WasmEdgeError, WasmEdgeResult};
+use crate::types::{Mutability, RefType, ValType};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+use std::ops::Range;
+use std::path::Path;
+
+pub(crate) const MAGIC: [u8; 4] = [0x00, 0x61, 0x73, 0x6D];
+const VERSION: [u8; 4] = [0x01, 0x00, 0x00, 0x00];
+
+/// Whether the file at `path` starts with the WebAssembly magic number, i.e. holds a binary
+/// module rather than e.g. a native artifact. Only the first bytes are read, and a file that
+/// cannot be read is not a module.
+pub(crate) fn is_module_file(path: &Path) -> bool {
+    let mut magic = [0; 4];
+    match File::open(path).and_then(|mut file| file.read_exact(&mut magic)) {
+        Ok(()) => magic == MAGIC,
+        Err(_) => false,
+    }
+}
+
+pub(crate) const SECTION_CUSTOM: u8 = 0;
+pub(crate) const SECTION_TYPE: u8 = 1;
+pub(crate) const SECTION_IMPORT: u8 = 2;
+pub(crate) const SECTION_FUNCTION: u8 = 3;
+pub(crate) const SECTION_TABLE: u8 = 4;
+pub(crate) const SECTION_MEMORY: u8 = 5;
+pub(crate) const SECTION_GLOBAL: u8 = 6;
+pub(crate) const SECTION_EXPORT: u8 = 7;
+pub(crate) const SECTION_START: u8 = 8;
+pub(crate) const SECTION_CODE: u8 = 10;
+
+/// Creates an error with the category WasmEdge uses for the same code.
+pub(crate) fn binary_error(code: u32, message: impl Into<String>) -> WasmEdgeError {
+    WasmEdgeError::from_code(code, message.into())
+}
+
+/// A cursor over a byte slice that decodes the primitive encodings of the binary format.
+#[derive(Clone)]
+pub(crate) struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    /// The offset of `buf` in the module, used in error messages.
+    base: usize,
+}
+impl<'a> Reader<'a> {
+    pub(crate) fn new(buf: &'a [u8], base: usize) -> Self {
+        Reader { buf, pos: 0, base }
+    }
+
+    pub(crate) fn offset(&self) -> usize {
+        self.base + self.pos
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.pos >= self.buf.len()
+    }
+
+    fn unexpected_end(&self) -> WasmEdgeError {
+        binary_error(
+            0x22,
+            format!("unexpected end at offset {:#x}", self.offset()),
+        )
+    }
+
+    pub(crate) fn peek(&self) -> Option<u8> {
+        self.buf.get(self.pos).copied()
+    }
+
+    pub(crate) fn u8(&mut self) -> WasmEdgeResult<u8> {
+        let byte = *self
+            .buf
+            .get(self.pos)
+            .ok_or_else(|| self.unexpected_end())?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    pub(crate) fn bytes(&mut self, len: usize) -> WasmEdgeResult<&'a [u8]> {
+        match self.pos.checked_add(len) {
+            Some(end) if end <= self.buf.len() => {
+                let bytes = &self.buf[self.pos..end];
+                self.pos = end;
+                Ok(bytes)
+            }
+            _ => Err(self.unexpected_end()),
+        }
+    }
+
+    /// Consumes and returns the remaining bytes.
+    pub(crate) fn rest(&mut self) -> &'a [u8] {
+        let rest = &self.buf[self.pos..];
+        self.pos = self.buf.len();
+        rest
+    }
+
+    fn leb(&mut self, bits: u32, signed: bool) -> WasmEdgeResult<u64> {
+        let max_bytes = (bits + 6) / 7;
+        let mut result: u64 = 0;
+        let mut shift = 0;
+        for i in 0..max_bytes {
+            let byte = self.u8()?;
+            result |= u64::from(byte & 0x7F) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 {
+                // The unused bits of the last byte must be a sign or zero extension
+                if i == max_bytes - 1 && bits % 7 != 0 {
+                    let unused = byte >> (bits % 7 - 1);
+                    let ok = match signed {
+                        true => unused == 0 || unused == (0x7F >> (bits % 7 - 1)),
+                        false => unused >> 1 == 0,
+                    };
+                    if !ok {
+                        return Err(binary_error(
+                            0x35,
+                            format!("integer too large at offset {:#x}", self.offset() - 1),
+                        ));
+                    }
+                }
+                if signed && shift < 64 && byte & 0x40 != 0 {
+                    result |= !0 << shift;
+                }
+                return Ok(result);
+            }
+        }
+        Err(binary_error(
+            0x36,
+            format!(
+                "integer representation too long at offset {:#x}",
+                self.offset()
+            ),
+        ))
+    }
+
+    pub(crate) fn u32(&mut self) -> WasmEdgeResult<u32> {
+        Ok(self.leb(32, false)? as u32)
+    }
+
+    pub(crate) fn s32(&mut self) -> WasmEdgeResult<i32> {
+        Ok(self.leb(32, true)? as i32)
+    }
+
+    pub(crate) fn s64(&mut self) -> WasmEdgeResult<i64> {
+        Ok(self.leb(64, true)? as i64)
+    }
+
+    /// Reads the signed 33-bit integer used for type indices in block types.
+    pub(crate) fn s33(&mut self) -> WasmEdgeResult<i64> {
+        Ok(self.leb(33, true)? as i64)
+    }
+
+    /// Reads a vector length, rejecting lengths that cannot fit in the remaining bytes.
+    pub(crate) fn vec_len(&mut self) -> WasmEdgeResult<usize> {
+        let len = self.u32()? as usize;
+        match len <= self.buf.len() - self.pos {
+            true => Ok(len),
+            false => Err(self.unexpected_end()),
+        }
+    }
+
+    pub(crate) fn name(&mut self) -> WasmEdgeResult<String> {
+        let len = self.vec_len()?;
+        let offset = self.offset();
+        let bytes = self.bytes(len)?;
+        match std::str::from_utf8(bytes) {
+            Ok(s) => Ok(s.to_owned()),
+            Err(_) => Err(binary_error(
+                0x34,
+                format!("malformed UTF-8 encoding at offset {:#x}", offset),
+            )),
+        }
+    }
+
+    pub(crate) fn val_type(&mut self) -> WasmEdgeResult<ValType> {
+        let offset = self.offset();
+        match self.u8()? {
+            0x7F => Ok(ValType::I32),
+            0x7E => Ok(ValType::I64),
+            0x7D => Ok(ValType::F32),
+            0x7C => Ok(ValType::F64),
+            0x7B => Ok(ValType::V128),
+            0x70 => Ok(ValType::FuncRef),
+            0x6F => Ok(ValType::ExternRef),
+            byte => Err(binary_error(
+                0x31,
+                format!("malformed value type {:#04x} at offset {:#x}", byte, offset),
+            )),
+        }
+    }
+
+    pub(crate) fn ref_type(&mut self) -> WasmEdgeResult<RefType> {
+        let offset = self.offset();
+        match self.u8()? {
+            0x70 => Ok(RefType::FuncRef),
+            0x6F => Ok(RefType::ExternRef),
+            byte => Err(binary_error(
+                0x33,
+                format!(
+                    "malformed reference type {:#04x} at offset {:#x}",
+                    byte, offset
+                ),
+            )),
+        }
+    }
+
+    /// Reads limits, returning them with the `shared` flag of the threads proposal.
+    pub(crate) fn limits(&mut self) -> WasmEdgeResult<(Limits, bool)> {
+        let offset = self.offset();
+        let flags = self.u8()?;
+        if flags > 0x03 {
+            return Err(binary_error(
+                0x2E,
+                format!(
+                    "malformed limits flags {:#04x} at offset {:#x}",
+                    flags, offset
+                ),
+            ));
+        }
+        let min = self.u32()?;
+        let max = match flags & 0x01 {
+            0 => None,
+            _ => Some(self.u32()?),
+        };
+        Ok((Limits { min, max }, flags & 0x02 != 0))
+    }
+
+    pub(crate) fn func_type(&mut self) -> WasmEdgeResult<FuncType> {
+        let offset = self.offset();
+        let form = self.u8()?;
+        if form != 0x60 {
+            return Err(binary_error(
+                0x31,
+                format!(
+                    "malformed function type {:#04x} at offset {:#x}",
+                    form, offset
+                ),
+            ));
+        }
+        let mut params = vec![];
+        for _ in 0..self.vec_len()? {
+            params.push(self.val_type()?);
+        }
+        let mut returns = vec![];
+        for _ in 0..self.vec_len()? {
+            returns.push(self.val_type()?);
+        }
+        Ok(FuncType { params, returns })
+    }
+
+    pub(crate) fn table_type(&mut self) -> WasmEdgeResult<TableType> {
+        let ref_type = self.ref_type()?;
+        let (limits, _) = self.limits()?;
+        Ok(TableType { ref_type, limits })
+    }
+
+    pub(crate) fn memory_type(&mut self) -> WasmEdgeResult<MemoryType> {
+        let (limits, shared) = self.limits()?;
+        Ok(MemoryType { limits, shared })
+    }
+
+    pub(crate) fn global_type(&mut self) -> WasmEdgeResult<GlobalType> {
+        let val_type = self.val_type()?;
+        let offset = self.offset();
+        let mutability = match self.u8()? {
+            0x00 => Mutability::Const,
+            0x01 => Mutability::Var,
+            byte => {
+                return Err(binary_error(
+                    0x2F,
+                    format!("invalid mutability {:#04x} at offset {:#x}", byte, offset),
+                ))
+            }
+        };
+        Ok(GlobalType {
+            val_type,
+            mutability,
+        })
+    }
+
+    /// Skips a constant expression up to and including its `end` opcode.
+    pub(crate) fn skip_const_expr(&mut self) -> WasmEdgeResult<()> {
+        loop {
+            let offset = self.offset();
+            match self.u8()? {
+                0x0B => return Ok(()),
+                0x41 => {
+                    self.s32()?;
+                }
+                0x42 => {
+                    self.s64()?;
+                }
+                0x43 => {
+                    self.bytes(4)?;
+                }
+                0x44 => {
+                    self.bytes(8)?;
+                }
+                0x23 | 0xD2 => {
+                    self.u32()?;
+                }
+                0xD0 => {
+                    self.ref_type()?;
+                }
+                // Extended constant expressions
+                0x6A | 0x6B | 0x6C | 0x7C | 0x7D | 0x7E => {}
+                0xFD => match self.u32()? {
+                    0x0C => {
+                        self.bytes(16)?;
+                    }
+                    op => {
+                        return Err(binary_error(
+                            0x4C,
+                            format!(
+                                "constant expression required, found 0xfd {:#x} at offset {:#x}",
+                                op, offset
+                            ),
+                        ))
+                    }
+                },
+                op => {
+                    return Err(binary_error(
+                        0x4C,
+                        format!(
+                            "constant expression required, found {:#04x} at offset {:#x}",
+                            op, offset
+                        ),
+                    ))
+                }
+            }
+        }
+    }
+}
+
+/// A section of a module: its id and the range of its payload in the module bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Section {
+    pub(crate) id: u8,
+    pub(crate) range: Range<usize>,
+}
+
+/// Splits a module into its sections, checking the header and the section sizes.
+pub(crate) fn sections(bytes: &[u8]) -> WasmEdgeResult<Vec<Section>> {
+    let mut reader = Reader::new(bytes, 0);
+    if reader.bytes(4).ok() != Some(&MAGIC[..]) {
+        return Err(binary_error(0x23, "magic header not detected"));
+    }
+    if reader.bytes(4).ok() != Some(&VERSION[..]) {
+        return Err(binary_error(0x24, "unknown binary version"));
+    }
+    let mut sections = vec![];
+    while !reader.is_empty() {
+        let offset = reader.offset();
+        let id = reader.u8()?;
+        let len = reader.u32()? as usize;
+        let start = reader.offset();
+        if reader.bytes(len).is_err() {
+            return Err(binary_error(
+                0x26,
+                format!(
+                    "length of section {} at offset {:#x} is out of bounds",
+                    id, offset
+                ),
+            ));
+        }
+        sections.push(Section {
+            id,
+            range: start..start + len,
+        });
+    }
+    Ok(sections)
+}
+
+/// The body of a defined function.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct FunctionBody {
+    /// The local declarations after the parameters, as `(count, type)` runs.
+    pub(crate) locals: Vec<(u32, ValType)>,
+    /// The range of the instructions in the module bytes, including the final `end`.
+    pub(crate) code: Range<usize>,
+}
+
+/// The parts of a module that describe its interface.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct ModuleInfo {
+    pub(crate) types: Vec<FuncType>,
+    pub(crate) imports: Vec<ImportType>,
+    /// The type of every function, imported ones first.
+    pub(crate) funcs: Vec<FuncType>,
+    pub(crate) tables: Vec<TableType>,
+    pub(crate) memories: Vec<MemoryType>,
+    pub(crate) globals: Vec<GlobalType>,
+    pub(crate) exports: Vec<ExportType>,
+    pub(crate) start: Option<u32>,
+    /// The bodies of the defined functions, in the order of the code section.
+    pub(crate) bodies: Vec<FunctionBody>,
+    pub(crate) sections: Vec<Section>,
+}
+
+impl ModuleInfo {
+    pub(crate) fn parse(bytes: &[u8]) -> WasmEdgeResult<ModuleInfo> {
+        let mut info = ModuleInfo {
+            sections: sections(bytes)?,
+            ..Default::default()
+        };
+        let sections = info.sections.clone();
+        for section in sections.iter() {
+            let mut reader = Reader::new(&bytes[section.range.clone()], section.range.start);
+            match section.id {
+                SECTION_TYPE => {
+                    for _ in 0..reader.vec_len()? {
+                        let ty = reader.func_type()?;
+                        info.types.push(ty);
+                    }
+                }
+                SECTION_IMPORT => {
+                    for _ in 0..reader.vec_len()? {
+                        let module = reader.name()?;
+                        let name = reader.name()?;
+                        let ty = info.extern_desc(&mut reader, true)?;
+                        info.imports.push(ImportType { module, name, ty });
+                    }
+                }
+                SECTION_FUNCTION => {
+                    for _ in 0..reader.vec_len()? {
+                        let ty = info.func_type_at(&mut reader)?;
+                        info.funcs.push(ty);
+                    }
+                }
+                SECTION_TABLE => {
+                    for _ in 0..reader.vec_len()? {
+                        let ty = reader.table_type()?;
+                        info.tables.push(ty);
+                    }
+                }
+                SECTION_MEMORY => {
+                    for _ in 0..reader.vec_len()? {
+                        let ty = reader.memory_type()?;
+                        info.memories.push(ty);
+                    }
+                }
+                SECTION_GLOBAL => {
+                    for _ in 0..reader.vec_len()? {
+                        let ty = reader.global_type()?;
+                        reader.skip_const_expr()?;
+                        info.globals.push(ty);
+                    }
+                }
+                SECTION_EXPORT => {
+                    for _ in 0..reader.vec_len()? {
+                        let name = reader.name()?;
+                        let ty = info.extern_desc(&mut reader, false)?;
+                        info.exports.push(ExportType { name, ty });
+                    }
+                }
+                SECTION_START => info.start = Some(reader.u32()?),
+                SECTION_CODE => {
+                    for _ in 0..reader.vec_len()? {
+                        let size = reader.vec_len()?;
+                        let start = reader.offset();
+                        let mut body = Reader::new(reader.bytes(size)?, start);
+                        let mut locals = vec![];
+                        for _ in 0..body.vec_len()? {
+                            let count = body.u32()?;
+                            let ty = body.val_type()?;
+                            locals.push((count, ty));
+                        }
+                        info.bodies.push(FunctionBody {
+                            locals,
+                            code: body.offset()..start + size,
+                        });
+                    }
+                }
+                _ => continue,
+            }
+            if !reader.is_empty() {
+                return Err(binary_error(
+                    0x26,
+                    format!(
+                        "section {} at offset {:#x} has trailing bytes",
+                        section.id, section.range.start
+                    ),
+                ));
+            }
+        }
+        Ok(info)
+    }
+
+    fn func_type_at(&self, reader: &mut Reader) -> WasmEdgeResult<FuncType> {
+        let offset = reader.offset();
+        let idx = reader.u32()?;
+        match self.types.get(idx as usize) {
+            Some(ty) => Ok(ty.clone()),
+            None => Err(binary_error(
+                0x44,
+                format!(
+                    "invalid function type index {} at offset {:#x}",
+                    idx, offset
+                ),
+            )),
+        }
+    }
+
+    /// Reads an import descriptor, adding it to the index spaces, or resolves an export index.
+    fn extern_desc(&mut self, reader: &mut Reader, import: bool) -> WasmEdgeResult<ExternType> {
+        let offset = reader.offset();
+        let kind = reader.u8()?;
+        if import {
+            let ty = match kind {
+                0x00 => {
+                    let ty = self.func_type_at(reader)?;
+                    self.funcs.push(ty.clone());
+                    ExternType::Function(ty)
+                }
+                0x01 => {
+                    let ty = reader.table_type()?;
+                    self.tables.push(ty.clone());
+                    ExternType::Table(ty)
+                }
+                0x02 => {
+                    let ty = reader.memory_type()?;
+                    self.memories.push(ty.clone());
+                    ExternType::Memory(ty)
+                }
+                0x03 => {
+                    let ty = reader.global_type()?;
+                    self.globals.push(ty.clone());
+                    ExternType::Global(ty)
+                }
+                _ => {
+                    return Err(binary_error(
+                        0x2C,
+                        format!(
+                            "malformed import kind {:#04x} at offset {:#x}",
+                            kind, offset
+                        ),
+                    ))
+                }
+            };
+            return Ok(ty);
+        }
+
+        let idx = reader.u32()? as usize;
+        let ty = match kind {
+            0x00 => self.funcs.get(idx).cloned().map(ExternType::Function),
+            0x01 => self.tables.get(idx).cloned().map(ExternType::Table),
+            0x02 => self.memories.get(idx).cloned().map(ExternType::Memory),
+            0x03 => self.globals.get(idx).cloned().map(ExternType::Global),
+            _ => {
+                return Err(binary_error(
+                    0x2D,
+                    format!(
+                        "malformed export kind {:#04x} at offset {:#x}",
+                        kind, offset
+                    ),
+                ))
+            }
+        };
+        ty.ok_or_else(|| {
+            binary_error(
+                0x45 + u32::from(kind),
+                format!("invalid export index {} at offset {:#x}", idx, offset),
+            )
+        })
+    }
+}
+
+/// Returns the custom sections of a module, in the order they appear.
+pub(crate) fn custom_sections(bytes: &[u8]) -> WasmEdgeResult<Vec<CustomSection>> {
+    let mut customs = vec![];
+    for section in sections(bytes)?.into_iter() {
+        if section.id != SECTION_CUSTOM {
+            continue;
+        }
+        let mut reader = Reader::new(&bytes[section.range.clone()], section.range.start);
+        let name = reader.name()?;
+        customs.push(CustomSection {
+            name,
+            data: reader.rest().to_vec(),
+        });
+    }
+    Ok(customs)
+}
+
+fn name_map(reader: &mut Reader) -> WasmEdgeResult<BTreeMap<u32, String>> {
+    let mut names = BTreeMap::new();
+    for _ in 0..reader.vec_len()? {
+        let idx = reader.u32()?;
+        let name = reader.name()?;
+        names.insert(idx, name);
+    }
+    Ok(names)
+}
+
+/// Decodes the payload of the `name` custom section.
+///
+/// Subsections other than the module, function and local names are skipped.
+pub(crate) fn name_section(data: &[u8]) -> WasmEdgeResult<NameSection> {
+    let mut names = NameSection::default();
+    let mut reader = Reader::new(data, 0);
+    while !reader.is_empty() {
+        let id = reader.u8()?;
+        let len = reader.vec_len()?;
+        let offset = reader.offset();
+        let mut sub = Reader::new(reader.bytes(len)?, offset);
+        match id {
+            0 => names.module = Some(sub.name()?),
+            1 => names.functions = name_map(&mut sub)?,
+            2 => {
+                for _ in 0..sub.vec_len()? {
+                    let func = sub.u32()?;
+                    let locals = name_map(&mut sub)?;
+                    names.locals.insert(func, locals);
+                }
+            }
+            _ => continue,
+        }
+        if !sub.is_empty() {
+            return Err(binary_error(
+                0x26,
+                format!(
+                    "name subsection {} at offset {:#x} has trailing bytes",
+                    id, offset
+                ),
+            ));
+        }
+    }
+    Ok(names)
+}
+
+/// Decodes the payload of the `producers` custom section.
+pub(crate) fn producers_section(data: &[u8]) -> WasmEdgeResult<Vec<ProducerField>> {
+    let mut fields = vec![];
+    let mut reader = Reader::new(data, 0);
+    for _ in 0..reader.vec_len()? {
+        let name = reader.name()?;
+        let mut values = vec![];
+        for _ in 0..reader.vec_len()? {
+            let value = reader.name()?;
+            let version = reader.name()?;
+            values.push((value, version));
+        }
+        fields.push(ProducerField { name, values });
+    }
+    Ok(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_binary_leb() {
+        let mut reader = Reader::new(&[0xE5, 0x8E, 0x26], 0);
+        assert_eq!(reader.u32().unwrap(), 624485);
+        let mut reader = Reader::new(&[0x7F], 0);
+        assert_eq!(reader.s32().unwrap(), -1);
+        let mut reader = Reader::new(&[0xC0, 0xBB, 0x78], 0);
+        assert_eq!(reader.s64().unwrap(), -123456);
+
+        // Too long, too large and truncated
+        let mut reader = Reader::new(&[0x80, 0x80, 0x80, 0x80, 0x80, 0x00], 0);
+        assert_eq!(reader.u32().unwrap_err().code(), Some(0x36));
+        let mut reader = Reader::new(&[0xFF, 0xFF, 0xFF, 0xFF, 0x1F], 0);
+        assert_eq!(reader.u32().unwrap_err().code(), Some(0x35));
+        let mut reader = Reader::new(&[0x80], 0);
+        assert_eq!(reader.u32().unwrap_err().code(), Some(0x22));
+    }
+
+    #[test]
+    fn test_binary_sections() {
+        assert_eq!(sections(&[0x00, 0x61]).unwrap_err().code(), Some(0x23));
+        assert_eq!(
+            sections(&[0x00, 0x61, 0x73, 0x6D, 0x02, 0x00, 0x00, 0x00])
+                .unwrap_err()
+                .code(),
+            Some(0x24)
+        );
+        let bytes = [
+            0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00, 0x00, 0x02, 0x01, 0x61,
+        ];
+        assert_eq!(
+            sections(&bytes).unwrap(),
+            vec![Section {
+                id: 0,
+                range: 10..12
+            }]
+        );
+        // A section that claims more bytes than the module has
+        let bytes = [
+            0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00, 0x01, 0x05, 0x00,
+        ];
+        assert_eq!(sections(&bytes).unwrap_err().code(), Some(0x26));
+    }
+
+    #[test]
+    fn test_binary_name_section() {
+        let data = [
+            /* module name: "m" */
+            0x00, 0x02, 0x01, 0x6D, /* function names: 0 -> "f", 2 -> "g" */
+            0x01, 0x07, 0x02, 0x00, 0x01, 0x66, 0x02, 0x01, 0x67,
+            /* local names: func 0 -> {1 -> "x"} */
+            0x02, 0x06, 0x01, 0x00, 0x01, 0x01, 0x01, 0x78, /* unknown subsection */
+            0x07, 0x01, 0x00,
+        ];
+        let names = name_section(&data).unwrap();
+        assert_eq!(names.module.as_deref(), Some("m"));
+        assert_eq!(names.functions.get(&0).map(String::as_str), Some("f"));
+        assert_eq!(names.functions.get(&2).map(String::as_str), Some("g"));
+        assert_eq!(names.local(0, 1), Some("x"));
+        assert_eq!(names.local(0, 0), None);
+
+        // A subsection longer than the section
+        assert!(name_section(&[0x00, 0x05, 0x01]).is_err());
+    }
+}
@@ -0,0 +1,285 @@
+//! A cache of parsed and validated modules, keyed by their content.
+
+use crate::context::{
+    ast::ASTModuleContext, configure::ConfigureContext, loader::LoaderContext, validator::Validator,
+};
+use crate::error::{WasmEdgeError, WasmEdgeResult};
+use crate::types::Proposal;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::rc::Rc;
+
+/// Identifies a module by the SHA-256 of its bytes and the proposals it was validated with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    digest: [u8; 32],
+    /// The enabled proposals, one bit per variant in `Proposal::all()` order.
+    proposals: u32,
+}
+impl CacheKey {
+    pub fn new(conf: &ConfigureContext, buf: &[u8]) -> Self {
+        let proposals = Proposal::all()
+            .enumerate()
+            .filter(|(_, prop)| conf.has_proposal(*prop))
+            .fold(0, |bits, (i, _)| bits | 1 << i);
+        CacheKey {
+            digest: Sha256::digest(buf).into(),
+            proposals,
+        }
+    }
+
+    /// Returns the SHA-256 of the module bytes.
+    pub fn digest(&self) -> &[u8; 32] {
+        &self.digest
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// The number of modules dropped to stay within the capacity.
+    pub evictions: u64,
+}
+
+/// Holds up to `capacity` validated modules and evicts the least recently used one when full.
+///
+/// The returned modules can be passed to `VMContext::load_from_ast`,
+/// `InterpreterContext::instantiate` and the other `ASTModuleContext` consumers, which do not
+/// take ownership of them.
+///
+/// ```ignore
+/// let mut cache = ModuleCache::new(16);
+/// let ast_mod = cache.load(&conf, &wasm)?;
+/// vm.load_from_ast(&ast_mod)?;
+/// ```
+pub struct ModuleCache {
+    capacity: usize,
+    entries: HashMap<CacheKey, Rc<ASTModuleContext>>,
+    /// The keys from the least to the most recently used.
+    order: VecDeque<CacheKey>,
+    stats: CacheStats,
+}
+impl ModuleCache {
+    /// Creates a cache holding up to `capacity` modules. A capacity of 0 disables caching.
+    pub fn new(capacity: usize) -> Self {
+        ModuleCache {
+            capacity,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            stats: CacheStats::default(),
+        }
+    }
+
+    /// Returns the cached module for `buf`, or parses and validates it with `conf` and caches it.
+    pub fn load(
+        &mut self,
+        conf: &ConfigureContext,
+        buf: &[u8],
+    ) -> WasmEdgeResult<Rc<ASTModuleContext>> {
+        let key = CacheKey::new(conf, buf);
+        if let Some(ast_mod) = self.entries.get(&key) {
+            let ast_mod = ast_mod.clone();
+            self.stats.hits += 1;
+            self.touch(key);
+            return Ok(ast_mod);
+        }
+        self.stats.misses += 1;
+
+        let ast_mod = Rc::new(Self::parse_and_validate(conf, buf)?);
+        if self.capacity > 0 {
+            if self.entries.len() == self.capacity {
+                if let Some(oldest) = self.order.pop_front() {
+                    self.entries.remove(&oldest);
+                    self.stats.evictions += 1;
+                }
+            }
+            self.entries.insert(key, ast_mod.clone());
+            self.order.push_back(key);
+        }
+        Ok(ast_mod)
+    }
+
+    /// Reads the file at `path` and loads it through the cache.
+    pub fn load_file<P: AsRef<Path>>(
+        &mut self,
+        conf: &ConfigureContext,
+        path: P,
+    ) -> WasmEdgeResult<Rc<ASTModuleContext>> {
+        match std::fs::read(path.as_ref()) {
+            Ok(buf) => self.load(conf, &buf),
+            Err(e) => Err(WasmEdgeError::Load {
+                code: 0x20,
+                message: format!(
+                    "Loading failed: failed to read {}: {}",
+                    path.as_ref().display(),
+                    e
+                ),
+            }),
+        }
+    }
+
+    pub fn contains(&self, key: &CacheKey) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats = CacheStats::default();
+    }
+
+    /// Drops all cached modules. Modules still held by callers stay alive until released.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+
+    fn touch(&mut self, key: CacheKey) {
+        if let Some(pos) = self.order.iter().position(|k| *k == key) {
+            self.order.remove(pos);
+        }
+        self.order.push_back(key);
+    }
+
+    fn parse_and_validate(conf: &ConfigureContext, buf: &[u8]) -> WasmEdgeResult<ASTModuleContext> {
+        let mut loader = match LoaderContext::create(conf) {
+            Some(loader) => loader,
+            None => {
+                return Err(WasmEdgeError::from(String::from(
+                    "Failed to create a loader",
+                )))
+            }
+        };
+        let validator = match Validator::create(conf) {
+            Some(validator) => validator,
+            None => {
+                return Err(WasmEdgeError::from(String::from(
+                    "Failed to create a validator",
+                )))
+            }
+        };
+        let mut ast_mod = ASTModuleContext::default();
+        loader.parse_from_buffer(&mut ast_mod, buf)?;
+        validator.validate(&ast_mod)?;
+        Ok(ast_mod)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{interpreter::InterpreterContext, store::StoreContext, vm::VMContext};
+    use crate::fixtures::ADD_WASM;
+    use crate::value::Val;
+
+    const TPATH: &str = "/root/workspace/wasmedge-ml/wasmedge/tests/data/test.wasm";
+
+    #[test]
+    fn test_cache_hit_miss() {
+        let conf = ConfigureContext::create();
+        let mut cache = ModuleCache::new(4);
+        assert!(cache.is_empty());
+
+        let first = cache.load(&conf, ADD_WASM).unwrap();
+        let second = cache.load(&conf, ADD_WASM).unwrap();
+        assert!(Rc::ptr_eq(&first, &second));
+        assert_eq!(cache.len(), 1);
+        assert!(cache.contains(&CacheKey::new(&conf, ADD_WASM)));
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 1,
+                evictions: 0
+            }
+        );
+
+        // The same bytes under other proposals are a different entry
+        let mut conf_ref = ConfigureContext::create();
+        conf_ref.add_proposal(Proposal::ReferenceTypes);
+        assert_ne!(
+            CacheKey::new(&conf, ADD_WASM),
+            CacheKey::new(&conf_ref, ADD_WASM)
+        );
+        let third = cache.load(&conf_ref, ADD_WASM).unwrap();
+        assert!(!Rc::ptr_eq(&first, &third));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.stats().misses, 2);
+
+        // Invalid modules are reported and not cached
+        assert!(cache.load(&conf, &ADD_WASM[..20]).is_err());
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.stats().misses, 3);
+
+        cache.reset_stats();
+        assert_eq!(cache.stats(), CacheStats::default());
+        cache.clear();
+        assert!(cache.is_empty());
+        assert!(cache.load_file(&conf, "file").is_err());
+    }
+
+    #[test]
+    fn test_cache_lru() {
+        let conf = ConfigureContext::create();
+        let mut conf_ref = ConfigureContext::create();
+        conf_ref.add_proposal(Proposal::ReferenceTypes);
+        let mut cache = ModuleCache::new(2);
+
+        assert!(cache.load(&conf, ADD_WASM).is_ok());
+        assert!(cache.load_file(&conf_ref, TPATH).is_ok());
+        // Use the first module again so that the second one is the least recently used
+        assert!(cache.load(&conf, ADD_WASM).is_ok());
+        assert!(cache.load(&conf_ref, ADD_WASM).is_ok());
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.stats().evictions, 1);
+        assert!(cache.contains(&CacheKey::new(&conf, ADD_WASM)));
+        let buf = std::fs::read(TPATH).unwrap();
+        assert!(!cache.contains(&CacheKey::new(&conf_ref, &buf)));
+
+        // A capacity of 0 never caches
+        let mut cache = ModuleCache::new(0);
+        assert!(cache.load(&conf, ADD_WASM).is_ok());
+        assert!(cache.load(&conf, ADD_WASM).is_ok());
+        assert!(cache.is_empty());
+        assert_eq!(cache.stats().misses, 2);
+    }
+
+    #[test]
+    fn test_cache_vm_interpreter() {
+        let conf = ConfigureContext::create();
+        let mut cache = ModuleCache::new(4);
+        let params = [Val::I32(1), Val::I32(2)];
+
+        for _ in 0..3 {
+            let ast_mod = cache.load(&conf, ADD_WASM).unwrap();
+            let mut vm = VMContext::create(Some(&conf), None).unwrap();
+            assert!(vm.load_from_ast(&ast_mod).is_ok());
+            assert!(vm.validate().is_ok());
+            assert!(vm.instantiate().is_ok());
+            assert_eq!(vm.execute("add", &params, 1).unwrap(), vec![Val::I32(3)]);
+        }
+
+        let ast_mod = cache.load(&conf, ADD_WASM).unwrap();
+        let mut interp = InterpreterContext::create(Some(&conf), None).unwrap();
+        let mut store = StoreContext::create();
+        assert!(interp.instantiate(&mut store, &ast_mod).is_ok());
+        assert_eq!(cache.stats().hits, 3);
+        assert_eq!(cache.stats().misses, 1);
+    }
+}
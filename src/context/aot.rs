@@ -1,30 +1,180 @@
-use crate::context::configure::ConfigureContext;
-use crate::error::WasmEdgeResult;
-use crate::utils::{check, path_to_cstring};
-use std::path::Path;
+use crate::cache::CacheStats;
+use crate::context::configure::{Configuration, ConfigureContext};
+use crate::error::{WasmEdgeError, WasmEdgeResult};
+use crate::system::version;
+use crate::types::{CompilerOptimizationLevel, Proposal};
+use crate::utils::{buffer_len, check, path_to_cstring, TempDir};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::convert::TryFrom;
+use std::env::consts::DLL_SUFFIX;
+use std::fmt::{self, Write as _};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
 use wasmedge_sys::ffi as we_ffi;
 
 pub struct Compiler {
     pub(crate) raw: *mut we_ffi::WasmEdge_CompilerContext,
+    /// The settings the compiler was created with, recorded in its artifacts.
+    config: Configuration,
+    embed_wasm: bool,
 }
 impl Compiler {
     pub fn create(conf: ConfigureContext) -> Option<Self> {
         let raw = unsafe { we_ffi::WasmEdge_CompilerCreate(conf.raw) };
         match raw.is_null() {
             true => None,
-            false => Some(Compiler { raw }),
+            false => Some(Compiler {
+                raw,
+                config: Configuration::from(&conf),
+                embed_wasm: true,
+            }),
         }
     }
 
+    /// Sets whether artifacts embed the original module, which `ArtifactInfo` returns so that it
+    /// can be interpreted when the artifact is incompatible. On by default.
+    pub fn embed_wasm(&mut self, embed: bool) -> &mut Self {
+        self.embed_wasm = embed;
+        self
+    }
+
+    /// Compiles the module at `in_path` into a native artifact at `out_path`.
+    ///
+    /// The module is read once, and WasmEdge compiles a private copy of those bytes. The
+    /// artifact carries the metadata read by `ArtifactInfo`, appended to it on ELF targets and
+    /// in a `.meta` sidecar file next to it on macOS, iOS and Windows.
     pub fn compile<P: AsRef<Path>>(&mut self, in_path: P, out_path: P) -> WasmEdgeResult<()> {
-        let in_path = path_to_cstring(in_path.as_ref())?;
-        let out_path = path_to_cstring(out_path.as_ref())?;
+        let wasm = match fs::read(in_path.as_ref()) {
+            Ok(wasm) => wasm,
+            Err(e) => {
+                return Err(WasmEdgeError::Load {
+                    code: 0x20,
+                    message: format!(
+                        "Loading failed: failed to read {}: {}",
+                        in_path.as_ref().display(),
+                        e
+                    ),
+                })
+            }
+        };
+        // The artifact is compiled next to `out_path`, so it can be renamed into place
+        let out_path = out_path.as_ref();
+        let parent = match out_path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        let dir = TempDir::new_in(parent)?;
+        let artifact = self.compile_in(&wasm, &dir)?;
+        store_artifact(&artifact, out_path)
+    }
+
+    /// Compiles `wasm` into an artifact in `dir` and returns its path.
+    ///
+    /// The output file is created beforehand, so WasmEdge only writes to a file the crate owns.
+    fn compile_in(&mut self, wasm: &[u8], dir: &TempDir) -> WasmEdgeResult<PathBuf> {
+        buffer_len(wasm.len())?;
+        let in_path = dir.create_file("module.wasm", wasm)?;
+        let out_path = dir.create_file(&format!("module{}", DLL_SUFFIX), &[])?;
+        let c_in_path = path_to_cstring(&in_path)?;
+        let c_out_path = path_to_cstring(&out_path)?;
         unsafe {
             check(we_ffi::WasmEdge_CompilerCompile(
                 self.raw,
-                in_path.as_ptr(),
-                out_path.as_ptr(),
-            ))
+                c_in_path.as_ptr(),
+                c_out_path.as_ptr(),
+            ))?;
+        }
+
+        let trailer = self.metadata_trailer(wasm)?;
+        match APPEND_METADATA {
+            true => OpenOptions::new()
+                .append(true)
+                .open(&out_path)
+                .and_then(|mut file| file.write_all(&trailer))
+                .map_err(|e| {
+                    WasmEdgeError::from(format!(
+                        "Failed to write the metadata of '{}': {}",
+                        out_path.display(),
+                        e
+                    ))
+                })?,
+            false => {
+                dir.create_file(&format!("module{}.meta", DLL_SUFFIX), &trailer)?;
+            }
+        }
+        Ok(out_path)
+    }
+
+    /// Returns the metadata, preceded by the module if it is embedded, and the footer.
+    fn metadata_trailer(&self, wasm: &[u8]) -> WasmEdgeResult<Vec<u8>> {
+        let metadata = Metadata {
+            runtime_version: version(),
+            opt_level: self.config.opt_level as u32,
+            proposals: self
+                .config
+                .proposals
+                .iter()
+                .map(|p| p.to_string())
+                .collect(),
+            dump_ir: self.config.dump_ir,
+            instruction_counting: self.config.instruction_counting,
+            cost_measuring: self.config.cost_measuring,
+        };
+        let metadata = match toml::to_string(&metadata) {
+            Ok(metadata) => metadata,
+            Err(e) => return Err(WasmEdgeError::from(e.to_string())),
+        };
+
+        let mut trailer = match self.embed_wasm {
+            true => wasm.to_vec(),
+            false => vec![],
+        };
+        let wasm_len = trailer.len() as u64;
+        trailer.extend_from_slice(metadata.as_bytes());
+        trailer.extend_from_slice(&wasm_len.to_le_bytes());
+        trailer.extend_from_slice(&(metadata.len() as u64).to_le_bytes());
+        trailer.extend_from_slice(METADATA_MAGIC);
+        Ok(trailer)
+    }
+
+    /// Compiles a module in memory and returns the native artifact.
+    ///
+    /// The artifact can be loaded with `LoaderContext::parse_from_aot_buffer` or
+    /// `VMContext::load_from_aot_buffer`.
+    pub fn compile_buffer(&mut self, wasm: &[u8]) -> WasmEdgeResult<Vec<u8>> {
+        let mut artifact = vec![];
+        self.compile_to_writer(wasm, &mut artifact)?;
+        Ok(artifact)
+    }
+
+    /// Compiles a module in memory and writes the native artifact to `writer`, e.g. a blob store
+    /// upload.
+    ///
+    /// Nothing is written if the compilation fails. On macOS, iOS and Windows, where the
+    /// metadata is kept in a sidecar file, the artifact carries none and loads as
+    /// `ArtifactLoad::Unverified`.
+    pub fn compile_to_writer<W: Write>(
+        &mut self,
+        wasm: &[u8],
+        writer: &mut W,
+    ) -> WasmEdgeResult<()> {
+        // WasmEdge only compiles from and to files, so the buffers go through temporary ones
+        let dir = TempDir::new()?;
+        let artifact = self.compile_in(wasm, &dir)?;
+
+        let copy = File::open(&artifact).and_then(|mut file| io::copy(&mut file, writer));
+        match copy {
+            Ok(_) => Ok(()),
+            Err(e) => Err(WasmEdgeError::from(format!(
+                "Failed to write the compiled artifact: {}",
+                e
+            ))),
         }
     }
 }
@@ -36,10 +186,738 @@ impl Drop for Compiler {
     }
 }
 
+/// Finds the artifacts of a `CompilationCache`, e.g. for `VMContext::set_compilation_cache`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ArtifactLocator {
+    dir: PathBuf,
+    /// Hash of everything besides the module that the artifact depends on.
+    config: String,
+}
+impl ArtifactLocator {
+    /// Returns the path the artifact of `wasm` is stored at, whether or not it exists.
+    pub(crate) fn path(&self, wasm: &[u8]) -> PathBuf {
+        let name = format!(
+            "{}-{}{}",
+            hex(&Sha256::digest(wasm)),
+            self.config,
+            DLL_SUFFIX
+        );
+        self.dir.join(name)
+    }
+
+    /// Returns the path of the artifact of `wasm` if it has been compiled.
+    ///
+    /// A hit refreshes the modification time of the artifact, so eviction removes the least
+    /// recently used artifacts first.
+    pub(crate) fn find(&self, wasm: &[u8]) -> Option<PathBuf> {
+        let path = self.path(wasm);
+        if !path.is_file() {
+            return None;
+        }
+        // Best effort: an artifact that cannot be touched is only evicted earlier
+        let _ = OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(std::time::SystemTime::now()));
+        Some(path)
+    }
+}
+
+/// Whether `Compiler` appends the metadata to the artifact itself.
+///
+/// ELF loaders ignore data past what the program headers map, so on ELF targets the metadata
+/// travels inside the artifact. Trailing data breaks Mach-O code signatures and PE loaders do not
+/// promise to ignore it, so on macOS, iOS and Windows the metadata is written to a sidecar file
+/// next to the artifact instead, see `metadata_path`.
+const APPEND_METADATA: bool = cfg!(not(any(target_os = "macos", target_os = "ios", windows)));
+
+/// Returns the path of the sidecar file holding the metadata of the artifact at `path`, used
+/// where `APPEND_METADATA` is false.
+fn metadata_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".meta");
+    path.with_file_name(name)
+}
+
+/// Reads the trailer appended to the artifact at `path`, without the native code before it, or
+/// nothing if the artifact has none. The result can be passed to `ArtifactInfo::from_bytes`.
+fn read_trailer(path: &Path) -> io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    if len < FOOTER_LEN as u64 {
+        return Ok(vec![]);
+    }
+    let mut footer = [0; FOOTER_LEN];
+    file.seek(SeekFrom::Start(len - FOOTER_LEN as u64))?;
+    file.read_exact(&mut footer)?;
+    if !footer.ends_with(METADATA_MAGIC) {
+        return Ok(vec![]);
+    }
+    let wasm_len = u64::from_le_bytes(<[u8; 8]>::try_from(&footer[..8]).unwrap());
+    let metadata_len = u64::from_le_bytes(<[u8; 8]>::try_from(&footer[8..16]).unwrap());
+    let trailer_len = wasm_len.saturating_add(metadata_len);
+    if trailer_len > len - FOOTER_LEN as u64 {
+        // `from_bytes` reports the lengths as malformed from the footer alone
+        return Ok(footer.to_vec());
+    }
+    let mut trailer = vec![0; trailer_len as usize + FOOTER_LEN];
+    file.seek(SeekFrom::Start(len - FOOTER_LEN as u64 - trailer_len))?;
+    file.read_exact(&mut trailer)?;
+    Ok(trailer)
+}
+
+/// Moves a compiled artifact, and its sidecar metadata if any, from `from` to `to`.
+fn store_artifact(from: &Path, to: &Path) -> WasmEdgeResult<()> {
+    let mut result = fs::rename(from, to);
+    if !APPEND_METADATA {
+        result = result.and_then(|_| fs::rename(metadata_path(from), metadata_path(to)));
+    }
+    result.map_err(|e| {
+        WasmEdgeError::from(format!(
+            "Failed to store the artifact '{}': {}",
+            to.display(),
+            e
+        ))
+    })
+}
+
+/// Removes an artifact and its sidecar metadata if any, returning whether the artifact was
+/// removed.
+fn remove_artifact(path: &Path) -> bool {
+    if !APPEND_METADATA {
+        let _ = fs::remove_file(metadata_path(path));
+    }
+    fs::remove_file(path).is_ok()
+}
+
+/// Whether `path` is named like an artifact of any `CompilationCache`.
+fn is_artifact(path: &Path) -> bool {
+    let name = match path.file_name().and_then(|name| name.to_str()) {
+        Some(name) => name,
+        None => return false,
+    };
+    let stem = match name.strip_suffix(DLL_SUFFIX) {
+        Some(stem) => stem,
+        None => return false,
+    };
+    let mut parts = stem.split('-');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(digest), Some(config), None) => {
+            digest.len() == 64
+                && config.len() == 16
+                && stem.bytes().all(|b| b == b'-' || b.is_ascii_hexdigit())
+        }
+        _ => false,
+    }
+}
+
+/// Keeps compiled artifacts in a directory, so that each module is compiled once per runtime
+/// version and compiler configuration.
+///
+/// An artifact is keyed by the SHA-256 of the module and by the WasmEdge version, optimization
+/// level, enabled proposals and compiler flags of the `ConfigureContext`. Artifacts are written
+/// to a temporary file and renamed into place, so concurrent processes may share a directory and
+/// never see a partial artifact.
+///
+/// ```ignore
+/// let mut cache = CompilationCache::create("/var/cache/wasmedge", conf)?;
+/// cache.max_size(1 << 30);
+/// let artifact = cache.compile_file("app.wasm")?;
+/// vm.load_from_file(artifact)?;
+/// ```
+pub struct CompilationCache {
+    compiler: Compiler,
+    locator: ArtifactLocator,
+    /// The total size of the artifacts in bytes above which the oldest ones are removed.
+    max_size: Option<u64>,
+    stats: CacheStats,
+}
+impl CompilationCache {
+    /// Creates a cache in `dir`, which is created if missing, compiling with `conf`.
+    pub fn create<P: AsRef<Path>>(dir: P, conf: ConfigureContext) -> WasmEdgeResult<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        if let Err(e) = fs::create_dir_all(&dir) {
+            return Err(WasmEdgeError::from(format!(
+                "Failed to create the cache directory '{}': {}",
+                dir.display(),
+                e
+            )));
+        }
+        let config = config_hash(&conf);
+        let compiler = match Compiler::create(conf) {
+            Some(compiler) => compiler,
+            None => {
+                return Err(WasmEdgeError::from(String::from(
+                    "Failed to create a compiler",
+                )))
+            }
+        };
+        Ok(CompilationCache {
+            compiler,
+            locator: ArtifactLocator { dir, config },
+            max_size: None,
+            stats: CacheStats::default(),
+        })
+    }
+
+    /// Removes the least recently used artifacts whenever their total size exceeds `bytes`.
+    pub fn max_size(&mut self, bytes: u64) -> &mut Self {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.locator.dir
+    }
+
+    /// Returns the path of the artifact of `wasm`, compiling it first if it is not cached.
+    pub fn compile(&mut self, wasm: &[u8]) -> WasmEdgeResult<PathBuf> {
+        if let Some(path) = self.locator.find(wasm) {
+            self.stats.hits += 1;
+            return Ok(path);
+        }
+        self.stats.misses += 1;
+
+        // The temporary directory is in the cache directory, so the artifact can be renamed into
+        // place atomically
+        let dir = TempDir::new_in(self.dir())?;
+        let artifact = self.compiler.compile_in(wasm, &dir)?;
+        let path = self.locator.path(wasm);
+        store_artifact(&artifact, &path)?;
+        self.evict(&path);
+        Ok(path)
+    }
+
+    /// Reads the module at `path` and compiles it through the cache.
+    pub fn compile_file<P: AsRef<Path>>(&mut self, path: P) -> WasmEdgeResult<PathBuf> {
+        match fs::read(path.as_ref()) {
+            Ok(wasm) => self.compile(&wasm),
+            Err(e) => Err(WasmEdgeError::Load {
+                code: 0x20,
+                message: format!(
+                    "Loading failed: failed to read {}: {}",
+                    path.as_ref().display(),
+                    e
+                ),
+            }),
+        }
+    }
+
+    /// Returns the path of the artifact of `wasm` if it is cached, without compiling it.
+    pub fn get(&self, wasm: &[u8]) -> Option<PathBuf> {
+        self.locator.find(wasm)
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats = CacheStats::default();
+    }
+
+    /// Removes all artifacts from the directory, including those of other configurations.
+    pub fn clear(&mut self) -> WasmEdgeResult<()> {
+        for (path, _) in self.artifacts()? {
+            remove_artifact(&path);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn locator(&self) -> &ArtifactLocator {
+        &self.locator
+    }
+
+    /// Returns the artifacts in the directory with their sizes, from the least to the most
+    /// recently used.
+    fn artifacts(&self) -> WasmEdgeResult<Vec<(PathBuf, u64)>> {
+        let entries = match fs::read_dir(self.dir()) {
+            Ok(entries) => entries,
+            Err(e) => {
+                return Err(WasmEdgeError::from(format!(
+                    "Failed to read the cache directory '{}': {}",
+                    self.dir().display(),
+                    e
+                )))
+            }
+        };
+        let mut artifacts = vec![];
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !is_artifact(&path) {
+                continue;
+            }
+            if let Ok(meta) = entry.metadata() {
+                let modified = meta.modified().unwrap_or(std::time::UNIX_EPOCH);
+                artifacts.push((modified, path, meta.len()));
+            }
+        }
+        artifacts.sort();
+        Ok(artifacts
+            .into_iter()
+            .map(|(_, path, len)| (path, len))
+            .collect())
+    }
+
+    /// Removes the least recently used artifacts, except `keep`, until the cache fits in
+    /// `max_size`.
+    fn evict(&mut self, keep: &Path) {
+        let max_size = match self.max_size {
+            Some(max_size) => max_size,
+            None => return,
+        };
+        // Eviction is best effort: a failure only leaves the cache larger than asked
+        let artifacts = match self.artifacts() {
+            Ok(artifacts) => artifacts,
+            Err(_) => return,
+        };
+        let mut total: u64 = artifacts.iter().map(|(_, len)| len).sum();
+        for (path, len) in artifacts {
+            if total <= max_size {
+                break;
+            }
+            if path != keep && remove_artifact(&path) {
+                total -= len;
+                self.stats.evictions += 1;
+            }
+        }
+    }
+}
+
+/// Stops a `BatchCompiler` from starting more compilations. Clones share the same state.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+impl CancelToken {
+    pub fn new() -> Self {
+        CancelToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// How the compilation of one module of a batch ended.
+#[derive(Debug)]
+pub enum CompileOutcome {
+    Compiled,
+    Failed(WasmEdgeError),
+    /// The batch was cancelled before the module was compiled.
+    Cancelled,
+}
+
+/// The result of compiling one module of a batch.
+#[derive(Debug)]
+pub struct CompileReport {
+    /// The position of the module in the batch.
+    pub index: usize,
+    pub input: PathBuf,
+    pub output: PathBuf,
+    /// The time spent compiling, zero for cancelled modules.
+    pub elapsed: Duration,
+    pub outcome: CompileOutcome,
+}
+impl CompileReport {
+    pub fn is_compiled(&self) -> bool {
+        matches!(self.outcome, CompileOutcome::Compiled)
+    }
+}
+
+/// Compiles many modules in parallel, with one `Compiler` per worker thread.
+///
+/// ```ignore
+/// let mut batch = BatchCompiler::new(Configuration::from(&conf));
+/// batch.threads(8).cancel_token(token.clone());
+/// let reports = batch.compile(&jobs, |report| println!("{}: {:?}", report.input.display(), report.outcome));
+/// ```
+#[derive(Debug, Clone)]
+pub struct BatchCompiler {
+    config: Configuration,
+    threads: usize,
+    cancel: CancelToken,
+}
+impl BatchCompiler {
+    /// Creates a batch compiler using as many threads as the machine has cores.
+    pub fn new(config: Configuration) -> Self {
+        BatchCompiler {
+            config,
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            cancel: CancelToken::new(),
+        }
+    }
+
+    /// Sets the number of worker threads, at least one.
+    pub fn threads(&mut self, threads: usize) -> &mut Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Sets the token that cancels the batch.
+    ///
+    /// WasmEdge cannot interrupt a compilation, so the modules being compiled when the token is
+    /// cancelled still finish; the remaining ones are reported as `CompileOutcome::Cancelled`.
+    pub fn cancel_token(&mut self, token: CancelToken) -> &mut Self {
+        self.cancel = token;
+        self
+    }
+
+    /// Compiles each `(input, output)` pair of paths and returns the reports in the order of
+    /// `jobs`.
+    ///
+    /// `progress` is called on the calling thread with each report as soon as it is ready.
+    pub fn compile<P, Q, F>(&self, jobs: &[(P, Q)], mut progress: F) -> Vec<CompileReport>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+        F: FnMut(&CompileReport),
+    {
+        let jobs: Arc<Vec<(PathBuf, PathBuf)>> = Arc::new(
+            jobs.iter()
+                .map(|(input, output)| {
+                    (input.as_ref().to_path_buf(), output.as_ref().to_path_buf())
+                })
+                .collect(),
+        );
+        let next = Arc::new(AtomicUsize::new(0));
+        let (sender, receiver) = mpsc::channel();
+        let workers: Vec<_> = (0..self.threads.min(jobs.len()))
+            .map(|_| {
+                let jobs = jobs.clone();
+                let next = next.clone();
+                let sender = sender.clone();
+                let config = self.config.clone();
+                let cancel = self.cancel.clone();
+                thread::spawn(move || Self::work(&jobs, &next, &config, &cancel, &sender))
+            })
+            .collect();
+        drop(sender);
+
+        let mut reports: Vec<Option<CompileReport>> = (0..jobs.len()).map(|_| None).collect();
+        for report in receiver {
+            progress(&report);
+            let index = report.index;
+            reports[index] = Some(report);
+        }
+        for worker in workers {
+            let _ = worker.join();
+        }
+        // A worker that panicked leaves its modules without a report
+        reports
+            .into_iter()
+            .zip(jobs.iter())
+            .enumerate()
+            .map(|(index, (report, (input, output)))| match report {
+                Some(report) => report,
+                None => CompileReport {
+                    index,
+                    input: input.clone(),
+                    output: output.clone(),
+                    elapsed: Duration::default(),
+                    outcome: CompileOutcome::Failed(WasmEdgeError::from(String::from(
+                        "The compiler thread panicked",
+                    ))),
+                },
+            })
+            .collect()
+    }
+
+    fn work(
+        jobs: &[(PathBuf, PathBuf)],
+        next: &AtomicUsize,
+        config: &Configuration,
+        cancel: &CancelToken,
+        sender: &mpsc::Sender<CompileReport>,
+    ) {
+        let mut compiler = Compiler::create(config.to_context());
+        loop {
+            let index = next.fetch_add(1, Ordering::SeqCst);
+            let (input, output) = match jobs.get(index) {
+                Some(job) => job,
+                None => return,
+            };
+            let start = Instant::now();
+            let outcome = match (&mut compiler, cancel.is_cancelled()) {
+                (_, true) => CompileOutcome::Cancelled,
+                (None, false) => CompileOutcome::Failed(WasmEdgeError::from(String::from(
+                    "Failed to create a compiler",
+                ))),
+                (Some(compiler), false) => match compiler.compile(input, output) {
+                    Ok(()) => CompileOutcome::Compiled,
+                    Err(e) => CompileOutcome::Failed(e),
+                },
+            };
+            let report = CompileReport {
+                index,
+                input: input.clone(),
+                output: output.clone(),
+                elapsed: start.elapsed(),
+                outcome,
+            };
+            if sender.send(report).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Ends the trailer that `Compiler` appends to its artifacts.
+const METADATA_MAGIC: &[u8; 8] = b"WEAOTMD1";
+
+/// The trailer layout after the native code is: the embedded module, the metadata in TOML, the
+/// lengths of both as little-endian `u64`s and `METADATA_MAGIC`.
+const FOOTER_LEN: usize = 8 + 8 + 8;
+
+const OPT_LEVELS: [CompilerOptimizationLevel; 6] = [
+    CompilerOptimizationLevel::WasmEdge_CompilerOptimizationLevel_O0,
+    CompilerOptimizationLevel::WasmEdge_CompilerOptimizationLevel_O1,
+    CompilerOptimizationLevel::WasmEdge_CompilerOptimizationLevel_O2,
+    CompilerOptimizationLevel::WasmEdge_CompilerOptimizationLevel_O3,
+    CompilerOptimizationLevel::WasmEdge_CompilerOptimizationLevel_Os,
+    CompilerOptimizationLevel::WasmEdge_CompilerOptimizationLevel_Oz,
+];
+
+#[derive(Serialize, Deserialize)]
+struct Metadata {
+    runtime_version: String,
+    opt_level: u32,
+    proposals: Vec<String>,
+    dump_ir: bool,
+    instruction_counting: bool,
+    cost_measuring: bool,
+}
+
+/// A reason an artifact cannot run as compiled in the current runtime and configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Incompatibility {
+    /// The artifact was compiled by another version of WasmEdge.
+    RuntimeVersion { artifact: String, runtime: String },
+    /// A proposal is enabled in the artifact but not in the configuration, or the other way
+    /// around.
+    Proposal { proposal: Proposal, compiled: bool },
+    /// A compiler flag that changes the generated code differs, e.g. `instruction_counting`.
+    CompilerFlag { flag: &'static str, compiled: bool },
+}
+impl fmt::Display for Incompatibility {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let with = |compiled: &bool| match compiled {
+            true => "with",
+            false => "without",
+        };
+        match self {
+            Incompatibility::RuntimeVersion { artifact, runtime } => write!(
+                f,
+                "compiled by WasmEdge {}, but the runtime is {}",
+                artifact, runtime
+            ),
+            Incompatibility::Proposal { proposal, compiled } => {
+                write!(f, "compiled {} the {} proposal", with(compiled), proposal)
+            }
+            Incompatibility::CompilerFlag { flag, compiled } => {
+                write!(f, "compiled {} {}", with(compiled), flag)
+            }
+        }
+    }
+}
+
+/// The metadata of an artifact produced by `Compiler`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtifactInfo {
+    pub runtime_version: String,
+    pub opt_level: CompilerOptimizationLevel,
+    pub proposals: Vec<Proposal>,
+    pub dump_ir: bool,
+    pub instruction_counting: bool,
+    pub cost_measuring: bool,
+    wasm: Option<Vec<u8>>,
+}
+impl ArtifactInfo {
+    /// Reads the metadata of the artifact at `path`, or returns `None` if it has none, e.g. if
+    /// it was compiled by the `wasmedgec` tool. The native code is not read.
+    ///
+    /// On macOS, iOS and Windows the metadata is read from the sidecar file `Compiler` writes
+    /// next to the artifact.
+    pub fn read<P: AsRef<Path>>(path: P) -> WasmEdgeResult<Option<Self>> {
+        let source = match APPEND_METADATA {
+            true => read_trailer(path.as_ref()),
+            false => match fs::read(metadata_path(path.as_ref())) {
+                // Without a sidecar file an existing artifact has no metadata
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                    fs::metadata(path.as_ref()).map(|_| vec![])
+                }
+                result => result,
+            },
+        };
+        match source {
+            Ok(artifact) => Self::from_bytes(&artifact),
+            Err(e) => Err(WasmEdgeError::Load {
+                code: 0x20,
+                message: format!(
+                    "Loading failed: failed to read {}: {}",
+                    path.as_ref().display(),
+                    e
+                ),
+            }),
+        }
+    }
+
+    /// Reads the metadata of an artifact in memory, see `read`.
+    pub fn from_bytes(artifact: &[u8]) -> WasmEdgeResult<Option<Self>> {
+        if artifact.len() < FOOTER_LEN || !artifact.ends_with(METADATA_MAGIC) {
+            return Ok(None);
+        }
+        let footer = &artifact[artifact.len() - FOOTER_LEN..];
+        let wasm_len = u64::from_le_bytes(<[u8; 8]>::try_from(&footer[..8]).unwrap());
+        let metadata_len = u64::from_le_bytes(<[u8; 8]>::try_from(&footer[8..16]).unwrap());
+        let malformed = |what: &str| {
+            Err(WasmEdgeError::from(format!(
+                "Malformed artifact metadata: {}",
+                what
+            )))
+        };
+        let trailer_len = (artifact.len() - FOOTER_LEN) as u64;
+        if wasm_len.saturating_add(metadata_len) > trailer_len {
+            return malformed("lengths exceed the artifact");
+        }
+        let metadata_start = trailer_len as usize - metadata_len as usize;
+        let wasm_start = metadata_start - wasm_len as usize;
+        let metadata = match std::str::from_utf8(&artifact[metadata_start..trailer_len as usize])
+            .ok()
+            .and_then(|s| toml::from_str::<Metadata>(s).ok())
+        {
+            Some(metadata) => metadata,
+            None => return malformed("invalid TOML"),
+        };
+
+        let opt_level = match OPT_LEVELS
+            .iter()
+            .find(|level| **level as u32 == metadata.opt_level)
+        {
+            Some(level) => *level,
+            None => return malformed("unknown optimization level"),
+        };
+        let mut proposals = vec![];
+        for name in metadata.proposals.iter() {
+            match name.parse() {
+                Ok(proposal) => proposals.push(proposal),
+                Err(_) => return malformed("unknown proposal"),
+            }
+        }
+        Ok(Some(ArtifactInfo {
+            runtime_version: metadata.runtime_version,
+            opt_level,
+            proposals,
+            dump_ir: metadata.dump_ir,
+            instruction_counting: metadata.instruction_counting,
+            cost_measuring: metadata.cost_measuring,
+            wasm: match wasm_len {
+                0 => None,
+                _ => Some(artifact[wasm_start..metadata_start].to_vec()),
+            },
+        }))
+    }
+
+    /// Returns the module the artifact was compiled from, if it is embedded.
+    pub fn wasm(&self) -> Option<&[u8]> {
+        self.wasm.as_deref()
+    }
+
+    /// Returns every reason the artifact cannot run in this runtime with `conf`.
+    ///
+    /// The optimization level and IR dumping do not change what the code does, so they are not
+    /// compared.
+    pub fn incompatibilities(&self, conf: &ConfigureContext) -> Vec<Incompatibility> {
+        let mut incompatibilities = vec![];
+        let runtime = version();
+        if self.runtime_version != runtime {
+            incompatibilities.push(Incompatibility::RuntimeVersion {
+                artifact: self.runtime_version.clone(),
+                runtime,
+            });
+        }
+        for proposal in Proposal::all() {
+            let compiled = self.proposals.contains(&proposal);
+            if compiled != conf.has_proposal(proposal) {
+                incompatibilities.push(Incompatibility::Proposal { proposal, compiled });
+            }
+        }
+        let flags = [
+            (
+                "instruction counting",
+                self.instruction_counting,
+                conf.is_instruction_counting(),
+            ),
+            (
+                "cost measuring",
+                self.cost_measuring,
+                conf.is_cost_measuring(),
+            ),
+        ];
+        for (flag, compiled, expected) in flags.iter() {
+            if compiled != expected {
+                incompatibilities.push(Incompatibility::CompilerFlag {
+                    flag: *flag,
+                    compiled: *compiled,
+                });
+            }
+        }
+        incompatibilities
+    }
+
+    /// Fails with `WasmEdgeError::IncompatibleArtifact` if the artifact cannot run with `conf`.
+    pub fn check(&self, conf: &ConfigureContext) -> WasmEdgeResult<()> {
+        let incompatibilities = self.incompatibilities(conf);
+        match incompatibilities.is_empty() {
+            true => Ok(()),
+            false => Err(WasmEdgeError::IncompatibleArtifact(incompatibilities)),
+        }
+    }
+}
+
+/// How `VMContext::load_from_artifact` loaded an artifact.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArtifactLoad {
+    /// The artifact is compatible and its native code was loaded.
+    Native,
+    /// The artifact has no metadata, so its native code was loaded unchecked.
+    Unverified,
+    /// The artifact is incompatible, so the module embedded in it was loaded to be interpreted.
+    Interpreted(Vec<Incompatibility>),
+}
+
+/// Hashes what besides the module decides the compiled code, into 16 hex digits.
+fn config_hash(conf: &ConfigureContext) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(version().as_bytes());
+    hasher.update([conf.get_opt_level() as u8]);
+    for prop in Proposal::all() {
+        hasher.update([conf.has_proposal(prop) as u8]);
+    }
+    hasher.update([
+        conf.is_dump_ir() as u8,
+        conf.is_instruction_counting() as u8,
+        conf.is_cost_measuring() as u8,
+    ]);
+    let mut hash = hex(&hasher.finalize());
+    hash.truncate(16);
+    hash
+}
+
+fn hex(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        let _ = write!(s, "{:02x}", b);
+    }
+    s
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::context::configure::ConfigureContext;
+    use crate::context::{configure::ConfigureContext, vm::VMContext};
 
     const TPATH: &str = "/root/workspace/wasmedge-ml/wasmedge/tests/data/test.wasm";
 
@@ -56,4 +934,244 @@ mod tests {
             .compile("not_exist.wasm", "not_exit.wasm.so")
             .is_err());
     }
+
+    #[test]
+    fn test_compiler_buffer() {
+        let wasm = std::fs::read(TPATH).unwrap();
+        let mut compiler = Compiler::create(ConfigureContext::create()).unwrap();
+
+        let artifact = compiler.compile_buffer(&wasm).unwrap();
+        assert!(!artifact.is_empty());
+        assert_ne!(&artifact[..4], b"\0asm");
+
+        let mut written = vec![];
+        assert!(compiler.compile_to_writer(&wasm, &mut written).is_ok());
+        assert!(!written.is_empty());
+
+        let mut written = vec![];
+        assert!(compiler
+            .compile_to_writer(&[0x00, 0x61, 0x73], &mut written)
+            .is_err());
+        assert!(written.is_empty());
+    }
+
+    #[test]
+    fn test_artifact_info() {
+        let dir = std::env::temp_dir().join(format!("wasmedge-aot-info-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let wasm = std::fs::read(TPATH).unwrap();
+        let artifact = dir.join("test.so");
+        let mut compiler = Compiler::create(ConfigureContext::create()).unwrap();
+        assert!(compiler
+            .compile(Path::new(TPATH), artifact.as_path())
+            .is_ok());
+
+        let info = ArtifactInfo::read(&artifact).unwrap().unwrap();
+        assert_eq!(info.runtime_version, version());
+        assert_eq!(info.wasm(), Some(&wasm[..]));
+        let conf = ConfigureContext::create();
+        assert!(info.incompatibilities(&conf).is_empty());
+        assert!(info.check(&conf).is_ok());
+
+        let mut conf = ConfigureContext::create();
+        conf.add_proposal(Proposal::Simd);
+        conf.set_instruction_counting(true);
+        let err = info.check(&conf).unwrap_err();
+        assert_eq!(
+            err,
+            WasmEdgeError::IncompatibleArtifact(vec![
+                Incompatibility::Proposal {
+                    proposal: Proposal::Simd,
+                    compiled: false,
+                },
+                Incompatibility::CompilerFlag {
+                    flag: "instruction counting",
+                    compiled: false,
+                },
+            ])
+        );
+        assert_eq!(err.phase(), crate::error::ErrorPhase::Load);
+        assert_eq!(
+            err.to_string(),
+            "incompatible AOT artifact: compiled without the simd proposal; \
+             compiled without instruction counting"
+        );
+
+        // An incompatible artifact falls back to its embedded module
+        let mut vm = VMContext::create(Some(&conf), None).unwrap();
+        let load = vm.load_from_artifact(&artifact, &conf).unwrap();
+        assert!(matches!(load, ArtifactLoad::Interpreted(ref v) if v.len() == 2));
+        assert!(vm.validate().is_ok());
+        let default_conf = ConfigureContext::create();
+        let mut vm = VMContext::create(Some(&default_conf), None).unwrap();
+        assert_eq!(
+            vm.load_from_artifact(&artifact, &default_conf).unwrap(),
+            ArtifactLoad::Native
+        );
+
+        // Without the embedded module there is nothing to fall back to
+        let bare = dir.join("bare.so");
+        compiler.embed_wasm(false);
+        assert!(compiler.compile(Path::new(TPATH), bare.as_path()).is_ok());
+        assert!(ArtifactInfo::read(&bare).unwrap().unwrap().wasm().is_none());
+        let mut vm = VMContext::create(Some(&conf), None).unwrap();
+        assert!(matches!(
+            vm.load_from_artifact(&bare, &conf),
+            Err(WasmEdgeError::IncompatibleArtifact(_))
+        ));
+
+        // Artifacts without metadata load unchecked
+        let stripped = dir.join("stripped.so");
+        match APPEND_METADATA {
+            true => {
+                let bytes = std::fs::read(&artifact).unwrap();
+                let native = &bytes[..bytes.len() - FOOTER_LEN - info_trailer_len(&bytes)];
+                assert_eq!(ArtifactInfo::from_bytes(native).unwrap(), None);
+                std::fs::write(&stripped, native).unwrap();
+            }
+            false => {
+                std::fs::copy(&artifact, &stripped).unwrap();
+            }
+        }
+        assert_eq!(ArtifactInfo::read(&stripped).unwrap(), None);
+        let mut vm = VMContext::create(Some(&conf), None).unwrap();
+        assert_eq!(
+            vm.load_from_artifact(&stripped, &conf).unwrap(),
+            ArtifactLoad::Unverified
+        );
+
+        let mut bogus = vec![0xFF; 16];
+        bogus.extend_from_slice(METADATA_MAGIC);
+        assert!(ArtifactInfo::from_bytes(&bogus).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_artifact_metadata_path() {
+        assert_eq!(
+            metadata_path(Path::new("/cache/module.so")),
+            PathBuf::from("/cache/module.so.meta")
+        );
+        assert_eq!(
+            metadata_path(Path::new("module.dll")),
+            PathBuf::from("module.dll.meta")
+        );
+    }
+
+    /// Returns the length of the embedded module and metadata of an artifact.
+    fn info_trailer_len(artifact: &[u8]) -> usize {
+        let footer = &artifact[artifact.len() - FOOTER_LEN..];
+        let wasm_len = u64::from_le_bytes(<[u8; 8]>::try_from(&footer[..8]).unwrap());
+        let metadata_len = u64::from_le_bytes(<[u8; 8]>::try_from(&footer[8..16]).unwrap());
+        (wasm_len + metadata_len) as usize
+    }
+
+    #[test]
+    fn test_batch_compiler() {
+        let dir = std::env::temp_dir().join(format!("wasmedge-aot-batch-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let jobs: Vec<_> = (0..4)
+            .map(|i| (PathBuf::from(TPATH), dir.join(format!("test-{}.so", i))))
+            .chain(std::iter::once((
+                PathBuf::from("not_exist.wasm"),
+                dir.join("not_exist.so"),
+            )))
+            .collect();
+
+        let mut batch = BatchCompiler::new(Configuration::from(&ConfigureContext::create()));
+        batch.threads(2);
+        let mut seen = vec![];
+        let reports = batch.compile(&jobs, |report| seen.push(report.index));
+        seen.sort_unstable();
+        assert_eq!(seen, vec![0, 1, 2, 3, 4]);
+        assert_eq!(reports.len(), 5);
+        for (i, report) in reports.iter().enumerate() {
+            assert_eq!(report.index, i);
+            assert_eq!(report.input, jobs[i].0);
+        }
+        assert!(reports[..4].iter().all(|report| report.is_compiled()));
+        assert!(jobs[..4].iter().all(|(_, output)| output.is_file()));
+        assert!(matches!(reports[4].outcome, CompileOutcome::Failed(_)));
+
+        // Cancelling before the batch starts skips every module
+        let token = CancelToken::new();
+        token.cancel();
+        batch.cancel_token(token);
+        let reports = batch.compile(&jobs, |_| {});
+        assert!(reports
+            .iter()
+            .all(|report| matches!(report.outcome, CompileOutcome::Cancelled)));
+        assert!(batch.compile(&[] as &[(&str, &str)], |_| {}).is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_compilation_cache() {
+        use crate::types::CompilerOptimizationLevel;
+
+        let dir = std::env::temp_dir().join(format!("wasmedge-aot-cache-{}", std::process::id()));
+        let wasm = std::fs::read(TPATH).unwrap();
+        let empty = [0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00];
+
+        let mut cache = CompilationCache::create(&dir, ConfigureContext::create()).unwrap();
+        assert!(cache.get(&wasm).is_none());
+        let path = cache.compile(&wasm).unwrap();
+        assert!(path.is_file());
+        assert!(is_artifact(&path));
+        assert_eq!(cache.compile_file(TPATH).unwrap(), path);
+        assert_eq!(cache.get(&wasm), Some(path.clone()));
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 1,
+                evictions: 0
+            }
+        );
+        assert!(cache.compile(&empty[..4]).is_err());
+
+        // Another optimization level gets its own artifact
+        let mut conf = ConfigureContext::create();
+        conf.set_opt_level(CompilerOptimizationLevel::WasmEdge_CompilerOptimizationLevel_O0);
+        let other = CompilationCache::create(&dir, conf).unwrap();
+        assert!(other.get(&wasm).is_none());
+
+        // The loaded module is replaced by its artifact
+        let mut vm = VMContext::create(None, None).unwrap();
+        vm.set_compilation_cache(&cache);
+        assert!(vm.load_from_file(TPATH).is_ok());
+        assert!(vm.validate().is_ok());
+        // An artifact bypasses the cache
+        assert!(vm.load_from_file(&path).is_ok());
+        assert!(vm.validate().is_ok());
+
+        // A file at the artifact path without matching metadata is not loaded
+        let artifact = std::fs::read(&path).unwrap();
+        std::fs::write(&path, b"not an artifact").unwrap();
+        let mut vm = VMContext::create(None, None).unwrap();
+        vm.set_compilation_cache(&cache);
+        assert!(vm.load_from_file(TPATH).is_ok());
+        assert!(vm.validate().is_ok());
+        std::fs::write(&path, &artifact).unwrap();
+
+        // A hit refreshes the artifact, so the least recently used one is evicted first
+        let past = std::time::UNIX_EPOCH + Duration::from_secs(1);
+        let file = OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_modified(past).unwrap();
+        drop(file);
+        assert!(cache.get(&wasm).is_some());
+        assert!(std::fs::metadata(&path).unwrap().modified().unwrap() > past);
+
+        // The newest artifact is kept even if it alone exceeds the cap
+        cache.reset_stats();
+        cache.max_size(1);
+        let empty_path = cache.compile(&empty).unwrap();
+        assert!(empty_path.is_file());
+        assert!(!path.exists());
+        assert_eq!(cache.stats().evictions, 1);
+
+        assert!(cache.clear().is_ok());
+        assert!(cache.get(&empty).is_none());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
@@ -1,8 +1,78 @@
+use crate::binary::{self, ModuleInfo};
+use crate::error::{WasmEdgeError, WasmEdgeResult};
+use crate::types::{Mutability, RefType, ValType};
+use memmap2::Mmap;
+use std::collections::BTreeMap;
+use std::fmt;
 use std::ptr;
+use std::sync::Arc;
 use wasmedge_sys::ffi as we_ffi;
 
+/// Where the bytes of a loaded module came from, kept for introspection.
+#[derive(Debug, Clone)]
+pub(crate) enum ModuleSource {
+    Buffer(Arc<[u8]>),
+    Mapped(Arc<Mmap>),
+}
+
 pub struct ASTModuleContext {
     pub(crate) raw: *mut we_ffi::WasmEdge_ASTModuleContext,
+    pub(crate) source: Option<ModuleSource>,
+}
+impl ASTModuleContext {
+    /// Returns the imports of the module, in the order they are declared.
+    pub fn imports(&self) -> WasmEdgeResult<impl Iterator<Item = ImportType>> {
+        Ok(self.module_info()?.imports.into_iter())
+    }
+
+    /// Returns the exports of the module, in the order they are declared.
+    pub fn exports(&self) -> WasmEdgeResult<impl Iterator<Item = ExportType>> {
+        Ok(self.module_info()?.exports.into_iter())
+    }
+
+    /// Returns the custom sections of the module, in the order they appear.
+    pub fn custom_sections(&self) -> WasmEdgeResult<impl Iterator<Item = CustomSection>> {
+        Ok(binary::custom_sections(&self.bytes()?)?.into_iter())
+    }
+
+    /// Returns the payload of the first custom section with the given name.
+    pub fn custom_section(&self, name: &str) -> WasmEdgeResult<Option<Vec<u8>>> {
+        Ok(self
+            .custom_sections()?
+            .find(|section| section.name == name)
+            .map(|section| section.data))
+    }
+
+    /// Decodes the `name` custom section, or returns `None` if the module has none.
+    pub fn names(&self) -> WasmEdgeResult<Option<NameSection>> {
+        match self.custom_section("name")? {
+            Some(data) => Ok(Some(binary::name_section(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Decodes the `producers` custom section, or returns `None` if the module has none.
+    pub fn producers(&self) -> WasmEdgeResult<Option<Vec<ProducerField>>> {
+        match self.custom_section("producers")? {
+            Some(data) => Ok(Some(binary::producers_section(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the bytes the module was parsed from, captured when it was loaded.
+    pub(crate) fn bytes(&self) -> WasmEdgeResult<&[u8]> {
+        match &self.source {
+            Some(ModuleSource::Buffer(buf)) => Ok(&buf[..]),
+            Some(ModuleSource::Mapped(map)) => Ok(&map[..]),
+            None => Err(WasmEdgeError::from(String::from(
+                "The AST module was not loaded by a LoaderContext, so its bytes are unknown",
+            ))),
+        }
+    }
+
+    pub(crate) fn module_info(&self) -> WasmEdgeResult<ModuleInfo> {
+        ModuleInfo::parse(&self.bytes()?)
+    }
 }
 impl Drop for ASTModuleContext {
     fn drop(&mut self) {
@@ -15,6 +85,324 @@ impl Default for ASTModuleContext {
     fn default() -> Self {
         ASTModuleContext {
             raw: ptr::null_mut(),
+            source: None,
         }
     }
 }
+
+/// The kind of an import or export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExternKind {
+    Function,
+    Table,
+    Memory,
+    Global,
+}
+impl fmt::Display for ExternKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self {
+            ExternKind::Function => "func",
+            ExternKind::Table => "table",
+            ExternKind::Memory => "memory",
+            ExternKind::Global => "global",
+        };
+        f.write_str(kind)
+    }
+}
+
+/// The type of a function, as declared in the type section.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FuncType {
+    pub params: Vec<ValType>,
+    pub returns: Vec<ValType>,
+}
+impl fmt::Display for FuncType {
+    /// Formats the type as in the text format, e.g. `(func (param i32 i32) (result i32))`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("(func")?;
+        for (keyword, types) in [("param", &self.params), ("result", &self.returns)].iter() {
+            if !types.is_empty() {
+                write!(f, " ({}", keyword)?;
+                for ty in types.iter() {
+                    write!(f, " {}", ty)?;
+                }
+                f.write_str(")")?;
+            }
+        }
+        f.write_str(")")
+    }
+}
+
+/// The size limits of a table, in elements, or of a memory, in 64 KiB pages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Limits {
+    pub min: u32,
+    pub max: Option<u32>,
+}
+impl fmt::Display for Limits {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.max {
+            Some(max) => write!(f, "{} {}", self.min, max),
+            None => write!(f, "{}", self.min),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TableType {
+    pub ref_type: RefType,
+    pub limits: Limits,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MemoryType {
+    pub limits: Limits,
+    /// Whether the memory is shared between threads.
+    pub shared: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GlobalType {
+    pub val_type: ValType,
+    pub mutability: Mutability,
+}
+
+/// The type of an imported or exported item.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ExternType {
+    Function(FuncType),
+    Table(TableType),
+    Memory(MemoryType),
+    Global(GlobalType),
+}
+impl ExternType {
+    pub fn kind(&self) -> ExternKind {
+        match self {
+            ExternType::Function(_) => ExternKind::Function,
+            ExternType::Table(_) => ExternKind::Table,
+            ExternType::Memory(_) => ExternKind::Memory,
+            ExternType::Global(_) => ExternKind::Global,
+        }
+    }
+}
+impl fmt::Display for ExternType {
+    /// Formats the type as in the text format, e.g. `(memory 1 2)` or `(global (mut i32))`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExternType::Function(ty) => ty.fmt(f),
+            ExternType::Table(ty) => write!(f, "(table {} {})", ty.limits, ty.ref_type),
+            ExternType::Memory(ty) if ty.shared => write!(f, "(memory {} shared)", ty.limits),
+            ExternType::Memory(ty) => write!(f, "(memory {})", ty.limits),
+            ExternType::Global(ty) => match ty.mutability {
+                Mutability::Const => write!(f, "(global {})", ty.val_type),
+                Mutability::Var => write!(f, "(global (mut {}))", ty.val_type),
+            },
+        }
+    }
+}
+
+/// An item a module imports from another module.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ImportType {
+    pub module: String,
+    pub name: String,
+    pub ty: ExternType,
+}
+impl ImportType {
+    pub fn kind(&self) -> ExternKind {
+        self.ty.kind()
+    }
+}
+
+/// An item a module exports.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ExportType {
+    pub name: String,
+    pub ty: ExternType,
+}
+impl ExportType {
+    pub fn kind(&self) -> ExternKind {
+        self.ty.kind()
+    }
+}
+
+/// A custom section: its name and its raw payload.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CustomSection {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+/// The decoded `name` custom section.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NameSection {
+    pub module: Option<String>,
+    /// Function names by function index.
+    pub functions: BTreeMap<u32, String>,
+    /// Local names by function index, then by local index.
+    pub locals: BTreeMap<u32, BTreeMap<u32, String>>,
+}
+impl NameSection {
+    pub fn function(&self, func_idx: u32) -> Option<&str> {
+        self.functions.get(&func_idx).map(String::as_str)
+    }
+
+    pub fn local(&self, func_idx: u32, local_idx: u32) -> Option<&str> {
+        self.locals
+            .get(&func_idx)
+            .and_then(|locals| locals.get(&local_idx))
+            .map(String::as_str)
+    }
+}
+
+/// A field of the `producers` custom section, e.g. `language` or `processed-by`, with its
+/// `(name, version)` values.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ProducerField {
+    pub name: String,
+    pub values: Vec<(String, String)>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{configure::ConfigureContext, loader::LoaderContext};
+
+    // (module
+    //   (import "env" "log" (func (param i32)))
+    //   (import "env" "mem" (memory 1 2))
+    //   (import "env" "g" (global (mut i64)))
+    //   (table 2 funcref)
+    //   (func (export "add") (param i32 i32) (result i32) local.get 0 local.get 1 i32.add)
+    //   (export "tab" (table 0))
+    //   (export "g" (global 0)))
+    const IMPORTS_WASM: &[u8] = &[
+        0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00, /* Type section */
+        0x01, 0x0B, 0x02, 0x60, 0x01, 0x7F, 0x00, 0x60, 0x02, 0x7F, 0x7F, 0x01, 0x7F,
+        /* Import section */
+        0x02, 0x20, 0x03, 0x03, 0x65, 0x6E, 0x76, 0x03, 0x6C, 0x6F, 0x67, 0x00, 0x00, 0x03, 0x65,
+        0x6E, 0x76, 0x03, 0x6D, 0x65, 0x6D, 0x02, 0x01, 0x01, 0x02, 0x03, 0x65, 0x6E, 0x76, 0x01,
+        0x67, 0x03, 0x7E, 0x01, /* Function section */
+        0x03, 0x02, 0x01, 0x01, /* Table section */
+        0x04, 0x04, 0x01, 0x70, 0x00, 0x02, /* Export section */
+        0x07, 0x11, 0x03, 0x03, 0x61, 0x64, 0x64, 0x00, 0x01, 0x03, 0x74, 0x61, 0x62, 0x01, 0x00,
+        0x01, 0x67, 0x03, 0x00, /* Code section */
+        0x0A, 0x09, 0x01, 0x07, 0x00, 0x20, 0x00, 0x20, 0x01, 0x6A, 0x0B,
+    ];
+
+    #[test]
+    fn test_ast_imports_exports() {
+        let conf = ConfigureContext::create();
+        let mut loader = LoaderContext::create(&conf).unwrap();
+        let mut ast_mod = ASTModuleContext::default();
+        assert!(ast_mod.imports().is_err());
+        assert!(loader.parse_from_buffer(&mut ast_mod, IMPORTS_WASM).is_ok());
+
+        let imports: Vec<_> = ast_mod.imports().unwrap().collect();
+        assert_eq!(imports.len(), 3);
+        assert_eq!(imports[0].module, "env");
+        assert_eq!(imports[0].name, "log");
+        assert_eq!(
+            imports[0].ty,
+            ExternType::Function(FuncType {
+                params: vec![ValType::I32],
+                returns: vec![],
+            })
+        );
+        assert_eq!(imports[1].kind(), ExternKind::Memory);
+        assert_eq!(
+            imports[1].ty,
+            ExternType::Memory(MemoryType {
+                limits: Limits {
+                    min: 1,
+                    max: Some(2)
+                },
+                shared: false,
+            })
+        );
+        assert_eq!(
+            imports[2].ty,
+            ExternType::Global(GlobalType {
+                val_type: ValType::I64,
+                mutability: Mutability::Var,
+            })
+        );
+
+        let exports: Vec<_> = ast_mod.exports().unwrap().collect();
+        assert_eq!(exports.len(), 3);
+        assert_eq!(exports[0].name, "add");
+        assert_eq!(
+            exports[0].ty,
+            ExternType::Function(FuncType {
+                params: vec![ValType::I32, ValType::I32],
+                returns: vec![ValType::I32],
+            })
+        );
+        assert_eq!(
+            exports[1].ty,
+            ExternType::Table(TableType {
+                ref_type: RefType::FuncRef,
+                limits: Limits { min: 2, max: None },
+            })
+        );
+        // Exported globals index the imported ones first
+        assert_eq!(exports[2].kind(), ExternKind::Global);
+        assert_eq!(exports[2].ty, imports[2].ty);
+    }
+
+    #[test]
+    fn test_ast_custom_sections() {
+        let mut wasm = vec![0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00];
+        // A user section: "version" -> "1.2.3"
+        wasm.extend_from_slice(&[0x00, 0x0D, 0x07]);
+        wasm.extend_from_slice(b"version1.2.3");
+        // The name section: module "m", function 0 "f"
+        wasm.extend_from_slice(&[0x00, 0x0F, 0x04]);
+        wasm.extend_from_slice(b"name");
+        wasm.extend_from_slice(&[0x00, 0x02, 0x01, 0x6D, 0x01, 0x04, 0x01, 0x00, 0x01, 0x66]);
+        // The producers section: language -> [("Rust", "1.56")]
+        wasm.extend_from_slice(&[0x00, 0x1F, 0x09]);
+        wasm.extend_from_slice(b"producers");
+        wasm.extend_from_slice(&[0x01, 0x08]);
+        wasm.extend_from_slice(b"language");
+        wasm.extend_from_slice(&[0x01, 0x04]);
+        wasm.extend_from_slice(b"Rust");
+        wasm.extend_from_slice(&[0x04]);
+        wasm.extend_from_slice(b"1.56");
+
+        let conf = ConfigureContext::create();
+        let mut loader = LoaderContext::create(&conf).unwrap();
+        let mut ast_mod = ASTModuleContext::default();
+        assert!(loader.parse_from_buffer(&mut ast_mod, &wasm).is_ok());
+
+        let sections: Vec<_> = ast_mod.custom_sections().unwrap().collect();
+        assert_eq!(sections.len(), 3);
+        assert_eq!(sections[0].name, "version");
+        assert_eq!(sections[0].data, b"1.2.3");
+        assert_eq!(
+            ast_mod.custom_section("version").unwrap(),
+            Some(b"1.2.3".to_vec())
+        );
+        assert_eq!(ast_mod.custom_section("license").unwrap(), None);
+
+        let names = ast_mod.names().unwrap().unwrap();
+        assert_eq!(names.module.as_deref(), Some("m"));
+        assert_eq!(names.function(0), Some("f"));
+        assert_eq!(names.function(1), None);
+
+        let producers = ast_mod.producers().unwrap().unwrap();
+        assert_eq!(
+            producers,
+            vec![ProducerField {
+                name: String::from("language"),
+                values: vec![(String::from("Rust"), String::from("1.56"))],
+            }]
+        );
+
+        // A module without custom sections
+        let mut ast_mod = ASTModuleContext::default();
+        assert!(loader.parse_from_buffer(&mut ast_mod, IMPORTS_WASM).is_ok());
+        assert_eq!(ast_mod.custom_sections().unwrap().count(), 0);
+        assert_eq!(ast_mod.names().unwrap(), None);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::types::{CompilerOptimizationLevel, HostRegistration, WasmEdgeProposal};
+use crate::types::{CompilerOptimizationLevel, HostRegistration, Proposal};
 use wasmedge_sys::{ffi as we_ffi, WasmEdge_ConfigureCompilerIsCostMeasuring};
 
 pub struct ConfigureContext {
@@ -12,29 +12,29 @@ impl ConfigureContext {
     }
 
     pub fn add_host_registration(&mut self, host: HostRegistration) {
-        unsafe { we_ffi::WasmEdge_ConfigureAddHostRegistration(self.raw, host) }
+        unsafe { we_ffi::WasmEdge_ConfigureAddHostRegistration(self.raw, host.into()) }
     }
 
     pub fn remove_host_registration(&mut self, host: HostRegistration) {
-        unsafe { we_ffi::WasmEdge_ConfigureRemoveHostRegistration(self.raw, host) }
+        unsafe { we_ffi::WasmEdge_ConfigureRemoveHostRegistration(self.raw, host.into()) }
     }
 
     pub fn has_host_registration(&self, host: HostRegistration) -> bool {
         {
-            unsafe { we_ffi::WasmEdge_ConfigureHasHostRegistration(self.raw, host) }
+            unsafe { we_ffi::WasmEdge_ConfigureHasHostRegistration(self.raw, host.into()) }
         }
     }
 
-    pub fn has_proposal(&self, prop: WasmEdgeProposal) -> bool {
-        unsafe { we_ffi::WasmEdge_ConfigureHasProposal(self.raw, prop) }
+    pub fn has_proposal(&self, prop: Proposal) -> bool {
+        unsafe { we_ffi::WasmEdge_ConfigureHasProposal(self.raw, prop.into()) }
     }
 
-    pub fn add_proposal(&mut self, prop: WasmEdgeProposal) {
-        unsafe { we_ffi::WasmEdge_ConfigureAddProposal(self.raw, prop) }
+    pub fn add_proposal(&mut self, prop: Proposal) {
+        unsafe { we_ffi::WasmEdge_ConfigureAddProposal(self.raw, prop.into()) }
     }
 
-    pub fn remove_proposal(&mut self, prop: WasmEdgeProposal) {
-        unsafe { we_ffi::WasmEdge_ConfigureRemoveProposal(self.raw, prop) }
+    pub fn remove_proposal(&mut self, prop: Proposal) {
+        unsafe { we_ffi::WasmEdge_ConfigureRemoveProposal(self.raw, prop.into()) }
     }
 
     pub fn set_max_memory_page(&mut self, page: usize) {
@@ -85,6 +85,62 @@ impl Drop for ConfigureContext {
     }
 }
 
+/// A copy of the settings of a `ConfigureContext`.
+///
+/// Unlike the context, it can be cloned and sent to other threads, e.g. to create one compiler
+/// per thread with `to_context`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Configuration {
+    pub(crate) proposals: Vec<Proposal>,
+    pub(crate) host_registrations: Vec<HostRegistration>,
+    pub(crate) max_memory_page: usize,
+    pub(crate) opt_level: CompilerOptimizationLevel,
+    pub(crate) dump_ir: bool,
+    pub(crate) instruction_counting: bool,
+    pub(crate) cost_measuring: bool,
+}
+impl Configuration {
+    /// Creates a new `ConfigureContext` with these settings.
+    pub fn to_context(&self) -> ConfigureContext {
+        let mut conf = ConfigureContext::create();
+        for prop in Proposal::all() {
+            match self.proposals.contains(&prop) {
+                true => conf.add_proposal(prop),
+                false => conf.remove_proposal(prop),
+            }
+        }
+        for host in HostRegistration::all() {
+            match self.host_registrations.contains(&host) {
+                true => conf.add_host_registration(host),
+                false => conf.remove_host_registration(host),
+            }
+        }
+        conf.set_max_memory_page(self.max_memory_page);
+        conf.set_opt_level(self.opt_level);
+        conf.set_dump_ir(self.dump_ir);
+        conf.set_instruction_counting(self.instruction_counting);
+        conf.set_cost_measuring(self.cost_measuring);
+        conf
+    }
+}
+impl From<&ConfigureContext> for Configuration {
+    fn from(conf: &ConfigureContext) -> Self {
+        Configuration {
+            proposals: Proposal::all()
+                .filter(|prop| conf.has_proposal(*prop))
+                .collect(),
+            host_registrations: HostRegistration::all()
+                .filter(|host| conf.has_host_registration(*host))
+                .collect(),
+            max_memory_page: conf.get_max_memory_page(),
+            opt_level: conf.get_opt_level(),
+            dump_ir: conf.is_dump_ir(),
+            instruction_counting: conf.is_instruction_counting(),
+            cost_measuring: conf.is_cost_measuring(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -93,22 +149,22 @@ mod tests {
     fn test_configure_proposals() {
         let mut config = ConfigureContext::create();
 
-        config.add_proposal(WasmEdgeProposal::WasmEdge_Proposal_SIMD);
-        config.add_proposal(WasmEdgeProposal::WasmEdge_Proposal_Memory64);
-        assert!(config.has_proposal(WasmEdgeProposal::WasmEdge_Proposal_SIMD));
-        assert!(config.has_proposal(WasmEdgeProposal::WasmEdge_Proposal_Memory64));
-        config.remove_proposal(WasmEdgeProposal::WasmEdge_Proposal_SIMD);
-        assert!(!config.has_proposal(WasmEdgeProposal::WasmEdge_Proposal_SIMD));
-        assert!(config.has_proposal(WasmEdgeProposal::WasmEdge_Proposal_Memory64));
+        config.add_proposal(Proposal::Simd);
+        config.add_proposal(Proposal::Memory64);
+        assert!(config.has_proposal(Proposal::Simd));
+        assert!(config.has_proposal(Proposal::Memory64));
+        config.remove_proposal(Proposal::Simd);
+        assert!(!config.has_proposal(Proposal::Simd));
+        assert!(config.has_proposal(Proposal::Memory64));
     }
 
     #[test]
     fn test_host_registration() {
         let mut config = ConfigureContext::create();
-        config.add_host_registration(HostRegistration::WasmEdge_HostRegistration_Wasi);
-        assert!(config.has_host_registration(HostRegistration::WasmEdge_HostRegistration_Wasi));
-        config.remove_host_registration(HostRegistration::WasmEdge_HostRegistration_Wasi);
-        assert!(!config.has_host_registration(HostRegistration::WasmEdge_HostRegistration_Wasi));
+        config.add_host_registration(HostRegistration::Wasi);
+        assert!(config.has_host_registration(HostRegistration::Wasi));
+        config.remove_host_registration(HostRegistration::Wasi);
+        assert!(!config.has_host_registration(HostRegistration::Wasi));
     }
 
     #[test]
@@ -135,4 +191,26 @@ mod tests {
         config.set_cost_measuring(true);
         assert!(config.is_cost_measuring());
     }
+
+    #[test]
+    fn test_configuration() {
+        let mut config = ConfigureContext::create();
+        config.add_proposal(Proposal::Simd);
+        config.remove_proposal(Proposal::ReferenceTypes);
+        config.add_host_registration(HostRegistration::Wasi);
+        config.set_max_memory_page(1234);
+        config.set_opt_level(CompilerOptimizationLevel::WasmEdge_CompilerOptimizationLevel_O0);
+        config.set_cost_measuring(true);
+
+        let snapshot = Configuration::from(&config);
+        let copy = std::thread::spawn(move || snapshot).join().unwrap();
+        let restored = copy.to_context();
+        assert!(restored.has_proposal(Proposal::Simd));
+        assert!(!restored.has_proposal(Proposal::ReferenceTypes));
+        assert!(restored.has_host_registration(HostRegistration::Wasi));
+        assert_eq!(restored.get_max_memory_page(), 1234);
+        assert!(restored.is_cost_measuring());
+        assert!(!restored.is_dump_ir());
+        assert_eq!(Configuration::from(&restored), copy);
+    }
 }
@@ -1,79 +1,78 @@
 use crate::{
-    context::vm::VMContext,
+    context::{ast::ExternType, vm::VMContext},
+    error::{WasmEdgeError, WasmEdgeResult},
     instance::{
         function::HostFunctionContext, global::GlobalInstanceContext,
         memory::MemoryInstanceContext, table::TableInstanceContext,
     },
     types::WasmEdgeString,
-    utils::string_to_c_array,
+    wasi::WasiConfig,
 };
 use std::marker::PhantomData;
 use std::ptr;
 use wasmedge_sys::ffi as we_ffi;
 
+/// The module name of the WASI import object.
+pub(crate) const WASI_MODULE: &str = "wasi_snapshot_preview1";
+
 #[derive(Clone)]
 pub struct ImportObjectContext<'vm> {
     pub(crate) raw: *mut we_ffi::WasmEdge_ImportObjectContext,
     pub(crate) _marker: PhantomData<&'vm VMContext>,
     pub(crate) _drop: bool,
+    /// The module name the import object is registered under.
+    pub(crate) module: String,
+    /// The items added through this wrapper, or `None` if they are unknown to the crate, e.g. for
+    /// the built-in WASI import object. Used by `link_check`.
+    pub(crate) exports: Option<Vec<(String, ExternType)>>,
 }
 impl<'a> ImportObjectContext<'a> {
-    pub fn create(mod_name: &str, data: *mut std::os::raw::c_void) -> Option<ImportObjectContext> {
-        let mod_name = WasmEdgeString::from_str(mod_name)
-            .expect(format!("Failed to create WasmEdgeString from '{}'", mod_name).as_str());
+    pub fn create(
+        mod_name: &str,
+        data: *mut std::os::raw::c_void,
+    ) -> WasmEdgeResult<ImportObjectContext<'a>> {
+        let module = String::from(mod_name);
+        let mod_name = WasmEdgeString::from_bytes(mod_name.as_bytes())?;
         let raw = unsafe { we_ffi::WasmEdge_ImportObjectCreate(mod_name.raw, data) };
         match raw.is_null() {
-            true => None,
-            false => Some(ImportObjectContext {
+            true => Err(WasmEdgeError::from(String::from(
+                "Failed to create the import object",
+            ))),
+            false => Ok(ImportObjectContext {
                 raw,
                 _marker: PhantomData,
                 _drop: true,
+                module,
+                exports: Some(vec![]),
             }),
         }
     }
 
-    pub fn create_wasi(
-        args: Option<&[&str]>,
-        envs: Option<&[&str]>,
-        dirs: Option<&[&str]>,
-        preopens: Option<&[&str]>,
-    ) -> Option<ImportObjectContext<'a>> {
-        let (args_len, args) = match args {
-            Some(args) => (args.len() as u32, string_to_c_array(args)),
-            None => (0, ptr::null()),
-        };
-        let (envs_len, envs) = match envs {
-            Some(envs) => (envs.len() as u32, string_to_c_array(envs)),
-            None => (0, ptr::null()),
-        };
-        let (dirs_len, dirs) = match dirs {
-            Some(dirs) => (dirs.len() as u32, string_to_c_array(dirs)),
-            None => (0, ptr::null()),
-        };
-        let (preopens_len, preopens) = match preopens {
-            Some(preopens) => (preopens.len() as u32, string_to_c_array(preopens)),
-            None => (0, ptr::null()),
-        };
+    pub fn create_wasi(config: &WasiConfig) -> WasmEdgeResult<ImportObjectContext<'a>> {
+        let c = config.to_c_arrays()?;
         let raw = unsafe {
             we_ffi::WasmEdge_ImportObjectCreateWASI(
-                args,
-                args_len,
-                envs,
-                envs_len,
-                dirs,
-                dirs_len,
-                preopens,
-                preopens_len,
+                c.args.as_ptr(),
+                c.args.len(),
+                c.envs.as_ptr(),
+                c.envs.len(),
+                c.dirs.as_ptr(),
+                c.dirs.len(),
+                c.preopens.as_ptr(),
+                c.preopens.len(),
             )
         };
 
         match raw.is_null() {
-            true => None,
-            // false => Some(raw),
-            false => Some(ImportObjectContext {
+            true => Err(WasmEdgeError::from(String::from(
+                "Failed to create the WASI import object",
+            ))),
+            false => Ok(ImportObjectContext {
                 raw,
                 _marker: PhantomData,
                 _drop: true,
+                module: String::from(WASI_MODULE),
+                exports: None,
             }),
         }
     }
@@ -83,6 +82,8 @@ impl<'a> ImportObjectContext<'a> {
             raw: unsafe { we_ffi::WasmEdge_Tensorflow_ImportObjectCreate() },
             _marker: PhantomData,
             _drop: true,
+            module: String::from("wasmedge_tensorflow"),
+            exports: None,
         }
     }
 
@@ -91,81 +92,90 @@ impl<'a> ImportObjectContext<'a> {
             raw: unsafe { we_ffi::WasmEdge_TensorflowLite_ImportObjectCreate() },
             _marker: PhantomData,
             _drop: true,
+            module: String::from("wasmedge_tensorflowlite"),
+            exports: None,
         }
     }
 
-    pub fn init_wasi(
-        &self,
-        args: Option<&[&str]>,
-        envs: Option<&[&str]>,
-        dirs: Option<&[&str]>,
-        preopens: Option<&[&str]>,
-    ) {
-        let (args_len, args) = match args {
-            Some(args) => (args.len() as u32, string_to_c_array(args)),
-            None => (0, ptr::null()),
-        };
-        let (envs_len, envs) = match envs {
-            Some(envs) => (envs.len() as u32, string_to_c_array(envs)),
-            None => (0, ptr::null()),
-        };
-        let (dirs_len, dirs) = match dirs {
-            Some(dirs) => (dirs.len() as u32, string_to_c_array(dirs)),
-            None => (0, ptr::null()),
-        };
-        let (preopens_len, preopens) = match preopens {
-            Some(preopens) => (preopens.len() as u32, string_to_c_array(preopens)),
-            None => (0, ptr::null()),
-        };
+    /// Re-initializes a WASI import object, e.g. the one returned by `VMContext::import_object`.
+    pub fn init_wasi(&self, config: &WasiConfig) -> WasmEdgeResult<()> {
+        let c = config.to_c_arrays()?;
         unsafe {
             we_ffi::WasmEdge_ImportObjectInitWASI(
                 self.raw,
-                args,
-                args_len,
-                envs,
-                envs_len,
-                dirs,
-                dirs_len,
-                preopens,
-                preopens_len,
+                c.args.as_ptr(),
+                c.args.len(),
+                c.envs.as_ptr(),
+                c.envs.len(),
+                c.dirs.as_ptr(),
+                c.dirs.len(),
+                c.preopens.as_ptr(),
+                c.preopens.len(),
             )
         };
+        Ok(())
     }
 
-    pub fn add_host_function(&mut self, name: &str, host_func_ctx: &mut HostFunctionContext) {
-        let name = WasmEdgeString::from_str(name)
-            .expect(format!("Failed to create WasmEdgeString from '{}'", name).as_str());
+    pub fn add_host_function(
+        &mut self,
+        name: &str,
+        host_func_ctx: &mut HostFunctionContext,
+    ) -> WasmEdgeResult<()> {
+        let raw_name = WasmEdgeString::from_bytes(name.as_bytes())?;
+        self.record(name, ExternType::Function(host_func_ctx.ty.clone()));
         unsafe {
-            we_ffi::WasmEdge_ImportObjectAddHostFunction(self.raw, name.raw, host_func_ctx.raw);
+            we_ffi::WasmEdge_ImportObjectAddHostFunction(self.raw, raw_name.raw, host_func_ctx.raw);
             host_func_ctx.raw = std::ptr::null_mut();
         }
+        Ok(())
     }
 
-    pub fn add_table(&mut self, name: &str, table_ctx: &mut TableInstanceContext) {
-        let name = WasmEdgeString::from_str(name)
-            .expect(format!("Failed to create WasmEdgeString from '{}'", name).as_str());
+    pub fn add_table(
+        &mut self,
+        name: &str,
+        table_ctx: &mut TableInstanceContext,
+    ) -> WasmEdgeResult<()> {
+        let raw_name = WasmEdgeString::from_bytes(name.as_bytes())?;
+        self.record(name, ExternType::Table(table_ctx.table_type()));
         unsafe {
-            we_ffi::WasmEdge_ImportObjectAddTable(self.raw, name.raw, table_ctx.raw);
+            we_ffi::WasmEdge_ImportObjectAddTable(self.raw, raw_name.raw, table_ctx.raw);
             table_ctx.raw = std::ptr::null_mut();
         }
+        Ok(())
     }
 
-    pub fn add_memory(&mut self, name: &str, mem_ctx: &mut MemoryInstanceContext) {
-        let name = WasmEdgeString::from_str(name)
-            .expect(format!("Failed to create WasmEdgeString from '{}'", name).as_str());
+    pub fn add_memory(
+        &mut self,
+        name: &str,
+        mem_ctx: &mut MemoryInstanceContext,
+    ) -> WasmEdgeResult<()> {
+        let raw_name = WasmEdgeString::from_bytes(name.as_bytes())?;
+        self.record(name, ExternType::Memory(mem_ctx.memory_type()));
         unsafe {
-            we_ffi::WasmEdge_ImportObjectAddMemory(self.raw, name.raw, mem_ctx.raw);
+            we_ffi::WasmEdge_ImportObjectAddMemory(self.raw, raw_name.raw, mem_ctx.raw);
             mem_ctx.raw = std::ptr::null_mut();
         }
+        Ok(())
     }
 
-    pub fn add_global(&mut self, name: &str, global_ctx: &mut GlobalInstanceContext) {
-        let name = WasmEdgeString::from_str(name)
-            .expect(format!("Failed to create WasmEdgeString from '{}'", name).as_str());
+    pub fn add_global(
+        &mut self,
+        name: &str,
+        global_ctx: &mut GlobalInstanceContext,
+    ) -> WasmEdgeResult<()> {
+        let raw_name = WasmEdgeString::from_bytes(name.as_bytes())?;
+        self.record(name, ExternType::Global(global_ctx.global_type()));
         unsafe {
-            we_ffi::WasmEdge_ImportObjectAddGlobal(self.raw, name.raw, global_ctx.raw);
+            we_ffi::WasmEdge_ImportObjectAddGlobal(self.raw, raw_name.raw, global_ctx.raw);
             global_ctx.raw = std::ptr::null_mut();
         }
+        Ok(())
+    }
+
+    fn record(&mut self, name: &str, ty: ExternType) {
+        if let Some(exports) = self.exports.as_mut() {
+            exports.push((String::from(name), ty));
+        }
     }
 }
 impl<'a> Drop for ImportObjectContext<'a> {
@@ -184,9 +194,11 @@ impl<'a> Drop for ImportObjectContext<'a> {
 mod test {
     use super::*;
     use crate::context::{configure::ConfigureContext, vm::VMContext};
+    use crate::externref::ExternRef;
     use crate::instance::function::*;
     use crate::types::*;
-    use crate::value::*;
+    use crate::value::Val;
+    use std::convert::TryFrom;
     use std::ptr;
 
     #[test]
@@ -195,22 +207,19 @@ mod test {
 
         // Create import object with name ""
         let result = ImportObjectContext::create("", ptr::null_mut());
-        assert!(result.is_some());
+        assert!(result.is_ok());
         let imp_obj = result.unwrap();
         assert!(!imp_obj.raw.is_null());
 
         // Create import object with name "extern"
         let result = ImportObjectContext::create(host_name, ptr::null_mut());
-        assert!(result.is_some());
+        assert!(result.is_ok());
         let mut imp_obj = result.unwrap();
         assert!(!imp_obj.raw.is_null());
 
         // Add host function "func-add": {externref, i32} -> {i32}
-        let params = [
-            WasmEdgeValType::WasmEdge_ValType_ExternRef,
-            WasmEdgeValType::WasmEdge_ValType_I32,
-        ];
-        let returns = [WasmEdgeValType::WasmEdge_ValType_I32];
+        let params = [ValType::ExternRef, ValType::I32];
+        let returns = [ValType::I32];
         let result = FunctionTypeContext::create(Some(&params), Some(&returns));
         assert!(result.is_some());
         let func_type = result.unwrap();
@@ -220,7 +229,7 @@ mod test {
         let mut host_func = result.unwrap();
         assert!(!host_func.raw.is_null());
         let host_name = "func-add";
-        imp_obj.add_host_function(host_name, &mut host_func);
+        assert!(imp_obj.add_host_function(host_name, &mut host_func).is_ok());
 
         // Add host table "table"
         let table_limit = WasmEdgeLimit {
@@ -228,12 +237,11 @@ mod test {
             Min: 10,
             Max: 20,
         };
-        let result =
-            TableInstanceContext::create(WasmEdgeRefType::WasmEdge_RefType_FuncRef, table_limit);
+        let result = TableInstanceContext::create(RefType::FuncRef, table_limit);
         assert!(result.is_some());
         let mut host_table = result.unwrap();
         assert!(!host_table.raw.is_null());
-        imp_obj.add_table("table", &mut host_table);
+        assert!(imp_obj.add_table("table", &mut host_table).is_ok());
         assert!(host_table.raw.is_null());
 
         // Add host memory "memory"
@@ -246,75 +254,79 @@ mod test {
         assert!(result.is_some());
         let mut host_memory = result.unwrap();
         assert!(!host_memory.raw.is_null());
-        imp_obj.add_memory("memory", &mut host_memory);
+        assert!(imp_obj.add_memory("memory", &mut host_memory).is_ok());
         assert!(host_memory.raw.is_null());
 
         // Add host global "global_i32": const 666
-        let result = GlobalInstanceContext::create(
-            WasmEdgeValueGenI32(666),
-            WasmEdgeMutability::WasmEdge_Mutability_Const,
-        );
+        let result = GlobalInstanceContext::create(Val::I32(666), Mutability::Const);
         assert!(result.is_some());
         let mut host_global = result.unwrap();
-        imp_obj.add_global("global_i32", &mut host_global);
+        assert!(imp_obj.add_global("global_i32", &mut host_global).is_ok());
         assert!(host_global.raw.is_null());
     }
 
     #[test]
     fn test_context_import_object_create_wasi() {
-        let args = ["arg1", "arg2"];
-        let envs = ["ENV1=VAL1", "ENV2=VAL2", "ENV3=VAL3"];
-        let dirs = [".:."];
-        let preopens = ["apiTestData", "Makefile", "CMakeFiles", "ssvmAPICoreTests"];
+        let mut config = WasiConfig::new();
+        config
+            .args(vec!["arg1", "arg2"])
+            .env("ENV1", "VAL1")
+            .env("ENV2", "VAL2")
+            .env("ENV3", "VAL3")
+            .preopen_dir(".", ".");
 
         // Create WASI
-        let result = ImportObjectContext::create_wasi(
-            Some(&args),
-            Some(&envs),
-            Some(&dirs),
-            Some(&preopens),
-        );
-        assert!(result.is_some());
+        let result = ImportObjectContext::create_wasi(&config);
+        assert!(result.is_ok());
         let imp_obj = result.unwrap();
         assert!(!imp_obj.raw.is_null());
 
-        let result = ImportObjectContext::create_wasi(None, None, None, None);
-        assert!(result.is_some());
+        let result = ImportObjectContext::create_wasi(&WasiConfig::new());
+        assert!(result.is_ok());
         let imp_obj = result.unwrap();
         assert!(!imp_obj.raw.is_null());
     }
 
     #[test]
     fn test_context_import_object_init_wasi_in_vm() {
-        let args = ["arg1", "arg2"];
-        let envs = ["ENV1=VAL1", "ENV2=VAL2", "ENV3=VAL3"];
-        let dirs = [".:."];
-        let preopens = ["apiTestData", "Makefile", "CMakeFiles", "ssvmAPICoreTests"];
+        let mut config = WasiConfig::new();
+        config
+            .args(vec!["arg1", "arg2"])
+            .env("ENV1", "VAL1")
+            .inherit_env()
+            .preopen_dir(".", ".");
 
         // Initialize WASI in VM.
         let mut conf = ConfigureContext::create();
-        conf.add_host_registration(HostRegistration::WasmEdge_HostRegistration_Wasi);
+        conf.add_host_registration(HostRegistration::Wasi);
         let result = VMContext::create(Some(&conf), None);
         assert!(result.is_some());
         let vm = result.unwrap();
         assert!(!vm.raw.is_null());
-        let result = vm.import_object(HostRegistration::WasmEdge_HostRegistration_Wasi);
+        let result = vm.import_object(HostRegistration::Wasi);
         assert!(result.is_some());
         let imp_obj = result.unwrap();
         assert!(!imp_obj.raw.is_null());
-        imp_obj.init_wasi(Some(&args), Some(&envs), Some(&dirs), Some(&preopens));
+        let result = imp_obj.init_wasi(&config);
+        assert!(result.is_ok());
+
+        // Interior NULs are rejected rather than truncated
+        let mut config = WasiConfig::new();
+        config.arg("arg\0");
+        let result = imp_obj.init_wasi(&config);
+        assert!(result.is_err());
     }
 
     #[test]
     fn test_context_import_object_init_process_in_vm() {
         // Initialize wasmedge_process in VM.
         let mut conf = ConfigureContext::create();
-        conf.add_host_registration(HostRegistration::WasmEdge_HostRegistration_WasmEdge_Process);
+        conf.add_host_registration(HostRegistration::WasmEdgeProcess);
         let result = VMContext::create(Some(&conf), None);
         assert!(result.is_some());
         let vm = result.unwrap();
         assert!(!vm.raw.is_null());
-        let result = vm.import_object(HostRegistration::WasmEdge_HostRegistration_WasmEdge_Process);
+        let result = vm.import_object(HostRegistration::WasmEdgeProcess);
         assert!(result.is_some());
         let imp_obj = result.unwrap();
         assert!(!imp_obj.raw.is_null());
@@ -328,9 +340,12 @@ mod test {
         returns: *mut WasmEdgeValue,
     ) -> we_ffi::WasmEdge_Result {
         let params = std::slice::from_raw_parts(params, 2);
-        let val1 = *(WasmEdgeValueGetExternRef(params[0]) as *const ::std::os::raw::c_int);
-        let val2 = WasmEdgeValueGetI32(params[1]);
-        let res = WasmEdgeValueGenI32(val1 + val2);
+        let val1 = match ExternRef::<i32>::try_from(Val::from(params[0])) {
+            Ok(val) => *val,
+            Err(_) => return we_ffi::WasmEdge_Result { Code: 2 },
+        };
+        let val2 = Val::from(params[1]).i32().unwrap_or_default();
+        let res = WasmEdgeValue::from(Val::I32(val1 + val2));
         returns.write(res);
 
         we_ffi::WasmEdge_Result { Code: 0 }
@@ -344,9 +359,12 @@ mod test {
         returns: *mut WasmEdgeValue,
     ) -> we_ffi::WasmEdge_Result {
         let params = std::slice::from_raw_parts(params, 2);
-        let val1 = *(WasmEdgeValueGetExternRef(params[0]) as *const ::std::os::raw::c_int);
-        let val2 = WasmEdgeValueGetI32(params[1]);
-        let res = WasmEdgeValueGenI32(val1 - val2);
+        let val1 = match ExternRef::<i32>::try_from(Val::from(params[0])) {
+            Ok(val) => *val,
+            Err(_) => return we_ffi::WasmEdge_Result { Code: 2 },
+        };
+        let val2 = Val::from(params[1]).i32().unwrap_or_default();
+        let res = WasmEdgeValue::from(Val::I32(val1 - val2));
         returns.write(res);
 
         we_ffi::WasmEdge_Result { Code: 0 }
@@ -360,9 +378,12 @@ mod test {
         returns: *mut WasmEdgeValue,
     ) -> we_ffi::WasmEdge_Result {
         let params = std::slice::from_raw_parts(params, 2);
-        let val1 = *(WasmEdgeValueGetExternRef(params[0]) as *const ::std::os::raw::c_int);
-        let val2 = WasmEdgeValueGetI32(params[1]);
-        let res = WasmEdgeValueGenI32(val1 * val2);
+        let val1 = match ExternRef::<i32>::try_from(Val::from(params[0])) {
+            Ok(val) => *val,
+            Err(_) => return we_ffi::WasmEdge_Result { Code: 2 },
+        };
+        let val2 = Val::from(params[1]).i32().unwrap_or_default();
+        let res = WasmEdgeValue::from(Val::I32(val1 * val2));
         returns.write(res);
 
         we_ffi::WasmEdge_Result { Code: 0 }
@@ -376,9 +397,12 @@ mod test {
         returns: *mut WasmEdgeValue,
     ) -> we_ffi::WasmEdge_Result {
         let params = std::slice::from_raw_parts(params, 2);
-        let val1 = *(WasmEdgeValueGetExternRef(params[0]) as *const ::std::os::raw::c_int);
-        let val2 = WasmEdgeValueGetI32(params[1]);
-        let res = WasmEdgeValueGenI32(val1 / val2);
+        let val1 = match ExternRef::<i32>::try_from(Val::from(params[0])) {
+            Ok(val) => *val,
+            Err(_) => return we_ffi::WasmEdge_Result { Code: 2 },
+        };
+        let val2 = Val::from(params[1]).i32().unwrap_or_default();
+        let res = WasmEdgeValue::from(Val::I32(val1 / val2));
         returns.write(res);
 
         we_ffi::WasmEdge_Result { Code: 0 }
@@ -391,7 +415,7 @@ mod test {
         _params: *const WasmEdgeValue,
         returns: *mut WasmEdgeValue,
     ) -> we_ffi::WasmEdge_Result {
-        let res = WasmEdgeValueGenI32(1234);
+        let res = WasmEdgeValue::from(Val::I32(1234));
         returns.write(res);
 
         we_ffi::WasmEdge_Result { Code: 1 }
@@ -404,7 +428,7 @@ mod test {
         _params: *const WasmEdgeValue,
         returns: *mut WasmEdgeValue,
     ) -> we_ffi::WasmEdge_Result {
-        let res = WasmEdgeValueGenI32(5678);
+        let res = WasmEdgeValue::from(Val::I32(5678));
         returns.write(res);
 
         we_ffi::WasmEdge_Result { Code: 2 }
@@ -5,9 +5,9 @@ use crate::{
         ast::ASTModuleContext, configure::ConfigureContext, import_object::ImportObjectContext,
         statistics::StatisticsContext, store::StoreContext,
     },
-    types::{WasmEdgeString, WasmEdgeValue},
+    types::WasmEdgeString,
+    value::{self, Val},
 };
-use std::mem;
 use std::ptr;
 use wasmedge_sys::ffi as we_ffi;
 
@@ -54,8 +54,7 @@ impl InterpreterContext {
         ast_mod: &ASTModuleContext,
         mod_name: &str,
     ) -> WasmEdgeResult<()> {
-        let mod_name = WasmEdgeString::from_str(mod_name)
-            .expect(format!("Failed to create WasmEdgeString from '{}'", mod_name).as_str());
+        let mod_name = WasmEdgeString::from_bytes(mod_name.as_bytes())?;
         unsafe {
             check(we_ffi::WasmEdge_InterpreterRegisterModule(
                 self.raw,
@@ -81,52 +80,44 @@ impl InterpreterContext {
     }
 
     /// Invoke a WASM function by name.
-    pub fn invoke<'a>(
+    pub fn invoke(
         &self,
         store: &mut StoreContext,
         func_name: &str,
-        params: Option<&[WasmEdgeValue]>,
-        buf: &'a mut [mem::MaybeUninit<WasmEdgeValue>],
-    ) -> WasmEdgeResult<&'a [WasmEdgeValue]> {
-        let func_name = WasmEdgeString::from_str(func_name)
-            .expect(format!("Failed to create WasmEdgeString from '{}'", func_name).as_str());
-        let (len, params) = match params {
-            Some(params) => (params.len(), params.as_ptr()),
-            None => (0, std::ptr::null()),
-        };
+        params: Option<&[Val]>,
+        returns_len: usize,
+    ) -> WasmEdgeResult<Vec<Val>> {
+        let func_name = WasmEdgeString::from_bytes(func_name.as_bytes())?;
+        let params = value::to_raw_vec(params.unwrap_or_default());
+        let mut returns = value::raw_buffer(returns_len);
 
         unsafe {
             check(we_ffi::WasmEdge_InterpreterInvoke(
                 self.raw,
                 store.raw,
                 func_name.raw,
-                params as *const _,
-                len as u32,
-                buf.as_mut_ptr() as *mut _,
-                buf.len() as u32,
+                params.as_ptr(),
+                params.len() as u32,
+                returns.as_mut_ptr(),
+                returns.len() as u32,
             ))?;
-
-            Ok(mem::MaybeUninit::slice_assume_init_ref(&buf[..buf.len()]))
         }
+
+        Ok(value::from_raw_slice(&returns))
     }
 
-    pub fn invoke_registered<'a>(
+    pub fn invoke_registered(
         &self,
         store: &mut StoreContext,
         mod_name: &str,
         func_name: &str,
-        params: Option<&[WasmEdgeValue]>,
-        buf: &'a mut [mem::MaybeUninit<WasmEdgeValue>],
-    ) -> WasmEdgeResult<&'a [WasmEdgeValue]> {
-        let mod_name = WasmEdgeString::from_str(mod_name)
-            .expect(format!("Failed to create WasmEdgeString from '{}'", mod_name).as_str());
-        let func_name = WasmEdgeString::from_str(func_name)
-            .expect(format!("Failed to create WasmEdgeString from '{}'", func_name).as_str());
-
-        let (len, params) = match params {
-            Some(params) => (params.len(), params.as_ptr()),
-            None => (0, std::ptr::null()),
-        };
+        params: Option<&[Val]>,
+        returns_len: usize,
+    ) -> WasmEdgeResult<Vec<Val>> {
+        let mod_name = WasmEdgeString::from_bytes(mod_name.as_bytes())?;
+        let func_name = WasmEdgeString::from_bytes(func_name.as_bytes())?;
+        let params = value::to_raw_vec(params.unwrap_or_default());
+        let mut returns = value::raw_buffer(returns_len);
 
         unsafe {
             check(we_ffi::WasmEdge_InterpreterInvokeRegistered(
@@ -134,14 +125,14 @@ impl InterpreterContext {
                 store.raw,
                 mod_name.raw,
                 func_name.raw,
-                params as *const _,
-                len as u32,
-                buf.as_mut_ptr() as *mut _,
-                buf.len() as u32,
+                params.as_ptr(),
+                params.len() as u32,
+                returns.as_mut_ptr(),
+                returns.len() as u32,
             ))?;
-
-            Ok(mem::MaybeUninit::slice_assume_init_ref(&buf[..buf.len()]))
         }
+
+        Ok(value::from_raw_slice(&returns))
     }
 }
 impl Drop for InterpreterContext {
@@ -157,16 +148,17 @@ mod tests {
         configure::ConfigureContext, import_object::ImportObjectContext, loader::LoaderContext,
         statistics::StatisticsContext, store::StoreContext, validator::Validator,
     };
+    use crate::externref::ExternRef;
     use crate::instance::function::{FunctionTypeContext, HostFunctionContext};
     use crate::types::*;
-    use crate::value::*;
+    use std::convert::TryFrom;
 
     const TPATH: &str = "/root/workspace/wasmedge-ml/wasmedge/tests/data/test.wasm";
 
     #[test]
     fn test_context_interpreter_create() {
         let mut conf = ConfigureContext::create();
-        conf.add_proposal(WasmEdgeProposal::WasmEdge_Proposal_ReferenceTypes);
+        conf.add_proposal(Proposal::ReferenceTypes);
 
         // load and validate file
         let result = load_module(&conf);
@@ -203,7 +195,7 @@ mod tests {
     #[test]
     fn test_context_interpreter_register() {
         let mut conf = ConfigureContext::create();
-        conf.add_proposal(WasmEdgeProposal::WasmEdge_Proposal_ReferenceTypes);
+        conf.add_proposal(Proposal::ReferenceTypes);
 
         // load and validate file
         let result = load_module(&conf);
@@ -261,34 +253,29 @@ mod tests {
         assert!(interp.instantiate(&mut store, &ast_mod).is_ok());
 
         // invoke functions
-        let params = [WasmEdgeValueGenI32(123), WasmEdgeValueGenI32(456)];
-        let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<2>();
-        let result = interp.invoke(&mut store, "func-mul-2", Some(&params), &mut buf);
+        let params = [Val::I32(123), Val::I32(456)];
+        let result = interp.invoke(&mut store, "func-mul-2", Some(&params), 2);
         assert!(result.is_ok());
         let returns = result.unwrap();
-        assert_eq!(246, WasmEdgeValueGetI32(returns[0]));
-        assert_eq!(912, WasmEdgeValueGetI32(returns[1]));
+        assert_eq!(Val::I32(246), returns[0]);
+        assert_eq!(Val::I32(912), returns[1]);
 
         // ? Function type mismatch
-        // let params = [WasmEdgeValueGenI64(123), WasmEdgeValueGenI32(456)];
-        // let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<2>();
-        // let result = interp.invoke(&mut store, "func-mul-2", Some(&params), &mut buf);
+        // let params = [Val::I64(123), Val::I32(456)];
+        // let result = interp.invoke(&mut store, "func-mul-2", Some(&params), 2);
         // assert!(result.is_err());
 
         // ? Function not found
-        // let params = [WasmEdgeValueGenI32(123), WasmEdgeValueGenI32(456)];
-        // let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<2>();
-        // let result = interp.invoke(&mut store, "func-mul-3", Some(&params), &mut buf);
+        // let params = [Val::I32(123), Val::I32(456)];
+        // let result = interp.invoke(&mut store, "func-mul-3", Some(&params), 2);
         // assert!(result.is_err());
 
         // Discard result
-        let params = [WasmEdgeValueGenI32(123), WasmEdgeValueGenI32(456)];
-        let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<0>();
-        let result = interp.invoke(&mut store, "func-mul-2", Some(&params), &mut buf);
+        let params = [Val::I32(123), Val::I32(456)];
+        let result = interp.invoke(&mut store, "func-mul-2", Some(&params), 0);
         assert!(result.is_ok());
-        let params = [WasmEdgeValueGenI32(123), WasmEdgeValueGenI32(456)];
-        let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<1>();
-        let result = interp.invoke(&mut store, "func-mul-2", Some(&params), &mut buf);
+        let params = [Val::I32(123), Val::I32(456)];
+        let result = interp.invoke(&mut store, "func-mul-2", Some(&params), 1);
         assert!(result.is_ok());
 
         // ? Invoke functions call to host functions
@@ -303,64 +290,49 @@ mod tests {
         // let mod_name2 = "error-name";
         let func_name = "func-add";
         // let func_name2 = "func-add2";
-        let mut test_value = 5000;
-        let params = [
-            WasmEdgeValueGenExternRef((&mut test_value) as *mut i32 as *mut std::os::raw::c_void),
-            WasmEdgeValueGenI32(1500),
-        ];
-        let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<1>();
-        let result =
-            interp.invoke_registered(&mut store, mod_name, func_name, Some(&params), &mut buf);
+        let test_value = ExternRef::new(&store, 5000i32);
+        let params = [Val::from(&test_value), Val::I32(1500)];
+        let result = interp.invoke_registered(&mut store, mod_name, func_name, Some(&params), 1);
         assert!(result.is_ok());
         let returns = result.unwrap();
-        assert_eq!(6500i32, WasmEdgeValueGetI32(returns[0]));
+        assert_eq!(Val::I32(6500i32), returns[0]);
 
         // ? Module not found
-        // let result = interp.invoke_registered(&mut store, mod_name2, func_name, &params, &mut buf);
+        // let result = interp.invoke_registered(&mut store, mod_name2, func_name, &params, 1);
         // assert!(result.is_err());
 
         // ? Function not found
-        // let result = interp.invoke_registered(&mut store, mod_name, func_name2, &params, &mut buf);
+        // let result = interp.invoke_registered(&mut store, mod_name, func_name2, &params, 1);
         // assert!(result.is_err());
 
         // Discard result
-        let params = [
-            WasmEdgeValueGenExternRef((&mut test_value) as *mut i32 as *mut std::os::raw::c_void),
-            WasmEdgeValueGenI32(1500),
-        ];
-        let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<0>();
-        let result =
-            interp.invoke_registered(&mut store, mod_name, func_name, Some(&params), &mut buf);
+        let params = [Val::from(&test_value), Val::I32(1500)];
+        let result = interp.invoke_registered(&mut store, mod_name, func_name, Some(&params), 0);
         assert!(result.is_ok());
 
         // Invoke host function to terminate execution
-        let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<1>();
-        let result = interp.invoke_registered(&mut store, "extern", "func-term", None, &mut buf);
+        let result = interp.invoke_registered(&mut store, "extern", "func-term", None, 1);
         assert!(result.is_ok());
 
         // ? Invoke host function to fail execution
-        // let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<1>();
-        // let result = interp.invoke_registered(&mut store, "extern", "func-fail", None, &mut buf);
+        // let result = interp.invoke_registered(&mut store, "extern", "func-fail", None, 1);
         // assert!(result.is_err());
 
         // ? Invoke host function with binding to functions
         // let mod_name = "extern-wrap";
-        // let mut test_value = 1234;
+        // let test_value = ExternRef::new(&store, 1234i32);
         // let params = [
-        //     WasmEdgeValueGenExternRef((&mut test_value) as *mut i32 as *mut std::os::raw::c_void),
-        //     WasmEdgeValueGenI32(1500),
+        //     Val::from(&test_value),
+        //     Val::I32(1500),
         // ];
-        // let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<1>();
         // let result =
-        //     interp.invoke_registered(&mut store, mod_name, "func-sub", Some(&params), &mut buf);
+        //     interp.invoke_registered(&mut store, mod_name, "func-sub", Some(&params), 1);
         // assert!(result.is_ok());
         // let returns = result.unwrap();
-        // assert_eq!(-266i32, WasmEdgeValueGetI32(returns[0]));
-        // let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<1>();
-        // let result = interp.invoke_registered(&mut store, mod_name, "func-term", None, &mut buf);
+        // assert_eq!(Val::I32(-266i32), returns[0]);
+        // let result = interp.invoke_registered(&mut store, mod_name, "func-term", None, 1);
         // assert!(result.is_ok());
-        // let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<1>();
-        // let result = interp.invoke_registered(&mut store, mod_name, "func-fail", None, &mut buf);
+        // let result = interp.invoke_registered(&mut store, mod_name, "func-fail", None, 1);
         // assert!(result.is_err());
 
         // Statistics get instruction count
@@ -400,14 +372,11 @@ mod tests {
     fn create_extern_module(name: &str) -> Option<ImportObjectContext<'_>> {
         // create import object
         let result = ImportObjectContext::create(name, ptr::null_mut());
-        assert!(result.is_some());
+        assert!(result.is_ok());
         let mut imp_obj = result.unwrap();
 
-        let params = [
-            WasmEdgeValType::WasmEdge_ValType_ExternRef,
-            WasmEdgeValType::WasmEdge_ValType_I32,
-        ];
-        let returns = [WasmEdgeValType::WasmEdge_ValType_I32];
+        let params = [ValType::ExternRef, ValType::I32];
+        let returns = [ValType::I32];
         let result = FunctionTypeContext::create(Some(&params), Some(&returns));
         assert!(result.is_some());
         let host_ftype = result.unwrap();
@@ -417,30 +386,30 @@ mod tests {
         let result = HostFunctionContext::create(&host_ftype, Some(extern_add_interp), 0);
         assert!(result.is_some());
         let mut host_func = result.unwrap();
-        imp_obj.add_host_function(host_name, &mut host_func);
+        assert!(imp_obj.add_host_function(host_name, &mut host_func).is_ok());
 
         // add host function "func-sub"
         let host_name = "func-sub";
         let result = HostFunctionContext::create(&host_ftype, Some(extern_sub_interp), 0);
         assert!(result.is_some());
         let mut host_func = result.unwrap();
-        imp_obj.add_host_function(host_name, &mut host_func);
+        assert!(imp_obj.add_host_function(host_name, &mut host_func).is_ok());
 
         // add host function "func-mul"
         let host_name = "func-mul";
         let result = HostFunctionContext::create(&host_ftype, Some(extern_mul_interp), 0);
         assert!(result.is_some());
         let mut host_func = result.unwrap();
-        imp_obj.add_host_function(host_name, &mut host_func);
+        assert!(imp_obj.add_host_function(host_name, &mut host_func).is_ok());
 
         // add host function "func-div"
         let host_name = "func-div";
         let result = HostFunctionContext::create(&host_ftype, Some(extern_div_interp), 0);
         assert!(result.is_some());
         let mut host_func = result.unwrap();
-        imp_obj.add_host_function(host_name, &mut host_func);
+        assert!(imp_obj.add_host_function(host_name, &mut host_func).is_ok());
 
-        let returns = [WasmEdgeValType::WasmEdge_ValType_I32];
+        let returns = [ValType::I32];
         let result = FunctionTypeContext::create(None, Some(&returns));
         assert!(result.is_some());
         let host_ftype = result.unwrap();
@@ -451,14 +420,14 @@ mod tests {
         let result = HostFunctionContext::create(&host_ftype, Some(extern_term_interp), 0);
         assert!(result.is_some());
         let mut host_func = result.unwrap();
-        imp_obj.add_host_function(host_name, &mut host_func);
+        assert!(imp_obj.add_host_function(host_name, &mut host_func).is_ok());
 
         // add host function "func-fail"
         let host_name = "func-fail";
         let result = HostFunctionContext::create(&host_ftype, Some(extern_fail_interp), 0);
         assert!(result.is_some());
         let mut host_func = result.unwrap();
-        imp_obj.add_host_function(host_name, &mut host_func);
+        assert!(imp_obj.add_host_function(host_name, &mut host_func).is_ok());
 
         Some(imp_obj)
     }
@@ -471,9 +440,12 @@ mod tests {
         returns: *mut WasmEdgeValue,
     ) -> we_ffi::WasmEdge_Result {
         let params = std::slice::from_raw_parts(params, 2);
-        let val1 = *(WasmEdgeValueGetExternRef(params[0]) as *const ::std::os::raw::c_int);
-        let val2 = WasmEdgeValueGetI32(params[1]);
-        let res = WasmEdgeValueGenI32(val1 + val2);
+        let val1 = match ExternRef::<i32>::try_from(Val::from(params[0])) {
+            Ok(val) => *val,
+            Err(_) => return we_ffi::WasmEdge_Result { Code: 2 },
+        };
+        let val2 = Val::from(params[1]).i32().unwrap_or_default();
+        let res = WasmEdgeValue::from(Val::I32(val1 + val2));
         returns.write(res);
 
         we_ffi::WasmEdge_Result { Code: 0 }
@@ -487,9 +459,12 @@ mod tests {
         returns: *mut WasmEdgeValue,
     ) -> we_ffi::WasmEdge_Result {
         let params = std::slice::from_raw_parts(params, 2);
-        let val1 = *(WasmEdgeValueGetExternRef(params[0]) as *const ::std::os::raw::c_int);
-        let val2 = WasmEdgeValueGetI32(params[1]);
-        let res = WasmEdgeValueGenI32(val1 - val2);
+        let val1 = match ExternRef::<i32>::try_from(Val::from(params[0])) {
+            Ok(val) => *val,
+            Err(_) => return we_ffi::WasmEdge_Result { Code: 2 },
+        };
+        let val2 = Val::from(params[1]).i32().unwrap_or_default();
+        let res = WasmEdgeValue::from(Val::I32(val1 - val2));
         returns.write(res);
 
         we_ffi::WasmEdge_Result { Code: 0 }
@@ -503,9 +478,12 @@ mod tests {
         returns: *mut WasmEdgeValue,
     ) -> we_ffi::WasmEdge_Result {
         let params = std::slice::from_raw_parts(params, 2);
-        let val1 = *(WasmEdgeValueGetExternRef(params[0]) as *const ::std::os::raw::c_int);
-        let val2 = WasmEdgeValueGetI32(params[1]);
-        let res = WasmEdgeValueGenI32(val1 * val2);
+        let val1 = match ExternRef::<i32>::try_from(Val::from(params[0])) {
+            Ok(val) => *val,
+            Err(_) => return we_ffi::WasmEdge_Result { Code: 2 },
+        };
+        let val2 = Val::from(params[1]).i32().unwrap_or_default();
+        let res = WasmEdgeValue::from(Val::I32(val1 * val2));
         returns.write(res);
 
         we_ffi::WasmEdge_Result { Code: 0 }
@@ -519,9 +497,12 @@ mod tests {
         returns: *mut WasmEdgeValue,
     ) -> we_ffi::WasmEdge_Result {
         let params = std::slice::from_raw_parts(params, 2);
-        let val1 = *(WasmEdgeValueGetExternRef(params[0]) as *const ::std::os::raw::c_int);
-        let val2 = WasmEdgeValueGetI32(params[1]);
-        let res = WasmEdgeValueGenI32(val1 / val2);
+        let val1 = match ExternRef::<i32>::try_from(Val::from(params[0])) {
+            Ok(val) => *val,
+            Err(_) => return we_ffi::WasmEdge_Result { Code: 2 },
+        };
+        let val2 = Val::from(params[1]).i32().unwrap_or_default();
+        let res = WasmEdgeValue::from(Val::I32(val1 / val2));
         returns.write(res);
 
         we_ffi::WasmEdge_Result { Code: 0 }
@@ -534,7 +515,7 @@ mod tests {
         _params: *const WasmEdgeValue,
         returns: *mut WasmEdgeValue,
     ) -> we_ffi::WasmEdge_Result {
-        let res = WasmEdgeValueGenI32(1234);
+        let res = WasmEdgeValue::from(Val::I32(1234));
         returns.write(res);
 
         we_ffi::WasmEdge_Result { Code: 1 }
@@ -547,7 +528,7 @@ mod tests {
         _params: *const WasmEdgeValue,
         returns: *mut WasmEdgeValue,
     ) -> we_ffi::WasmEdge_Result {
-        let res = WasmEdgeValueGenI32(5678);
+        let res = WasmEdgeValue::from(Val::I32(5678));
         returns.write(res);
 
         we_ffi::WasmEdge_Result { Code: 2 }
@@ -1,7 +1,18 @@
-use crate::context::{ast::ASTModuleContext, configure::ConfigureContext};
+use crate::binary;
+use crate::context::{
+    aot::ArtifactInfo,
+    ast::{ASTModuleContext, ModuleSource},
+    configure::ConfigureContext,
+};
 use crate::error::{WasmEdgeError, WasmEdgeResult};
-use crate::utils::{check, path_to_cstring};
+use crate::utils::{buffer_len, check, path_to_cstring, TempDir};
+use memmap2::Mmap;
+use std::env::consts::{DLL_EXTENSION, DLL_SUFFIX};
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::Read;
 use std::path::Path;
+use std::sync::Arc;
 use wasmedge_sys::ffi as we_ffi;
 
 pub struct LoaderContext {
@@ -16,43 +27,176 @@ impl LoaderContext {
         }
     }
 
+    /// Parses a module or a native artifact from a file, telling them apart by content.
+    ///
+    /// A module is streamed from the file once, and the bytes are kept for introspection; use
+    /// `parse_from_mmap` to avoid holding a copy. An artifact is parsed by WasmEdge from its path
+    /// without being read here, and introspection uses the module embedded in it, if any.
     pub fn parse_from_file<P: AsRef<Path>>(
         &mut self,
         ast_mod: &mut ASTModuleContext,
         path: P,
     ) -> WasmEdgeResult<()> {
-        if !path.as_ref().exists() {
-            return Err(WasmEdgeError {
-                code: 0x20 as usize,
-                message: format!(
-                    "Loading failed: invalid file path: {}",
-                    path.as_ref().to_string_lossy().into_owned()
-                ),
+        let mut file = match File::open(path.as_ref()) {
+            Ok(file) => file,
+            Err(e) => {
+                return Err(WasmEdgeError::Load {
+                    code: 0x20,
+                    message: format!(
+                        "Loading failed: invalid file path: {}: {}",
+                        path.as_ref().display(),
+                        e
+                    ),
+                })
+            }
+        };
+        let mut magic = [0; 4];
+        match file.read_exact(&mut magic).is_ok() && magic == binary::MAGIC {
+            true => self.parse_from_reader(ast_mod, (&magic[..]).chain(file)),
+            false => self.parse_artifact_file(ast_mod, path.as_ref()),
+        }
+    }
+
+    pub fn parse_from_buffer(
+        &mut self,
+        ast_mod: &mut ASTModuleContext,
+        buf: &[u8],
+    ) -> WasmEdgeResult<()> {
+        self.parse(ast_mod, buf)?;
+        ast_mod.source = Some(ModuleSource::Buffer(Arc::from(buf)));
+        Ok(())
+    }
+
+    /// Parses a module from a reader, e.g. a socket or a decompressing stream.
+    ///
+    /// Modules larger than 4 GiB are rejected rather than truncated.
+    pub fn parse_from_reader<R: Read>(
+        &mut self,
+        ast_mod: &mut ASTModuleContext,
+        reader: R,
+    ) -> WasmEdgeResult<()> {
+        // Reading one byte past the limit tells a module of exactly 4 GiB from a larger one
+        let mut buf = vec![];
+        if let Err(e) = reader.take(u64::from(u32::MAX) + 1).read_to_end(&mut buf) {
+            return Err(WasmEdgeError::Load {
+                code: 0x21,
+                message: format!("Loading failed: read error: {}", e),
+            });
+        }
+        self.parse(ast_mod, &buf)?;
+        ast_mod.source = Some(ModuleSource::Buffer(Arc::from(buf)));
+        Ok(())
+    }
+
+    /// Parses a module from a memory-mapped file, without copying it into memory first.
+    ///
+    /// # Safety
+    ///
+    /// The mapping is kept by `ast_mod` for introspection. The caller must ensure that the file
+    /// is not truncated or modified, by this or any other process, while `ast_mod` is alive;
+    /// otherwise reading the module is undefined behavior.
+    pub unsafe fn parse_from_mmap<P: AsRef<Path>>(
+        &mut self,
+        ast_mod: &mut ASTModuleContext,
+        path: P,
+    ) -> WasmEdgeResult<()> {
+        let map_error = |e: std::io::Error| WasmEdgeError::Load {
+            code: 0x21,
+            message: format!(
+                "Loading failed: failed to map {}: {}",
+                path.as_ref().display(),
+                e
+            ),
+        };
+        let file = File::open(path.as_ref()).map_err(map_error)?;
+        let map = Mmap::map(&file).map_err(map_error)?;
+        self.parse(ast_mod, &map)?;
+        ast_mod.source = Some(ModuleSource::Mapped(Arc::new(map)));
+        Ok(())
+    }
+
+    /// Parses a native artifact produced by `Compiler::compile_buffer`.
+    ///
+    /// WasmEdge only loads native artifacts from files, so the artifact is written to a private
+    /// temporary directory that is removed once it is loaded. Introspection uses the module
+    /// embedded in the artifact, and is unavailable if there is none.
+    pub fn parse_from_aot_buffer(
+        &mut self,
+        ast_mod: &mut ASTModuleContext,
+        artifact: &[u8],
+    ) -> WasmEdgeResult<()> {
+        let dir = TempDir::new()?;
+        let path = dir.create_file(&format!("module{}", DLL_SUFFIX), artifact)?;
+        self.parse_path(ast_mod, &path)?;
+        ast_mod.source = ArtifactInfo::from_bytes(artifact)
+            .ok()
+            .flatten()
+            .and_then(|info| {
+                info.wasm()
+                    .map(|wasm| ModuleSource::Buffer(Arc::from(wasm)))
             });
+        Ok(())
+    }
+
+    /// Parses a module in the WebAssembly text format.
+    ///
+    /// Syntax errors are reported as `WasmEdgeError::Text` with the line and column in `text`.
+    #[cfg(feature = "wat")]
+    pub fn parse_from_wat(
+        &mut self,
+        ast_mod: &mut ASTModuleContext,
+        text: &str,
+    ) -> WasmEdgeResult<()> {
+        let buf = crate::wat::wat_to_wasm(text)?;
+        self.parse_from_buffer(ast_mod, &buf)
+    }
+
+    fn parse_artifact_file(
+        &mut self,
+        ast_mod: &mut ASTModuleContext,
+        path: &Path,
+    ) -> WasmEdgeResult<()> {
+        match path.extension() == Some(OsStr::new(DLL_EXTENSION)) {
+            true => self.parse_path(ast_mod, path)?,
+            false => {
+                // WasmEdge only loads a file as an artifact if it is named like one
+                let dir = TempDir::new()?;
+                let link = dir.path().join(format!("module{}", DLL_SUFFIX));
+                if let Err(e) = link_artifact(path, &link) {
+                    return Err(WasmEdgeError::from(format!(
+                        "Failed to link the artifact '{}': {}",
+                        path.display(),
+                        e
+                    )));
+                }
+                self.parse_path(ast_mod, &link)?;
+            }
         }
+        ast_mod.source = ArtifactInfo::read(path).ok().flatten().and_then(|info| {
+            info.wasm()
+                .map(|wasm| ModuleSource::Buffer(Arc::from(wasm)))
+        });
+        Ok(())
+    }
 
-        let path = path_to_cstring(path.as_ref())?;
+    fn parse_path(&mut self, ast_mod: &mut ASTModuleContext, path: &Path) -> WasmEdgeResult<()> {
+        let c_path = path_to_cstring(path)?;
         unsafe {
             check(we_ffi::WasmEdge_LoaderParseFromFile(
                 self.raw,
                 &mut ast_mod.raw,
-                path.as_ptr(),
-            ))?;
+                c_path.as_ptr(),
+            ))
         }
-        Ok(())
     }
 
-    pub fn parse_from_buffer(
-        &mut self,
-        ast_mod: &mut ASTModuleContext,
-        buf: &[u8],
-    ) -> WasmEdgeResult<()> {
+    fn parse(&mut self, ast_mod: &mut ASTModuleContext, buf: &[u8]) -> WasmEdgeResult<()> {
         unsafe {
             check(we_ffi::WasmEdge_LoaderParseFromBuffer(
                 self.raw,
                 &mut ast_mod.raw,
                 buf.as_ptr(),
-                buf.len() as u32,
+                buffer_len(buf.len())?,
             ))
         }
     }
@@ -65,17 +209,28 @@ impl Drop for LoaderContext {
     }
 }
 
+/// Makes the artifact at `path` available as `link`, without copying it where the platform
+/// allows.
+#[cfg(unix)]
+fn link_artifact(path: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(std::fs::canonicalize(path)?, link)
+}
+#[cfg(not(unix))]
+fn link_artifact(path: &Path, link: &Path) -> std::io::Result<()> {
+    std::fs::copy(path, link).map(|_| ())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{context::ast::ASTModuleContext, types::WasmEdgeProposal};
+    use crate::{context::ast::ASTModuleContext, error::ErrorPhase, types::Proposal};
 
     const TPATH: &str = "/root/workspace/wasmedge-ml/wasmedge/tests/data/test.wasm";
 
     #[test]
     fn test_loader_create() {
         let mut conf = ConfigureContext::create();
-        conf.add_proposal(WasmEdgeProposal::WasmEdge_Proposal_ReferenceTypes);
+        conf.add_proposal(Proposal::ReferenceTypes);
         let loader = LoaderContext::create(&conf);
         assert!(loader.is_some());
     }
@@ -83,7 +238,7 @@ mod tests {
     #[test]
     fn test_loader_parse_from_file() {
         let mut conf = ConfigureContext::create();
-        conf.add_proposal(WasmEdgeProposal::WasmEdge_Proposal_ReferenceTypes);
+        conf.add_proposal(Proposal::ReferenceTypes);
         let result = LoaderContext::create(&conf);
         assert!(result.is_some());
         let mut loader = result.unwrap();
@@ -92,16 +247,20 @@ mod tests {
         let result = loader.parse_from_file(&mut ast_mod, TPATH);
         assert!(result.is_ok());
         assert!(!ast_mod.raw.is_null());
+        assert_eq!(ast_mod.bytes().unwrap(), std::fs::read(TPATH).unwrap());
 
         let mut ast_mod = ASTModuleContext::default();
         let result = loader.parse_from_file(&mut ast_mod, "file");
         assert!(result.is_err());
+        let err = result.unwrap_err();
+        assert_eq!(err.phase(), ErrorPhase::Load);
+        assert_eq!(err.code(), Some(0x20));
     }
 
     #[test]
     fn test_loader_parse_from_buffer() {
         let mut conf = ConfigureContext::create();
-        conf.add_proposal(WasmEdgeProposal::WasmEdge_Proposal_ReferenceTypes);
+        conf.add_proposal(Proposal::ReferenceTypes);
         let result = LoaderContext::create(&conf);
         assert!(result.is_some());
         let mut loader = result.unwrap();
@@ -114,4 +273,105 @@ mod tests {
         assert!(loader.parse_from_buffer(&mut ast_mod, &buf).is_ok());
         assert!(!ast_mod.raw.is_null())
     }
+
+    #[cfg(feature = "wat")]
+    #[test]
+    fn test_loader_parse_from_wat() {
+        let conf = ConfigureContext::create();
+        let mut loader = LoaderContext::create(&conf).unwrap();
+
+        let mut ast_mod = ASTModuleContext::default();
+        let text = r#"(module (func (export "add") (param i32 i32) (result i32)
+            local.get 0
+            local.get 1
+            i32.add))"#;
+        assert!(loader.parse_from_wat(&mut ast_mod, text).is_ok());
+        assert!(!ast_mod.raw.is_null());
+        let exports: Vec<_> = ast_mod.exports().unwrap().collect();
+        assert_eq!(exports.len(), 1);
+        assert_eq!(exports[0].name, "add");
+
+        let mut ast_mod = ASTModuleContext::default();
+        let result = loader.parse_from_wat(&mut ast_mod, "(module\n  (func (param i33)))");
+        assert!(matches!(result, Err(WasmEdgeError::Text { line: 2, .. })));
+        assert!(ast_mod.raw.is_null());
+    }
+
+    #[test]
+    fn test_loader_parse_from_reader() {
+        let conf = ConfigureContext::create();
+        let mut loader = LoaderContext::create(&conf).unwrap();
+
+        let file = std::fs::File::open(TPATH).unwrap();
+        let mut ast_mod = ASTModuleContext::default();
+        assert!(loader.parse_from_reader(&mut ast_mod, file).is_ok());
+        assert!(!ast_mod.raw.is_null());
+        assert_eq!(ast_mod.bytes().unwrap(), std::fs::read(TPATH).unwrap());
+
+        let buf = std::fs::read(TPATH).unwrap();
+        let mut ast_mod = ASTModuleContext::default();
+        let reader = std::io::Cursor::new(&buf[..buf.len() / 2]);
+        let result = loader.parse_from_reader(&mut ast_mod, reader);
+        assert_eq!(result.unwrap_err().phase(), ErrorPhase::Load);
+    }
+
+    #[test]
+    fn test_loader_parse_from_mmap() {
+        let conf = ConfigureContext::create();
+        let mut loader = LoaderContext::create(&conf).unwrap();
+
+        let mut ast_mod = ASTModuleContext::default();
+        assert!(unsafe { loader.parse_from_mmap(&mut ast_mod, TPATH) }.is_ok());
+        assert!(!ast_mod.raw.is_null());
+        assert!(ast_mod.exports().unwrap().count() > 0);
+
+        let mut ast_mod = ASTModuleContext::default();
+        let result = unsafe { loader.parse_from_mmap(&mut ast_mod, "file") };
+        assert!(matches!(
+            result,
+            Err(WasmEdgeError::Load { code: 0x21, .. })
+        ));
+    }
+
+    #[test]
+    fn test_loader_parse_from_aot_buffer() {
+        use crate::context::aot::Compiler;
+
+        let wasm = std::fs::read(TPATH).unwrap();
+        let mut compiler = Compiler::create(ConfigureContext::create()).unwrap();
+        let artifact = compiler.compile_buffer(&wasm).unwrap();
+
+        let conf = ConfigureContext::create();
+        let mut loader = LoaderContext::create(&conf).unwrap();
+        let mut ast_mod = ASTModuleContext::default();
+        assert!(loader
+            .parse_from_aot_buffer(&mut ast_mod, &artifact)
+            .is_ok());
+        assert!(!ast_mod.raw.is_null());
+        // Introspection reads the embedded module rather than the native code
+        assert_eq!(ast_mod.bytes().unwrap(), &wasm[..]);
+        assert!(ast_mod.exports().is_ok());
+
+        let mut ast_mod = ASTModuleContext::default();
+        assert!(loader
+            .parse_from_aot_buffer(&mut ast_mod, &artifact[..16])
+            .is_err());
+
+        // A file is parsed as an artifact by its content, whatever its name
+        let dir = TempDir::new().unwrap();
+        let path = dir.create_file("module.wasm", &artifact).unwrap();
+        let mut ast_mod = ASTModuleContext::default();
+        assert!(loader.parse_from_file(&mut ast_mod, &path).is_ok());
+        assert!(!ast_mod.raw.is_null());
+        assert_eq!(ast_mod.bytes().unwrap(), &wasm[..]);
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn test_loader_buffer_len() {
+        assert_eq!(buffer_len(u32::MAX as usize).unwrap(), u32::MAX);
+        let err = buffer_len(u32::MAX as usize + 1).unwrap_err();
+        assert_eq!(err.phase(), ErrorPhase::Crate);
+        assert!(err.message().contains("4 GiB"));
+    }
 }
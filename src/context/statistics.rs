@@ -1,5 +1,8 @@
 use crate::context::vm::VMContext;
+use crate::cost::CostTable;
+use crate::error::WasmEdgeResult;
 use std::marker::PhantomData;
+use std::time::{Duration, Instant};
 use wasmedge_sys::ffi as we_ffi;
 
 pub struct StatisticsContext<'vm> {
@@ -30,6 +33,13 @@ impl<'vm> StatisticsContext<'vm> {
         }
     }
 
+    /// Sets the cost of each instruction from a validated `CostTable`.
+    pub fn set_costs(&mut self, table: &CostTable) -> WasmEdgeResult<()> {
+        let mut costs = table.to_array()?;
+        self.set_cost_table(&mut costs);
+        Ok(())
+    }
+
     pub fn set_cost_limit(&mut self, limit: u64) {
         unsafe { we_ffi::WasmEdge_StatisticsSetCostLimit(self.raw, limit) }
     }
@@ -45,6 +55,15 @@ impl<'vm> StatisticsContext<'vm> {
     pub fn get_total_cost(&self) -> usize {
         unsafe { we_ffi::WasmEdge_StatisticsGetTotalCost(self.raw) as usize }
     }
+
+    /// Records the cumulative counters, to be diffed with a later snapshot.
+    pub fn snapshot(&self) -> StatisticsSnapshot {
+        StatisticsSnapshot {
+            instr_count: unsafe { we_ffi::WasmEdge_StatisticsGetInstrCount(self.raw) },
+            total_cost: unsafe { we_ffi::WasmEdge_StatisticsGetTotalCost(self.raw) },
+            taken_at: Instant::now(),
+        }
+    }
 }
 impl<'vm> Drop for StatisticsContext<'vm> {
     fn drop(&mut self) {
@@ -57,3 +76,41 @@ impl<'vm> Drop for StatisticsContext<'vm> {
         }
     }
 }
+
+/// The counters of a `StatisticsContext` at one point in time.
+///
+/// WasmEdge only keeps cumulative counters, so the statistics of one call are the difference of
+/// the snapshots taken around it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatisticsSnapshot {
+    pub instr_count: u64,
+    pub total_cost: u64,
+    pub taken_at: Instant,
+}
+impl StatisticsSnapshot {
+    /// A snapshot with no executed instructions, taken now.
+    pub(crate) fn empty() -> Self {
+        StatisticsSnapshot {
+            instr_count: 0,
+            total_cost: 0,
+            taken_at: Instant::now(),
+        }
+    }
+
+    /// Returns what was executed between `earlier` and this snapshot.
+    pub fn since(&self, earlier: &StatisticsSnapshot) -> ExecutionReport {
+        ExecutionReport {
+            instr_count: self.instr_count.saturating_sub(earlier.instr_count),
+            cost: self.total_cost.saturating_sub(earlier.total_cost),
+            elapsed: self.taken_at.saturating_duration_since(earlier.taken_at),
+        }
+    }
+}
+
+/// The instructions, cost and wall time of one or more calls.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExecutionReport {
+    pub instr_count: u64,
+    pub cost: u64,
+    pub elapsed: Duration,
+}
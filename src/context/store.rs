@@ -1,13 +1,13 @@
 use crate::{
     context::vm::VMContext,
     error::{WasmEdgeError, WasmEdgeResult},
+    externref,
     instance::{
         function::FunctionInstanceContext, global::GlobalInstanceContext,
         memory::MemoryInstanceContext, table::TableInstanceContext,
     },
-    types::WasmEdgeString,
+    types::{WasmEdgeStr, WasmEdgeString},
 };
-use std::ffi::CStr;
 use std::marker::PhantomData;
 use std::mem;
 use wasmedge_sys::ffi as we_ffi;
@@ -27,12 +27,14 @@ impl<'vm> StoreContext<'vm> {
     }
 
     pub fn find_function(&self, func_name: &str) -> Option<FunctionInstanceContext> {
-        let func_name = WasmEdgeString::from_str(func_name)
-            .expect(format!("Failed to create WasmEdgeString from '{}'", func_name).as_str());
+        let func_name = WasmEdgeString::from_bytes(func_name.as_bytes()).ok()?;
         let raw = unsafe { we_ffi::WasmEdge_StoreFindFunction(self.raw, func_name.raw) };
         match raw.is_null() {
             true => None,
-            false => Some(FunctionInstanceContext { raw }),
+            false => Some(FunctionInstanceContext {
+                raw,
+                _marker: PhantomData,
+            }),
         }
     }
 
@@ -41,22 +43,22 @@ impl<'vm> StoreContext<'vm> {
         mod_name: &str,
         func_name: &str,
     ) -> Option<FunctionInstanceContext> {
-        let mod_name = WasmEdgeString::from_str(mod_name)
-            .expect(format!("Failed to create WasmEdgeString from '{}'", mod_name).as_str());
-        let func_name = WasmEdgeString::from_str(func_name)
-            .expect(format!("Failed to create WasmEdgeString from '{}'", func_name).as_str());
+        let mod_name = WasmEdgeString::from_bytes(mod_name.as_bytes()).ok()?;
+        let func_name = WasmEdgeString::from_bytes(func_name.as_bytes()).ok()?;
         let raw = unsafe {
             we_ffi::WasmEdge_StoreFindFunctionRegistered(self.raw, mod_name.raw, func_name.raw)
         };
         match raw.is_null() {
             true => None,
-            false => Some(FunctionInstanceContext { raw }),
+            false => Some(FunctionInstanceContext {
+                raw,
+                _marker: PhantomData,
+            }),
         }
     }
 
     pub fn find_table(&self, table_name: &str) -> Option<TableInstanceContext> {
-        let table_name = WasmEdgeString::from_str(table_name)
-            .expect(format!("Failed to create WasmEdgeString from '{}'", table_name).as_str());
+        let table_name = WasmEdgeString::from_bytes(table_name.as_bytes()).ok()?;
         let raw = unsafe { we_ffi::WasmEdge_StoreFindTable(self.raw, table_name.raw) };
         match raw.is_null() {
             true => None,
@@ -73,10 +75,8 @@ impl<'vm> StoreContext<'vm> {
         mod_name: &str,
         table_name: &str,
     ) -> Option<TableInstanceContext> {
-        let mod_name = WasmEdgeString::from_str(mod_name)
-            .expect(format!("Failed to create WasmEdgeString from '{}'", mod_name).as_str());
-        let table_name = WasmEdgeString::from_str(table_name)
-            .expect(format!("Failed to create WasmEdgeString from '{}'", table_name).as_str());
+        let mod_name = WasmEdgeString::from_bytes(mod_name.as_bytes()).ok()?;
+        let table_name = WasmEdgeString::from_bytes(table_name.as_bytes()).ok()?;
         let raw = unsafe {
             we_ffi::WasmEdge_StoreFindTableRegistered(self.raw, mod_name.raw, table_name.raw)
         };
@@ -91,8 +91,7 @@ impl<'vm> StoreContext<'vm> {
     }
 
     pub fn find_memory(&self, mem_name: &str) -> Option<MemoryInstanceContext> {
-        let mem_name = WasmEdgeString::from_str(mem_name)
-            .expect(format!("Failed to create WasmEdgeString from '{}'", mem_name).as_str());
+        let mem_name = WasmEdgeString::from_bytes(mem_name.as_bytes()).ok()?;
         let raw = unsafe { we_ffi::WasmEdge_StoreFindMemory(self.raw, mem_name.raw) };
         match raw.is_null() {
             true => None,
@@ -109,10 +108,8 @@ impl<'vm> StoreContext<'vm> {
         mod_name: &str,
         mem_name: &str,
     ) -> Option<MemoryInstanceContext> {
-        let mod_name = WasmEdgeString::from_str(mod_name)
-            .expect(format!("Failed to create WasmEdgeString from '{}'", mod_name).as_str());
-        let mem_name = WasmEdgeString::from_str(mem_name)
-            .expect(format!("Failed to create WasmEdgeString from '{}'", mem_name).as_str());
+        let mod_name = WasmEdgeString::from_bytes(mod_name.as_bytes()).ok()?;
+        let mem_name = WasmEdgeString::from_bytes(mem_name.as_bytes()).ok()?;
         let raw = unsafe {
             we_ffi::WasmEdge_StoreFindMemoryRegistered(self.raw, mod_name.raw, mem_name.raw)
         };
@@ -127,8 +124,7 @@ impl<'vm> StoreContext<'vm> {
     }
 
     pub fn find_global(&self, global_name: &str) -> Option<GlobalInstanceContext> {
-        let global_name = WasmEdgeString::from_str(global_name)
-            .expect(format!("Failed to create WasmEdgeString from '{}'", global_name).as_str());
+        let global_name = WasmEdgeString::from_bytes(global_name.as_bytes()).ok()?;
         let raw = unsafe { we_ffi::WasmEdge_StoreFindGlobal(self.raw, global_name.raw) };
         match raw.is_null() {
             true => None,
@@ -145,10 +141,8 @@ impl<'vm> StoreContext<'vm> {
         mod_name: &str,
         global_name: &str,
     ) -> Option<GlobalInstanceContext> {
-        let mod_name = WasmEdgeString::from_str(mod_name)
-            .expect(format!("Failed to create WasmEdgeString from '{}'", mod_name).as_str());
-        let global_name = WasmEdgeString::from_str(global_name)
-            .expect(format!("Failed to create WasmEdgeString from '{}'", global_name).as_str());
+        let mod_name = WasmEdgeString::from_bytes(mod_name.as_bytes()).ok()?;
+        let global_name = WasmEdgeString::from_bytes(global_name.as_bytes()).ok()?;
         let raw = unsafe {
             we_ffi::WasmEdge_StoreFindGlobalRegistered(self.raw, mod_name.raw, global_name.raw)
         };
@@ -185,8 +179,8 @@ impl<'vm> StoreContext<'vm> {
                 };
                 let mut names = vec![];
                 for s in s_vec {
-                    let slice = unsafe { CStr::from_ptr(s.Buf as *const _) };
-                    names.push(slice.to_string_lossy().into_owned());
+                    let name = unsafe { WasmEdgeStr::from_raw(*s) };
+                    names.push(name.to_string_lossy().into_owned());
                 }
 
                 Ok((len as usize, names))
@@ -199,8 +193,11 @@ impl<'vm> StoreContext<'vm> {
     }
 
     pub fn list_function_registered_len(&self, mod_name: &str) -> usize {
-        let mod_name = WasmEdgeString::from_str(mod_name)
-            .expect(format!("Failed to create WasmEdgeString from '{}'", mod_name).as_str());
+        let mod_name = match WasmEdgeString::from_bytes(mod_name.as_bytes()) {
+            Ok(mod_name) => mod_name,
+            // No module can be registered under a name WasmEdge cannot represent
+            Err(_) => return 0,
+        };
         unsafe {
             we_ffi::WasmEdge_StoreListFunctionRegisteredLength(self.raw, mod_name.raw) as usize
         }
@@ -214,9 +211,7 @@ impl<'vm> StoreContext<'vm> {
         let max_len = self.list_function_registered_len(mod_name);
         match 0 < func_names.len() && func_names.len() <= max_len {
             true => {
-                let mod_name = WasmEdgeString::from_str(mod_name).expect(
-                    format!("Failed to create WasmEdgeString from '{}'", mod_name).as_str(),
-                );
+                let mod_name = WasmEdgeString::from_bytes(mod_name.as_bytes())?;
                 let len = unsafe {
                     we_ffi::WasmEdge_StoreListFunctionRegistered(
                         self.raw,
@@ -230,8 +225,8 @@ impl<'vm> StoreContext<'vm> {
                 };
                 let mut names = vec![];
                 for s in s_vec {
-                    let slice = unsafe { CStr::from_ptr(s.Buf as *const _) };
-                    names.push(slice.to_string_lossy().into_owned());
+                    let name = unsafe { WasmEdgeStr::from_raw(*s) };
+                    names.push(name.to_string_lossy().into_owned());
                 }
 
                 Ok((len as usize, names))
@@ -266,8 +261,8 @@ impl<'vm> StoreContext<'vm> {
                 };
                 let mut names = vec![];
                 for s in s_vec {
-                    let slice = unsafe { CStr::from_ptr(s.Buf as *const _) };
-                    names.push(slice.to_string_lossy().into_owned());
+                    let name = unsafe { WasmEdgeStr::from_raw(*s) };
+                    names.push(name.to_string_lossy().into_owned());
                 }
 
                 Ok((len as usize, names))
@@ -280,8 +275,10 @@ impl<'vm> StoreContext<'vm> {
     }
 
     pub fn list_table_registered_len(&self, mod_name: &str) -> usize {
-        let mod_name = WasmEdgeString::from_str(mod_name)
-            .expect(format!("Failed to create WasmEdgeString from '{}'", mod_name).as_str());
+        let mod_name = match WasmEdgeString::from_bytes(mod_name.as_bytes()) {
+            Ok(mod_name) => mod_name,
+            Err(_) => return 0,
+        };
         unsafe { we_ffi::WasmEdge_StoreListTableRegisteredLength(self.raw, mod_name.raw) as usize }
     }
 
@@ -293,9 +290,7 @@ impl<'vm> StoreContext<'vm> {
         let max_len = self.list_global_registered_len(mod_name);
         match 0 < table_names.len() && table_names.len() <= max_len {
             true => {
-                let mod_name = WasmEdgeString::from_str(mod_name).expect(
-                    format!("Failed to create WasmEdgeString from '{}'", mod_name).as_str(),
-                );
+                let mod_name = WasmEdgeString::from_bytes(mod_name.as_bytes())?;
                 let len = unsafe {
                     we_ffi::WasmEdge_StoreListTableRegistered(
                         self.raw,
@@ -309,8 +304,8 @@ impl<'vm> StoreContext<'vm> {
                 };
                 let mut names = vec![];
                 for s in s_vec {
-                    let slice = unsafe { CStr::from_ptr(s.Buf as *const _) };
-                    names.push(slice.to_string_lossy().into_owned());
+                    let name = unsafe { WasmEdgeStr::from_raw(*s) };
+                    names.push(name.to_string_lossy().into_owned());
                 }
 
                 Ok((len as usize, names))
@@ -345,10 +340,8 @@ impl<'vm> StoreContext<'vm> {
                 };
                 let mut names = vec![];
                 for s in s_vec {
-                    // let x = unsafe { std::ffi::CString::from_raw(s.Buf as *mut _) };
-                    // names.push(x.to_string_lossy().into_owned());
-                    let slice = unsafe { CStr::from_ptr(s.Buf as *const _) };
-                    names.push(slice.to_string_lossy().into_owned());
+                    let name = unsafe { WasmEdgeStr::from_raw(*s) };
+                    names.push(name.to_string_lossy().into_owned());
                 }
 
                 Ok((len as usize, names))
@@ -361,8 +354,10 @@ impl<'vm> StoreContext<'vm> {
     }
 
     pub fn list_global_registered_len(&self, mod_name: &str) -> usize {
-        let mod_name = WasmEdgeString::from_str(mod_name)
-            .expect(format!("Failed to create WasmEdgeString from '{}'", mod_name).as_str());
+        let mod_name = match WasmEdgeString::from_bytes(mod_name.as_bytes()) {
+            Ok(mod_name) => mod_name,
+            Err(_) => return 0,
+        };
         unsafe { we_ffi::WasmEdge_StoreListGlobalRegisteredLength(self.raw, mod_name.raw) as usize }
     }
 
@@ -374,9 +369,7 @@ impl<'vm> StoreContext<'vm> {
         let max_len = self.list_global_registered_len(mod_name);
         match 0 < global_names.len() && global_names.len() <= max_len {
             true => {
-                let mod_name = WasmEdgeString::from_str(mod_name).expect(
-                    format!("Failed to create WasmEdgeString from '{}'", mod_name).as_str(),
-                );
+                let mod_name = WasmEdgeString::from_bytes(mod_name.as_bytes())?;
                 let len = unsafe {
                     we_ffi::WasmEdge_StoreListGlobalRegistered(
                         self.raw,
@@ -390,10 +383,8 @@ impl<'vm> StoreContext<'vm> {
                 };
                 let mut names = vec![];
                 for s in s_vec {
-                    // let x = unsafe { std::ffi::CString::from_raw(s.Buf as *mut _) };
-                    // names.push(x.to_string_lossy().into_owned());
-                    let slice = unsafe { CStr::from_ptr(s.Buf as *const _) };
-                    names.push(slice.to_string_lossy().into_owned());
+                    let name = unsafe { WasmEdgeStr::from_raw(*s) };
+                    names.push(name.to_string_lossy().into_owned());
                 }
 
                 Ok((len as usize, names))
@@ -429,8 +420,8 @@ impl<'vm> StoreContext<'vm> {
                 };
                 let mut names = vec![];
                 for s in s_vec {
-                    let slice = unsafe { CStr::from_ptr(s.Buf as *const _) };
-                    names.push(slice.to_string_lossy().into_owned());
+                    let name = unsafe { WasmEdgeStr::from_raw(*s) };
+                    names.push(name.to_string_lossy().into_owned());
                 }
 
                 Ok((len as usize, names))
@@ -443,8 +434,10 @@ impl<'vm> StoreContext<'vm> {
     }
 
     pub fn list_memory_registered_len(&self, mod_name: &str) -> usize {
-        let mod_name = WasmEdgeString::from_str(mod_name)
-            .expect(format!("Failed to create WasmEdgeString from '{}'", mod_name).as_str());
+        let mod_name = match WasmEdgeString::from_bytes(mod_name.as_bytes()) {
+            Ok(mod_name) => mod_name,
+            Err(_) => return 0,
+        };
         let len =
             unsafe { we_ffi::WasmEdge_StoreListMemoryRegisteredLength(self.raw, mod_name.raw) };
         len as usize
@@ -458,9 +451,7 @@ impl<'vm> StoreContext<'vm> {
         let max_len = self.list_memory_registered_len(mod_name);
         match 0 < memory_names.len() && memory_names.len() <= max_len {
             true => {
-                let mod_name = WasmEdgeString::from_str(mod_name).expect(
-                    format!("Failed to create WasmEdgeString from '{}'", mod_name).as_str(),
-                );
+                let mod_name = WasmEdgeString::from_bytes(mod_name.as_bytes())?;
                 let len = unsafe {
                     we_ffi::WasmEdge_StoreListMemoryRegistered(
                         self.raw,
@@ -474,10 +465,8 @@ impl<'vm> StoreContext<'vm> {
                 };
                 let mut names = vec![];
                 for s in s_vec {
-                    // let x = unsafe { std::ffi::CString::from_raw(s.Buf as *mut _) };
-                    // names.push(x.to_string_lossy().into_owned());
-                    let slice = unsafe { CStr::from_ptr(s.Buf as *const _) };
-                    names.push(slice.to_string_lossy().into_owned());
+                    let name = unsafe { WasmEdgeStr::from_raw(*s) };
+                    names.push(name.to_string_lossy().into_owned());
                 }
 
                 Ok((len as usize, names))
@@ -513,10 +502,8 @@ impl<'vm> StoreContext<'vm> {
                 };
                 let mut names = vec![];
                 for s in s_vec {
-                    // let x = unsafe { std::ffi::CString::from_raw(s.Buf as *mut _) };
-                    // names.push(x.to_string_lossy().into_owned());
-                    let slice = unsafe { CStr::from_ptr(s.Buf as *const _) };
-                    names.push(slice.to_string_lossy().into_owned());
+                    let name = unsafe { WasmEdgeStr::from_raw(*s) };
+                    names.push(name.to_string_lossy().into_owned());
                 }
 
                 Ok((len as usize, names))
@@ -532,6 +519,7 @@ impl<'vm> Drop for StoreContext<'vm> {
     fn drop(&mut self) {
         if !self.raw.is_null() {
             if self._drop {
+                externref::release_store(self.raw as usize);
                 unsafe { we_ffi::WasmEdge_StoreDelete(self.raw) }
             } else {
                 self.raw = std::ptr::null_mut();
@@ -543,6 +531,7 @@ impl<'vm> Drop for StoreContext<'vm> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::externref::ExternRef;
     use crate::instance::function::HostFunctionContext;
     use crate::{
         context::{
@@ -551,8 +540,9 @@ mod tests {
         },
         instance::function::FunctionTypeContext,
         types::*,
-        value::*,
+        value::Val,
     };
+    use std::convert::TryFrom;
     use std::ptr;
     use wasmedge_sys::ffi as we_ffi;
 
@@ -562,9 +552,9 @@ mod tests {
     fn test_context_store() {
         // create contexts
         let mut conf = ConfigureContext::create();
-        conf.add_host_registration(HostRegistration::WasmEdge_HostRegistration_Wasi);
-        conf.add_host_registration(HostRegistration::WasmEdge_HostRegistration_WasmEdge_Process);
-        conf.add_proposal(WasmEdgeProposal::WasmEdge_Proposal_ReferenceTypes);
+        conf.add_host_registration(HostRegistration::Wasi);
+        conf.add_host_registration(HostRegistration::WasmEdgeProcess);
+        conf.add_proposal(Proposal::ReferenceTypes);
         let mut store = StoreContext::create();
         let mod_name = vec!["module", "extern", "no-such-module"];
         let err_name = "invalid-instance-name";
@@ -627,6 +617,18 @@ mod tests {
         assert!(store
             .find_function_registered(mod_name[0], err_name)
             .is_none());
+        let res = store.find_function_registered(mod_name[1], "func-add");
+        assert!(res.is_some());
+        let func = res.unwrap();
+        let res = func.function_type();
+        assert!(res.is_some());
+        let func_type = res.unwrap();
+        let expected = FunctionTypeContext::create(
+            Some(&[ValType::ExternRef, ValType::I32]),
+            Some(&[ValType::I32]),
+        )
+        .unwrap();
+        assert!(func_type == expected);
 
         // Store list table exports
         assert_eq!(store.list_table_len(), 2);
@@ -746,7 +748,7 @@ mod tests {
         assert!(result.is_err());
         let err = result.unwrap_err();
         assert_eq!(
-            err.message,
+            err.message(),
             format!(
                 "The length of the argument 'mod_names' should be between 1 and the max length ({}).",
                 store.list_module_len()
@@ -757,14 +759,11 @@ mod tests {
     fn create_extern_module(name: &str) -> Option<ImportObjectContext<'_>> {
         // create import object
         let result = ImportObjectContext::create(name, ptr::null_mut());
-        assert!(result.is_some());
+        assert!(result.is_ok());
         let mut imp_obj = result.unwrap();
 
-        let params = [
-            WasmEdgeValType::WasmEdge_ValType_ExternRef,
-            WasmEdgeValType::WasmEdge_ValType_I32,
-        ];
-        let returns = [WasmEdgeValType::WasmEdge_ValType_I32];
+        let params = [ValType::ExternRef, ValType::I32];
+        let returns = [ValType::I32];
         let result = FunctionTypeContext::create(Some(&params), Some(&returns));
         assert!(result.is_some());
         let host_ftype = result.unwrap();
@@ -775,30 +774,30 @@ mod tests {
         let result = HostFunctionContext::create(&host_ftype, Some(extern_add), 0);
         assert!(result.is_some());
         let mut host_func = result.unwrap();
-        imp_obj.add_host_function(host_name, &mut host_func);
+        assert!(imp_obj.add_host_function(host_name, &mut host_func).is_ok());
 
         // add host function "func-sub"
         let host_name = "func-sub";
         let result = HostFunctionContext::create(&host_ftype, Some(extern_sub), 0);
         assert!(result.is_some());
         let mut host_func = result.unwrap();
-        imp_obj.add_host_function(host_name, &mut host_func);
+        assert!(imp_obj.add_host_function(host_name, &mut host_func).is_ok());
 
         // add host function "func-mul"
         let host_name = "func-mul";
         let result = HostFunctionContext::create(&host_ftype, Some(extern_mul), 0);
         assert!(result.is_some());
         let mut host_func = result.unwrap();
-        imp_obj.add_host_function(host_name, &mut host_func);
+        assert!(imp_obj.add_host_function(host_name, &mut host_func).is_ok());
 
         // add host function "func-div"
         let host_name = "func-div";
         let result = HostFunctionContext::create(&host_ftype, Some(extern_div), 0);
         assert!(result.is_some());
         let mut host_func = result.unwrap();
-        imp_obj.add_host_function(host_name, &mut host_func);
+        assert!(imp_obj.add_host_function(host_name, &mut host_func).is_ok());
 
-        let returns = [WasmEdgeValType::WasmEdge_ValType_I32];
+        let returns = [ValType::I32];
         let result = FunctionTypeContext::create(None, Some(&returns));
         assert!(result.is_some());
         let host_ftype = result.unwrap();
@@ -809,14 +808,14 @@ mod tests {
         let result = HostFunctionContext::create(&host_ftype, Some(extern_term), 0);
         assert!(result.is_some());
         let mut host_func = result.unwrap();
-        imp_obj.add_host_function(host_name, &mut host_func);
+        assert!(imp_obj.add_host_function(host_name, &mut host_func).is_ok());
 
         // add host function "func-fail"
         let host_name = "func-fail";
         let result = HostFunctionContext::create(&host_ftype, Some(extern_fail), 0);
         assert!(result.is_some());
         let mut host_func = result.unwrap();
-        imp_obj.add_host_function(host_name, &mut host_func);
+        assert!(imp_obj.add_host_function(host_name, &mut host_func).is_ok());
 
         Some(imp_obj)
     }
@@ -876,9 +875,12 @@ mod tests {
         returns: *mut WasmEdgeValue,
     ) -> we_ffi::WasmEdge_Result {
         let params = std::slice::from_raw_parts(params, 2);
-        let val1 = *(WasmEdgeValueGetExternRef(params[0]) as *const ::std::os::raw::c_int);
-        let val2 = WasmEdgeValueGetI32(params[1]);
-        let res = WasmEdgeValueGenI32(val1 + val2);
+        let val1 = match ExternRef::<i32>::try_from(Val::from(params[0])) {
+            Ok(val) => *val,
+            Err(_) => return we_ffi::WasmEdge_Result { Code: 2 },
+        };
+        let val2 = Val::from(params[1]).i32().unwrap_or_default();
+        let res = WasmEdgeValue::from(Val::I32(val1 + val2));
         returns.write(res);
 
         we_ffi::WasmEdge_Result { Code: 0 }
@@ -892,9 +894,12 @@ mod tests {
         returns: *mut WasmEdgeValue,
     ) -> we_ffi::WasmEdge_Result {
         let params = std::slice::from_raw_parts(params, 2);
-        let val1 = *(WasmEdgeValueGetExternRef(params[0]) as *const ::std::os::raw::c_int);
-        let val2 = WasmEdgeValueGetI32(params[1]);
-        let res = WasmEdgeValueGenI32(val1 - val2);
+        let val1 = match ExternRef::<i32>::try_from(Val::from(params[0])) {
+            Ok(val) => *val,
+            Err(_) => return we_ffi::WasmEdge_Result { Code: 2 },
+        };
+        let val2 = Val::from(params[1]).i32().unwrap_or_default();
+        let res = WasmEdgeValue::from(Val::I32(val1 - val2));
         returns.write(res);
 
         we_ffi::WasmEdge_Result { Code: 0 }
@@ -908,9 +913,12 @@ mod tests {
         returns: *mut WasmEdgeValue,
     ) -> we_ffi::WasmEdge_Result {
         let params = std::slice::from_raw_parts(params, 2);
-        let val1 = *(WasmEdgeValueGetExternRef(params[0]) as *const ::std::os::raw::c_int);
-        let val2 = WasmEdgeValueGetI32(params[1]);
-        let res = WasmEdgeValueGenI32(val1 * val2);
+        let val1 = match ExternRef::<i32>::try_from(Val::from(params[0])) {
+            Ok(val) => *val,
+            Err(_) => return we_ffi::WasmEdge_Result { Code: 2 },
+        };
+        let val2 = Val::from(params[1]).i32().unwrap_or_default();
+        let res = WasmEdgeValue::from(Val::I32(val1 * val2));
         returns.write(res);
 
         we_ffi::WasmEdge_Result { Code: 0 }
@@ -924,9 +932,12 @@ mod tests {
         returns: *mut WasmEdgeValue,
     ) -> we_ffi::WasmEdge_Result {
         let params = std::slice::from_raw_parts(params, 2);
-        let val1 = *(WasmEdgeValueGetExternRef(params[0]) as *const ::std::os::raw::c_int);
-        let val2 = WasmEdgeValueGetI32(params[1]);
-        let res = WasmEdgeValueGenI32(val1 / val2);
+        let val1 = match ExternRef::<i32>::try_from(Val::from(params[0])) {
+            Ok(val) => *val,
+            Err(_) => return we_ffi::WasmEdge_Result { Code: 2 },
+        };
+        let val2 = Val::from(params[1]).i32().unwrap_or_default();
+        let res = WasmEdgeValue::from(Val::I32(val1 / val2));
         returns.write(res);
 
         we_ffi::WasmEdge_Result { Code: 0 }
@@ -939,7 +950,7 @@ mod tests {
         _params: *const WasmEdgeValue,
         returns: *mut WasmEdgeValue,
     ) -> we_ffi::WasmEdge_Result {
-        let res = WasmEdgeValueGenI32(1234);
+        let res = WasmEdgeValue::from(Val::I32(1234));
         returns.write(res);
 
         we_ffi::WasmEdge_Result { Code: 1 }
@@ -952,7 +963,7 @@ mod tests {
         _params: *const WasmEdgeValue,
         returns: *mut WasmEdgeValue,
     ) -> we_ffi::WasmEdge_Result {
-        let res = WasmEdgeValueGenI32(5678);
+        let res = WasmEdgeValue::from(Val::I32(5678));
         returns.write(res);
 
         we_ffi::WasmEdge_Result { Code: 2 }
@@ -1,22 +1,87 @@
 use crate::context::{ast::ASTModuleContext, configure::ConfigureContext};
-use crate::error::WasmEdgeResult;
+use crate::error::{WasmEdgeError, WasmEdgeResult};
+use crate::policy::ValidationPolicy;
+use crate::typecheck::{self, FindingKind};
+use crate::types::{Proposal, ValType};
 use crate::utils::check;
+use std::fmt;
 use wasmedge_sys::ffi as we_ffi;
 
 pub struct Validator {
     pub(crate) raw: *mut we_ffi::WasmEdge_ValidatorContext,
+    /// The proposals enabled in the configuration, used to explain failures.
+    proposals: Vec<Proposal>,
 }
 impl Validator {
     pub fn create(conf: &ConfigureContext) -> Option<Self> {
         let raw = unsafe { we_ffi::WasmEdge_ValidatorCreate(conf.raw) };
         match raw.is_null() {
             true => None,
-            false => Some(Validator { raw }),
+            false => Some(Validator {
+                raw,
+                proposals: Proposal::all().filter(|p| conf.has_proposal(*p)).collect(),
+            }),
         }
     }
 
+    /// Validates a module.
+    ///
+    /// On failure, the returned `WasmEdgeError::Validation` carries a `ValidationDiagnostic` if
+    /// the module was loaded by a `LoaderContext` and the failing instruction could be located.
     pub fn validate(&self, ast_mod: &ASTModuleContext) -> WasmEdgeResult<()> {
-        unsafe { check(we_ffi::WasmEdge_ValidatorValidate(self.raw, ast_mod.raw)) }
+        let result = unsafe { check(we_ffi::WasmEdge_ValidatorValidate(self.raw, ast_mod.raw)) };
+        match result {
+            Err(WasmEdgeError::Validation { code, message, .. }) => {
+                Err(WasmEdgeError::Validation {
+                    code,
+                    message,
+                    diagnostic: self.diagnose(ast_mod).map(Box::new),
+                })
+            }
+            result => result,
+        }
+    }
+
+    /// Validates a module, then checks it against a policy.
+    ///
+    /// All violations of the policy are returned together in `WasmEdgeError::Policy`.
+    pub fn validate_with_policy(
+        &self,
+        ast_mod: &ASTModuleContext,
+        policy: &ValidationPolicy,
+    ) -> WasmEdgeResult<()> {
+        self.validate(ast_mod)?;
+        let violations = policy.check(ast_mod)?;
+        match violations.is_empty() {
+            true => Ok(()),
+            false => Err(WasmEdgeError::Policy(violations)),
+        }
+    }
+
+    fn diagnose(&self, ast_mod: &ASTModuleContext) -> Option<ValidationDiagnostic> {
+        let bytes = ast_mod.bytes().ok()?;
+        let finding = typecheck::check_module(&bytes, &self.proposals)?;
+        let function_name = match ast_mod.names() {
+            Ok(Some(names)) => names.function(finding.func).map(String::from),
+            _ => None,
+        };
+        let mut diagnostic = ValidationDiagnostic {
+            function: finding.func,
+            function_name,
+            offset: finding.offset,
+            expected: None,
+            actual: None,
+            proposal: None,
+        };
+        match finding.kind {
+            FindingKind::TypeMismatch { expected, actual } => {
+                diagnostic.expected = Some(expected);
+                diagnostic.actual = Some(actual);
+            }
+            FindingKind::Proposal(proposal) => diagnostic.proposal = Some(proposal),
+            FindingKind::Invalid => {}
+        }
+        Some(diagnostic)
     }
 }
 impl Drop for Validator {
@@ -27,18 +92,64 @@ impl Drop for Validator {
     }
 }
 
+/// Where and why a module failed validation, as far as the crate could tell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationDiagnostic {
+    /// The index of the failing function, imported functions included.
+    pub function: u32,
+    /// The name of the failing function, from the `name` section.
+    pub function_name: Option<String>,
+    /// The offset of the failing instruction in the module bytes.
+    pub offset: usize,
+    /// The operand types the instruction expected, for type mismatches.
+    pub expected: Option<Vec<ValType>>,
+    /// The operand types found on the stack, for type mismatches.
+    pub actual: Option<Vec<ValType>>,
+    /// The disabled proposal that would allow the failing instruction.
+    pub proposal: Option<Proposal>,
+}
+impl fmt::Display for ValidationDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "in function {}", self.function)?;
+        if let Some(name) = &self.function_name {
+            write!(f, " ({})", name)?;
+        }
+        write!(f, " at offset {:#x}", self.offset)?;
+        if let (Some(expected), Some(actual)) = (&self.expected, &self.actual) {
+            write!(
+                f,
+                ": expected [{}], found [{}]",
+                join_types(expected),
+                join_types(actual)
+            )?;
+        }
+        if let Some(proposal) = self.proposal {
+            write!(f, ": requires the {} proposal", proposal)?;
+        }
+        Ok(())
+    }
+}
+
+fn join_types(types: &[ValType]) -> String {
+    types
+        .iter()
+        .map(ValType::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::context::{configure::ConfigureContext, loader::LoaderContext};
-    use crate::types::WasmEdgeProposal;
+    use crate::types::Proposal;
 
     const TPATH: &str = "/root/workspace/wasmedge-ml/wasmedge/tests/data/test.wasm";
 
     #[test]
     fn test_validator() {
         let mut conf = ConfigureContext::create();
-        conf.add_proposal(WasmEdgeProposal::WasmEdge_Proposal_ReferenceTypes);
+        conf.add_proposal(Proposal::ReferenceTypes);
         let result = Validator::create(&conf);
         assert!(result.is_some());
         let validator = result.unwrap();
@@ -62,4 +173,47 @@ mod tests {
             Err(_) => None,
         }
     }
+
+    #[test]
+    fn test_validator_diagnostic() {
+        let wasm = [
+            /* WASM header */
+            0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00, /* Type section */
+            0x01, 0x05, 0x01, /* function type {} -> {i32} */
+            0x60, 0x00, 0x01, 0x7F, /* Function section */
+            0x03, 0x02, 0x01, 0x00, /* Code section */
+            0x0A, 0x0C, 0x01, 0x0A, 0x00, /* i32.const 1, f32.const 0, i32.add */
+            0x41, 0x01, 0x43, 0x00, 0x00, 0x00, 0x00, 0x6A, 0x0B,
+            /* Name section: function 0 -> "bad" */
+            0x00, 0x0D, 0x04, 0x6E, 0x61, 0x6D, 0x65, 0x01, 0x06, 0x01, 0x00, 0x03, 0x62, 0x61,
+            0x64,
+        ];
+        let conf = ConfigureContext::create();
+        let mut loader = LoaderContext::create(&conf).unwrap();
+        let validator = Validator::create(&conf).unwrap();
+        let mut ast_mod = ASTModuleContext::default();
+        assert!(loader.parse_from_buffer(&mut ast_mod, &wasm).is_ok());
+
+        let err = validator.validate(&ast_mod).unwrap_err();
+        assert_eq!(err.phase(), crate::error::ErrorPhase::Validation);
+        let diagnostic = err.diagnostic().unwrap();
+        assert_eq!(diagnostic.function, 0);
+        assert_eq!(diagnostic.function_name.as_deref(), Some("bad"));
+        assert_eq!(diagnostic.offset, 0x1F);
+        assert_eq!(diagnostic.expected, Some(vec![ValType::I32]));
+        assert_eq!(diagnostic.actual, Some(vec![ValType::F32]));
+        assert_eq!(diagnostic.proposal, None);
+        assert!(err
+            .to_string()
+            .ends_with("in function 0 (bad) at offset 0x1f: expected [i32], found [f32]"));
+
+        // Modules without known bytes still fail, without a diagnostic
+        let mut ast_mod = ast_mod;
+        ast_mod.source = None;
+        assert!(validator
+            .validate(&ast_mod)
+            .unwrap_err()
+            .diagnostic()
+            .is_none());
+    }
 }
@@ -1,14 +1,23 @@
 use crate::{
+    binary,
     context::{
-        ast::ASTModuleContext, configure::ConfigureContext, import_object::ImportObjectContext,
-        statistics::StatisticsContext, store::StoreContext,
+        aot::{ArtifactInfo, ArtifactLoad, ArtifactLocator, CompilationCache},
+        ast::ASTModuleContext,
+        configure::{Configuration, ConfigureContext},
+        import_object::{ImportObjectContext, WASI_MODULE},
+        statistics::{ExecutionReport, StatisticsContext, StatisticsSnapshot},
+        store::StoreContext,
     },
-    error::WasmEdgeResult,
+    error::{WasmEdgeError, WasmEdgeResult},
+    externref,
     instance::function::FunctionTypeContext,
+    instrument,
+    profile::{Profile, Profiler},
     types::*,
-    utils::{check, path_to_cstring},
+    utils::{buffer_len, check, path_to_cstring, TempDir},
+    value::{self, Val},
 };
-use std::ffi::CStr;
+use std::cell::{Cell, RefCell};
 use std::marker::PhantomData;
 use std::mem;
 use std::path::Path;
@@ -17,42 +26,194 @@ use wasmedge_sys::ffi as we_ffi;
 
 pub struct VMContext {
     pub(crate) raw: *mut we_ffi::WasmEdge_VMContext,
+    /// Whether the VM created its own store, rather than using one passed to `create`.
+    owns_store: bool,
+    /// The settings the VM was created with, as WasmEdge has no getter for them.
+    config: Configuration,
+    /// Where `load_from_file` looks for compiled artifacts, see `set_compilation_cache`.
+    aot_cache: Option<ArtifactLocator>,
+    /// The statistics at the last `reset_statistics`, or at creation.
+    stats_baseline: StatisticsSnapshot,
+    /// The fuel given by `set_fuel` and `add_fuel`, as WasmEdge has no cost limit getter. It
+    /// changes in `&self` calls when the top-up handler grants fuel.
+    fuel: Cell<Option<FuelTank>>,
+    /// Tops up the tank before each call, see `set_pre_call_top_up`.
+    pre_call_top_up: RefCell<Option<Box<dyn FnMut(u64) -> u64>>>,
+    /// The probes of profiling mode, see `enable_profiling`.
+    profiler: Option<Profiler>,
+}
+
+/// Fuel is counted in the total cost of the statistics context.
+#[derive(Clone, Copy, Debug)]
+struct FuelTank {
+    /// The total cost when metering started.
+    start: u64,
+    /// The total cost at which execution stops.
+    limit: u64,
 }
 impl VMContext {
     pub fn create(
         conf: Option<&ConfigureContext>,
         store: Option<&mut StoreContext>,
     ) -> Option<VMContext> {
-        let conf = match conf {
-            Some(conf) => conf.raw,
-            None => ptr::null(),
+        let (config, conf) = match conf {
+            Some(conf) => (Configuration::from(conf), conf.raw),
+            None => (
+                Configuration::from(&ConfigureContext::create()),
+                ptr::null(),
+            ),
         };
-        let store = match store {
-            Some(store) => store.raw,
-            None => ptr::null_mut(),
+        let (owns_store, store) = match store {
+            Some(store) => (false, store.raw),
+            None => (true, ptr::null_mut()),
         };
         let vm = unsafe { we_ffi::WasmEdge_VMCreate(conf, store) };
-        match vm.is_null() {
-            true => None,
-            false => Some(VMContext { raw: vm }),
+        if vm.is_null() {
+            return None;
         }
+        let mut vm = VMContext {
+            raw: vm,
+            owns_store,
+            config,
+            aot_cache: None,
+            stats_baseline: StatisticsSnapshot::empty(),
+            fuel: Cell::new(None),
+            pre_call_top_up: RefCell::new(None),
+            profiler: None,
+        };
+        vm.reset_statistics();
+        Some(vm)
     }
 
+    /// Loads a module from a file, or its compiled artifact if the compilation cache holds one
+    /// that is compatible with the VM. A native artifact is loaded as is.
+    ///
+    /// In profiling mode a module is instrumented and the compilation cache is not used.
     pub fn load_from_file<P: AsRef<Path>>(&mut self, path: P) -> WasmEdgeResult<()> {
-        let path = path_to_cstring(path.as_ref())?;
+        let is_module = binary::is_module_file(path.as_ref());
+        if self.profiler.is_some() && is_module {
+            return match std::fs::read(path.as_ref()) {
+                Ok(buf) => self.load_from_buffer(&buf),
+                Err(e) => Err(WasmEdgeError::from(format!(
+                    "Failed to read module file '{}': {}",
+                    path.as_ref().display(),
+                    e
+                ))),
+            };
+        }
+        // Only modules have artifacts in the cache; anything else is left to WasmEdge
+        if self.aot_cache.is_some() && is_module {
+            if let Ok(wasm) = std::fs::read(path.as_ref()) {
+                let artifact = self.aot_cache.as_ref().and_then(|cache| cache.find(&wasm));
+                return match artifact {
+                    Some(artifact) if self.is_artifact_of(&artifact, &wasm) => {
+                        self.load_path(&artifact)
+                    }
+                    _ => self.load_from_buffer(&wasm),
+                };
+            }
+        }
+        self.load_path(path.as_ref())
+    }
+
+    fn load_path(&mut self, path: &Path) -> WasmEdgeResult<()> {
+        let path = path_to_cstring(path)?;
         unsafe { check(we_ffi::WasmEdge_VMLoadWasmFromFile(self.raw, path.as_ptr())) }
     }
 
+    /// Whether the cached artifact at `path` carries metadata showing it was compiled from `wasm`
+    /// with settings compatible with the VM. A file that is not, e.g. one written by another
+    /// program or one without the embedded module to compare, is never loaded.
+    fn is_artifact_of(&self, path: &Path, wasm: &[u8]) -> bool {
+        match ArtifactInfo::read(path) {
+            Ok(Some(info)) => {
+                info.incompatibilities(&self.config.to_context()).is_empty()
+                    && info.wasm().map_or(false, |embedded| embedded == wasm)
+            }
+            _ => false,
+        }
+    }
+
+    /// Loads an AOT artifact after checking it against the runtime version and `conf`, which
+    /// should be the configuration the VM was created with.
+    ///
+    /// An incompatible artifact that embeds its module falls back to interpreting the module.
+    /// Otherwise it fails with `WasmEdgeError::IncompatibleArtifact`.
+    pub fn load_from_artifact<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        conf: &ConfigureContext,
+    ) -> WasmEdgeResult<ArtifactLoad> {
+        let info = match ArtifactInfo::read(path.as_ref())? {
+            Some(info) => info,
+            None => {
+                self.load_from_file(path)?;
+                return Ok(ArtifactLoad::Unverified);
+            }
+        };
+        let incompatibilities = info.incompatibilities(conf);
+        if incompatibilities.is_empty() {
+            self.load_from_file(path)?;
+            return Ok(ArtifactLoad::Native);
+        }
+        match info.wasm() {
+            Some(wasm) => {
+                self.load_from_buffer(wasm)?;
+                Ok(ArtifactLoad::Interpreted(incompatibilities))
+            }
+            None => Err(WasmEdgeError::IncompatibleArtifact(incompatibilities)),
+        }
+    }
+
+    /// Makes `load_from_file` load the artifacts that `cache` has already compiled, instead of
+    /// the modules themselves. Modules are not compiled on a miss.
+    ///
+    /// The VM should be created with the configuration the cache compiles with.
+    pub fn set_compilation_cache(&mut self, cache: &CompilationCache) {
+        self.aot_cache = Some(cache.locator().clone());
+    }
+
+    /// Loads a module from a buffer. In profiling mode the module is instrumented.
     pub fn load_from_buffer(&mut self, buf: &[u8]) -> WasmEdgeResult<()> {
+        if let Some(profiler) = &self.profiler {
+            let instrumented = instrument::instrument(buf)?;
+            profiler.set_functions(instrumented.functions);
+            return unsafe {
+                check(we_ffi::WasmEdge_VMLoadWasmFromBuffer(
+                    self.raw,
+                    instrumented.bytes.as_ptr() as *const _,
+                    buffer_len(instrumented.bytes.len())?,
+                ))
+            };
+        }
         unsafe {
             check(we_ffi::WasmEdge_VMLoadWasmFromBuffer(
                 self.raw,
                 buf.as_ptr() as *const _,
-                buf.len() as u32,
+                buffer_len(buf.len())?,
             ))
         }
     }
 
+    /// Loads a native artifact produced by `Compiler::compile_buffer`.
+    ///
+    /// WasmEdge only loads native artifacts from files, so the artifact is written to a private
+    /// temporary directory that is removed once it is loaded.
+    pub fn load_from_aot_buffer(&mut self, artifact: &[u8]) -> WasmEdgeResult<()> {
+        let dir = TempDir::new()?;
+        let path = dir.create_file(&format!("module{}", std::env::consts::DLL_SUFFIX), artifact)?;
+        self.load_path(&path)
+    }
+
+    /// Loads a module in the WebAssembly text format.
+    ///
+    /// Syntax errors are reported as `WasmEdgeError::Text` with the line and column in `text`.
+    #[cfg(feature = "wat")]
+    pub fn load_from_wat(&mut self, text: &str) -> WasmEdgeResult<()> {
+        let buf = crate::wat::wat_to_wasm(text)?;
+        self.load_from_buffer(&buf)
+    }
+
     pub fn load_from_ast(&mut self, ast_mod: &ASTModuleContext) -> WasmEdgeResult<()> {
         unsafe {
             check(we_ffi::WasmEdge_VMLoadWasmFromASTModule(
@@ -74,52 +235,234 @@ impl VMContext {
         unsafe { we_ffi::WasmEdge_VMCleanup(self.raw) }
     }
 
-    pub fn execute<'vm>(
+    pub fn execute(
         &self,
         func_name: &str,
-        params: &[WasmEdgeValue],
-        buf: &'vm mut [mem::MaybeUninit<WasmEdgeValue>],
-    ) -> WasmEdgeResult<&'vm [WasmEdgeValue]> {
-        let func_name = WasmEdgeString::from_str(func_name)
-            .expect(format!("Failed to create WasmEdgeString from '{}'", func_name).as_str());
-        unsafe {
+        params: &[Val],
+        returns_len: usize,
+    ) -> WasmEdgeResult<Vec<Val>> {
+        let func_name = WasmEdgeString::from_bytes(func_name.as_bytes())?;
+        let params = value::to_raw_vec(params);
+        let mut returns = value::raw_buffer(returns_len);
+        self.top_up();
+        let result = unsafe {
             check(we_ffi::WasmEdge_VMExecute(
                 self.raw,
                 func_name.raw,
-                params.as_ptr() as *const _,
+                params.as_ptr(),
                 params.len() as u32,
-                buf.as_mut_ptr() as *mut _,
-                buf.len() as u32,
-            ))?;
+                returns.as_mut_ptr(),
+                returns.len() as u32,
+            ))
+        };
+        self.unwind_profile();
+        result?;
+
+        Ok(value::from_raw_slice(&returns))
+    }
+
+    /// Executes a function like `execute` and reports the instructions, cost and wall time of
+    /// this call alone.
+    ///
+    /// The report is returned even if the call fails, e.g. to bill a call that trapped.
+    pub fn execute_with_report(
+        &mut self,
+        func_name: &str,
+        params: &[Val],
+        returns_len: usize,
+    ) -> (WasmEdgeResult<Vec<Val>>, ExecutionReport) {
+        self.measure(|vm| vm.execute(func_name, params, returns_len))
+    }
+
+    /// Runs `f` with the VM and reports the instructions, cost and wall time of what it ran.
+    ///
+    /// ```ignore
+    /// let (results, report) = vm.measure(|vm| {
+    ///     vm.execute("init", &[], 0)?;
+    ///     vm.execute("handle", &[Val::I32(request)], 1)
+    /// });
+    /// ```
+    pub fn measure<T, F>(&mut self, f: F) -> (T, ExecutionReport)
+    where
+        F: FnOnce(&mut VMContext) -> T,
+    {
+        let before = self.statistics_snapshot();
+        let result = f(self);
+        (result, self.statistics_snapshot().since(&before))
+    }
+
+    /// Starts counting `statistics_since_reset` from now.
+    ///
+    /// WasmEdge cannot clear its counters, so the `StatisticsContext` getters stay cumulative.
+    pub fn reset_statistics(&mut self) {
+        self.stats_baseline = self.statistics_snapshot();
+    }
+
+    /// Reports what the VM executed since the last `reset_statistics`, or since its creation.
+    pub fn statistics_since_reset(&self) -> ExecutionReport {
+        self.statistics_snapshot().since(&self.stats_baseline)
+    }
+
+    /// Meters execution with `fuel` units of cost, replacing any fuel left. Instructions are
+    /// priced by the cost table of the statistics context.
+    ///
+    /// Fails if the VM was not created with cost measuring enabled, as WasmEdge would not count
+    /// the cost and the fuel would never run out.
+    ///
+    /// A call that uses up the fuel is aborted with `WasmEdgeError::OutOfFuel`. WasmEdge cannot
+    /// resume it, so fuel added afterwards only applies to the following calls.
+    pub fn set_fuel(&mut self, fuel: u64) -> WasmEdgeResult<()> {
+        let cost = self.statistics_snapshot().total_cost;
+        self.set_fuel_limit(FuelTank {
+            start: cost,
+            limit: cost.saturating_add(fuel),
+        })
+    }
+
+    /// Adds `fuel` to what is left, or starts metering with it like `set_fuel`.
+    pub fn add_fuel(&mut self, fuel: u64) -> WasmEdgeResult<()> {
+        self.refill(fuel)
+    }
 
-            Ok(mem::MaybeUninit::slice_assume_init_ref(&buf[..buf.len()]))
+    /// The fuel left, or `None` if execution is not metered.
+    pub fn remaining_fuel(&self) -> Option<u64> {
+        let tank = self.fuel.get()?;
+        Some(
+            tank.limit
+                .saturating_sub(self.statistics_snapshot().total_cost),
+        )
+    }
+
+    /// The fuel used since metering started, or `None` if execution is not metered.
+    pub fn consumed_fuel(&self) -> Option<u64> {
+        let tank = self.fuel.get()?;
+        Some(
+            self.statistics_snapshot()
+                .total_cost
+                .saturating_sub(tank.start),
+        )
+    }
+
+    /// Stops metering; execution is no longer limited.
+    pub fn remove_fuel(&mut self) {
+        if let Some(mut stat) = self.statistics_context() {
+            stat.set_cost_limit(u64::MAX);
         }
+        self.fuel.set(None);
     }
 
-    pub fn execute_registered<'vm>(
+    /// Sets a handler that may add fuel between calls. Before each call it gets the fuel left and
+    /// returns the fuel to add, or 0 to add none.
+    ///
+    /// This is not a refuel on exhaustion: the handler never runs while a call executes. It runs
+    /// only while metering, before every call made with `execute`, `execute_registered` and the
+    /// `run_wasm_*` functions. WasmEdge checks the cost limit inside the interpreter and has no
+    /// hook to extend it, so a call that uses up its fuel part-way still fails with
+    /// `WasmEdgeError::OutOfFuel`, whatever the handler would grant.
+    ///
+    /// ```ignore
+    /// let mut budget = 3;
+    /// vm.set_pre_call_top_up(move |remaining| match remaining == 0 && budget > 0 {
+    ///     true => {
+    ///         budget -= 1;
+    ///         10_000
+    ///     }
+    ///     false => 0,
+    /// });
+    /// ```
+    pub fn set_pre_call_top_up<F>(&mut self, handler: F)
+    where
+        F: FnMut(u64) -> u64 + 'static,
+    {
+        *self.pre_call_top_up.borrow_mut() = Some(Box::new(handler));
+    }
+
+    pub fn remove_pre_call_top_up(&mut self) {
+        *self.pre_call_top_up.borrow_mut() = None;
+    }
+
+    /// Runs the pre-call top-up handler, if execution is metered.
+    fn top_up(&self) {
+        let remaining = match self.remaining_fuel() {
+            Some(remaining) => remaining,
+            None => return,
+        };
+        let granted = match self.pre_call_top_up.borrow_mut().as_mut() {
+            Some(handler) => handler(remaining),
+            None => return,
+        };
+        if granted > 0 {
+            // Metering is on, so the VM measures cost and this cannot fail
+            let _ = self.refill(granted);
+        }
+    }
+
+    fn refill(&self, fuel: u64) -> WasmEdgeResult<()> {
+        let cost = self.statistics_snapshot().total_cost;
+        let tank = match self.fuel.get() {
+            // The call that ran out of fuel may have gone past the limit
+            Some(tank) => FuelTank {
+                limit: tank.limit.max(cost).saturating_add(fuel),
+                ..tank
+            },
+            None => FuelTank {
+                start: cost,
+                limit: cost.saturating_add(fuel),
+            },
+        };
+        self.set_fuel_limit(tank)
+    }
+
+    fn set_fuel_limit(&self, tank: FuelTank) -> WasmEdgeResult<()> {
+        if !self.config.cost_measuring {
+            let message = "Fuel requires a VM created with cost measuring enabled";
+            return Err(WasmEdgeError::from(message.to_owned()));
+        }
+        match self.statistics_context() {
+            Some(mut stat) => stat.set_cost_limit(tank.limit),
+            None => {
+                let message = "Fuel requires a VM with a statistics context";
+                return Err(WasmEdgeError::from(message.to_owned()));
+            }
+        }
+        self.fuel.set(Some(tank));
+        Ok(())
+    }
+
+    fn statistics_snapshot(&self) -> StatisticsSnapshot {
+        match self.statistics_context() {
+            Some(stat) => stat.snapshot(),
+            None => StatisticsSnapshot::empty(),
+        }
+    }
+
+    pub fn execute_registered(
         &self,
         mod_name: &str,
         func_name: &str,
-        params: &[WasmEdgeValue],
-        buf: &'vm mut [mem::MaybeUninit<WasmEdgeValue>],
-    ) -> WasmEdgeResult<&'vm [WasmEdgeValue]> {
-        let mod_name = WasmEdgeString::from_str(mod_name)
-            .expect(format!("Failed to create WasmEdgeString from '{}'", mod_name).as_str());
-        let func_name = WasmEdgeString::from_str(func_name)
-            .expect(format!("Failed to create WasmEdgeString from '{}'", func_name).as_str());
-        unsafe {
+        params: &[Val],
+        returns_len: usize,
+    ) -> WasmEdgeResult<Vec<Val>> {
+        let mod_name = WasmEdgeString::from_bytes(mod_name.as_bytes())?;
+        let func_name = WasmEdgeString::from_bytes(func_name.as_bytes())?;
+        let params = value::to_raw_vec(params);
+        let mut returns = value::raw_buffer(returns_len);
+        self.top_up();
+        let result = unsafe {
             check(we_ffi::WasmEdge_VMExecuteRegistered(
                 self.raw,
                 mod_name.raw,
                 func_name.raw,
-                params.as_ptr() as *const _,
+                params.as_ptr(),
                 params.len() as u32,
-                buf.as_mut_ptr() as *mut _,
-                buf.len() as u32,
-            ))?;
+                returns.as_mut_ptr(),
+                returns.len() as u32,
+            ))
+        };
+        self.unwind_profile();
+        result?;
 
-            Ok(mem::MaybeUninit::slice_assume_init_ref(&buf[..buf.len()]))
-        }
+        Ok(value::from_raw_slice(&returns))
     }
 
     pub fn register_module_from_import_object(
@@ -139,8 +482,7 @@ impl VMContext {
         mod_name: &str,
         path: P,
     ) -> WasmEdgeResult<()> {
-        let mod_name = WasmEdgeString::from_str(mod_name)
-            .expect(format!("Failed to create WasmEdgeString from '{}'", mod_name).as_str());
+        let mod_name = WasmEdgeString::from_bytes(mod_name.as_bytes())?;
         let path = path_to_cstring(path.as_ref())?;
         unsafe {
             check(we_ffi::WasmEdge_VMRegisterModuleFromFile(
@@ -156,14 +498,13 @@ impl VMContext {
         mod_name: &str,
         buf: &[u8],
     ) -> WasmEdgeResult<()> {
-        let mod_name = WasmEdgeString::from_str(mod_name)
-            .expect(format!("Failed to create WasmEdgeString from '{}'", mod_name).as_str());
+        let mod_name = WasmEdgeString::from_bytes(mod_name.as_bytes())?;
         unsafe {
             check(we_ffi::WasmEdge_VMRegisterModuleFromBuffer(
                 self.raw,
                 mod_name.raw,
                 buf.as_ptr(),
-                buf.len() as u32,
+                buffer_len(buf.len())?,
             ))
         }
     }
@@ -173,8 +514,7 @@ impl VMContext {
         mod_name: &str,
         ast_mod: &ASTModuleContext,
     ) -> WasmEdgeResult<()> {
-        let mod_name = WasmEdgeString::from_str(mod_name)
-            .expect(format!("Failed to create WasmEdgeString from '{}'", mod_name).as_str());
+        let mod_name = WasmEdgeString::from_bytes(mod_name.as_bytes())?;
         unsafe {
             check(we_ffi::WasmEdge_VMRegisterModuleFromASTModule(
                 self.raw,
@@ -184,94 +524,100 @@ impl VMContext {
         }
     }
 
-    pub fn run_wasm_from_file<'vm, P: AsRef<Path>>(
+    pub fn run_wasm_from_file<P: AsRef<Path>>(
         &mut self,
         path: P,
         func_name: &str,
-        params: &[WasmEdgeValue],
-        buf: &'vm mut [mem::MaybeUninit<WasmEdgeValue>],
-    ) -> WasmEdgeResult<&'vm [WasmEdgeValue]> {
+        params: &[Val],
+        returns_len: usize,
+    ) -> WasmEdgeResult<Vec<Val>> {
         let path = path_to_cstring(path.as_ref())?;
-        let func_name = WasmEdgeString::from_str(func_name)
-            .expect(format!("Failed to create WasmEdgeString from '{}'", func_name).as_str());
+        let func_name = WasmEdgeString::from_bytes(func_name.as_bytes())?;
+        let params = value::to_raw_vec(params);
+        let mut returns = value::raw_buffer(returns_len);
+        self.top_up();
 
         unsafe {
             check(we_ffi::WasmEdge_VMRunWasmFromFile(
                 self.raw,
                 path.as_ptr(),
                 func_name.raw,
-                params.as_ptr() as *const WasmEdgeValue,
+                params.as_ptr(),
                 params.len() as u32,
-                buf.as_mut_ptr() as *mut WasmEdgeValue,
-                buf.len() as u32,
+                returns.as_mut_ptr(),
+                returns.len() as u32,
             ))?;
-
-            Ok(mem::MaybeUninit::slice_assume_init_ref(&buf[..buf.len()]))
         }
+
+        Ok(value::from_raw_slice(&returns))
     }
 
-    pub fn run_wasm_from_buffer<'vm>(
+    pub fn run_wasm_from_buffer(
         &mut self,
         buf: &[u8],
         func_name: &str,
-        params: &[WasmEdgeValue],
-        returns: &'vm mut [mem::MaybeUninit<WasmEdgeValue>],
-    ) -> WasmEdgeResult<&'vm [WasmEdgeValue]> {
-        let func_name = WasmEdgeString::from_str(func_name)
-            .expect(format!("Failed to create WasmEdgeString from '{}'", func_name).as_str());
+        params: &[Val],
+        returns_len: usize,
+    ) -> WasmEdgeResult<Vec<Val>> {
+        let func_name = WasmEdgeString::from_bytes(func_name.as_bytes())?;
+        let params = value::to_raw_vec(params);
+        let mut returns = value::raw_buffer(returns_len);
+        self.top_up();
 
         unsafe {
             check(we_ffi::WasmEdge_VMRunWasmFromBuffer(
                 self.raw,
                 buf.as_ptr(),
-                buf.len() as u32,
+                buffer_len(buf.len())?,
                 func_name.raw,
-                params.as_ptr() as *const WasmEdgeValue,
+                params.as_ptr(),
                 params.len() as u32,
-                returns.as_mut_ptr() as *mut WasmEdgeValue,
+                returns.as_mut_ptr(),
                 returns.len() as u32,
             ))?;
-
-            Ok(mem::MaybeUninit::slice_assume_init_ref(
-                &returns[..returns.len()],
-            ))
         }
+
+        Ok(value::from_raw_slice(&returns))
     }
 
-    pub fn run_wasm_from_ast<'vm>(
+    pub fn run_wasm_from_ast(
         &mut self,
         ast_mod: &ASTModuleContext,
         func_name: &str,
-        params: &[WasmEdgeValue],
-        buf: &'vm mut [mem::MaybeUninit<WasmEdgeValue>],
-    ) -> WasmEdgeResult<&'vm [WasmEdgeValue]> {
-        let func_name = WasmEdgeString::from_str(func_name)
-            .expect(format!("Failed to create WasmEdgeString from '{}'", func_name).as_str());
+        params: &[Val],
+        returns_len: usize,
+    ) -> WasmEdgeResult<Vec<Val>> {
+        let func_name = WasmEdgeString::from_bytes(func_name.as_bytes())?;
+        let params = value::to_raw_vec(params);
+        let mut returns = value::raw_buffer(returns_len);
+        self.top_up();
 
         unsafe {
             check(we_ffi::WasmEdge_VMRunWasmFromASTModule(
                 self.raw,
                 ast_mod.raw,
                 func_name.raw,
-                params.as_ptr() as *const _,
+                params.as_ptr(),
                 params.len() as u32,
-                buf.as_mut_ptr() as *mut _,
-                buf.len() as u32,
+                returns.as_mut_ptr(),
+                returns.len() as u32,
             ))?;
-
-            Ok(mem::MaybeUninit::slice_assume_init_ref(&buf[..buf.len()]))
         }
+
+        Ok(value::from_raw_slice(&returns))
     }
 
     pub fn function_type(&self, func_name: &str) -> Option<FunctionTypeContext> {
-        let func_name = WasmEdgeString::from_str(func_name)
-            .expect(format!("Failed to create WasmEdgeString from '{}'", func_name).as_str());
+        let func_name = WasmEdgeString::from_bytes(func_name.as_bytes()).ok()?;
         let result = unsafe { we_ffi::WasmEdge_VMGetFunctionType(self.raw, func_name.raw) };
         if result.is_null() {
             return None;
         }
 
-        Some(FunctionTypeContext { raw: result })
+        Some(FunctionTypeContext {
+            raw: result,
+            _drop: true,
+        })
     }
 
     pub fn function_type_registered(
@@ -279,10 +625,8 @@ impl VMContext {
         mod_name: &str,
         func_name: &str,
     ) -> Option<FunctionTypeContext> {
-        let mod_name = WasmEdgeString::from_str(mod_name)
-            .expect(format!("Failed to create WasmEdgeString from '{}'", mod_name).as_str());
-        let func_name = WasmEdgeString::from_str(func_name)
-            .expect(format!("Failed to create WasmEdgeString from '{}'", func_name).as_str());
+        let mod_name = WasmEdgeString::from_bytes(mod_name.as_bytes()).ok()?;
+        let func_name = WasmEdgeString::from_bytes(func_name.as_bytes()).ok()?;
         let result = unsafe {
             we_ffi::WasmEdge_VMGetFunctionTypeRegistered(self.raw, mod_name.raw, func_name.raw)
         };
@@ -290,7 +634,10 @@ impl VMContext {
             return None;
         }
 
-        Some(FunctionTypeContext { raw: result })
+        Some(FunctionTypeContext {
+            raw: result,
+            _drop: true,
+        })
     }
 
     pub fn function_list_len(&self) -> usize {
@@ -314,8 +661,7 @@ impl VMContext {
                 let names = mem::MaybeUninit::slice_assume_init_ref(&buf[..buf.len()]);
                 let mut s_vec = vec![];
                 for s in names {
-                    let slice = CStr::from_ptr(s.Buf as *const _);
-                    s_vec.push(slice.to_string_lossy().into_owned());
+                    s_vec.push(WasmEdgeStr::from_raw(*s).to_string_lossy().into_owned());
                 }
                 Some(s_vec)
             },
@@ -326,13 +672,18 @@ impl VMContext {
     // pub fn function_types()
 
     pub fn import_object(&self, reg: HostRegistration) -> Option<ImportObjectContext> {
-        let raw = unsafe { we_ffi::WasmEdge_VMGetImportModuleContext(self.raw, reg) };
+        let raw = unsafe { we_ffi::WasmEdge_VMGetImportModuleContext(self.raw, reg.into()) };
         match raw.is_null() {
             true => None,
             false => Some(ImportObjectContext {
                 raw,
                 _marker: PhantomData,
                 _drop: false,
+                module: String::from(match reg {
+                    HostRegistration::Wasi => WASI_MODULE,
+                    HostRegistration::WasmEdgeProcess => "wasmedge_process",
+                }),
+                exports: None,
             }),
         }
     }
@@ -349,6 +700,51 @@ impl VMContext {
         }
     }
 
+    /// Turns on profiling mode, in which the modules loaded by `load_from_buffer` and
+    /// `load_from_file` are instrumented to record per-function call counts, self instruction
+    /// counts and costs, and wall times, see `profile`. It must be turned on before loading.
+    ///
+    /// Function names come from the `name` section, the exports or the imports. Host functions
+    /// are profiled when the module calls them directly, not through a table. AST modules, AOT
+    /// artifacts, registered modules and the `run_wasm_from_*` shortcuts are not instrumented.
+    ///
+    /// The probes are registered in the VM's store and point into the VM, so profiling requires
+    /// a VM that owns its store; it fails for a VM created on a caller's `StoreContext`.
+    pub fn enable_profiling(&mut self) -> WasmEdgeResult<()> {
+        if self.profiler.is_some() {
+            return Ok(());
+        }
+        if !self.owns_store {
+            return Err(WasmEdgeError::from(String::from(
+                "Profiling requires a VM that owns its store",
+            )));
+        }
+        let stat = unsafe { we_ffi::WasmEdge_VMGetStatisticsContext(self.raw) };
+        let profiler = Profiler::create(stat)?;
+        self.register_module_from_import_object(profiler.import_object())?;
+        self.profiler = Some(profiler);
+        Ok(())
+    }
+
+    /// Returns what the profiled module executed since it was loaded or since `reset_profile`,
+    /// or `None` if profiling mode is off.
+    pub fn profile(&self) -> Option<Profile> {
+        self.profiler.as_ref().map(Profiler::profile)
+    }
+
+    pub fn reset_profile(&mut self) {
+        if let Some(profiler) = &self.profiler {
+            profiler.reset();
+        }
+    }
+
+    /// Closes the profile frames left open by a call that trapped.
+    fn unwind_profile(&self) {
+        if let Some(profiler) = &self.profiler {
+            profiler.unwind();
+        }
+    }
+
     pub fn statistics_context(&self) -> Option<StatisticsContext> {
         let raw = unsafe { we_ffi::WasmEdge_VMGetStatisticsContext(self.raw) };
         match raw.is_null() {
@@ -364,6 +760,10 @@ impl VMContext {
 impl Drop for VMContext {
     fn drop(&mut self) {
         if !self.raw.is_null() {
+            if self.owns_store {
+                let store = unsafe { we_ffi::WasmEdge_VMGetStoreContext(self.raw) };
+                externref::release_store(store as usize);
+            }
             unsafe { we_ffi::WasmEdge_VMDelete(self.raw) }
         }
     }
@@ -372,14 +772,17 @@ impl Drop for VMContext {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::externref::ExternRef;
+    use crate::fixtures::ADD_WASM;
+    use crate::profile::ProfileWeight;
     use crate::{
         context::{
             ast::ASTModuleContext, configure::ConfigureContext, loader::LoaderContext,
             store::StoreContext, validator::Validator,
         },
         instance::function::*,
-        value::*,
     };
+    use std::convert::TryFrom;
     use std::mem;
 
     const TPATH: &str = "/root/workspace/wasmedge-ml/wasmedge/tests/data/test.wasm";
@@ -387,8 +790,8 @@ mod tests {
     #[test]
     fn test_context_vm_basic() {
         let mut conf = ConfigureContext::create();
-        conf.add_proposal(WasmEdgeProposal::WasmEdge_Proposal_ReferenceTypes);
-        conf.add_host_registration(HostRegistration::WasmEdge_HostRegistration_Wasi);
+        conf.add_proposal(Proposal::ReferenceTypes);
+        conf.add_host_registration(HostRegistration::Wasi);
         let mut store = StoreContext::create();
         let result = create_extern_module("extern");
         assert!(result.is_some());
@@ -443,113 +846,98 @@ mod tests {
         let func_name = "func-mul-2";
         // let func_name2 = "func-mul-3";
         // VM run wasm from file
-        let params = [WasmEdgeValueGenI32(123), WasmEdgeValueGenI32(456)];
-        let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<2>();
-        let result = vm.run_wasm_from_file(TPATH, func_name, &params, &mut buf);
+        let params = [Val::I32(123), Val::I32(456)];
+        let result = vm.run_wasm_from_file(TPATH, func_name, &params, 2);
         assert!(result.is_ok());
         let returns = result.unwrap();
-        assert_eq!(246, WasmEdgeValueGetI32(returns[0]));
-        assert_eq!(912, WasmEdgeValueGetI32(returns[1]));
+        assert_eq!(Val::I32(246), returns[0]);
+        assert_eq!(Val::I32(912), returns[1]);
 
         // ! error: loading failed: invalid path, Code: 0x20
-        // let result = vm.run_wasm_from_file("no-file", func_name, &params, &mut buf);
+        // let result = vm.run_wasm_from_file("no-file", func_name, &params, 2);
         // assert!(result.is_err());
 
         // Function not found
         // ! error: wasmedge runtime failed: wasm function not found, Code: 0x05
-        // let result = vm.run_wasm_from_file(TPATH, func_name2, &params, &mut buf);
+        // let result = vm.run_wasm_from_file(TPATH, func_name2, &params, 2);
         // assert!(result.is_err());
 
         // Discard result
-        let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<1>();
-        let result = vm.run_wasm_from_file(TPATH, func_name, &params, &mut buf);
+        let result = vm.run_wasm_from_file(TPATH, func_name, &params, 1);
         assert!(result.is_ok());
         let returns = result.unwrap();
-        assert_eq!(246, WasmEdgeValueGetI32(returns[0]));
+        assert_eq!(Val::I32(246), returns[0]);
         // Discard result
-        let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<0>();
-        let result = vm.run_wasm_from_file(TPATH, func_name, &params, &mut buf);
+        let result = vm.run_wasm_from_file(TPATH, func_name, &params, 0);
         assert!(result.is_ok());
 
         // VM run wasm from buffer
-        let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<2>();
-        let result = vm.run_wasm_from_buffer(&mod_buf, func_name, &params, &mut buf);
+        let result = vm.run_wasm_from_buffer(&mod_buf, func_name, &params, 2);
         assert!(result.is_ok());
         let returns = result.unwrap();
-        assert_eq!(246, WasmEdgeValueGetI32(returns[0]));
-        assert_eq!(912, WasmEdgeValueGetI32(returns[1]));
+        assert_eq!(Val::I32(246), returns[0]);
+        assert_eq!(Val::I32(912), returns[1]);
         // ! error
-        // let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<2>();
-        // let result = vm.run_wasm_from_buffer(&mod_buf, func_name, &params, &mut buf);
+        // let result = vm.run_wasm_from_buffer(&mod_buf, func_name, &params, 2);
         // assert!(result.is_err());
 
         // Function type mismatch
         // ! error: execution failed: function signature mismatch, Code: 0x83
-        // let params = [WasmEdgeValueGenI32(123)];
-        // let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<2>();
-        // let result = vm.run_wasm_from_buffer(&mod_buf, func_name, &params, &mut buf);
+        // let params = [Val::I32(123)];
+        // let result = vm.run_wasm_from_buffer(&mod_buf, func_name, &params, 2);
         // assert!(result.is_err());
 
         // Function type mismatch
         // ! error: execution failed: function signature mismatch, Code: 0x83
-        // let params = [WasmEdgeValueGenI64(123), WasmEdgeValueGenI32(456)];
-        // let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<2>();
-        // let result = vm.run_wasm_from_file(TPATH, func_name, &params, &mut buf);
+        // let params = [Val::I64(123), Val::I32(456)];
+        // let result = vm.run_wasm_from_file(TPATH, func_name, &params, 2);
         // assert!(result.is_err());
 
         // Discard result
-        let params = [WasmEdgeValueGenI32(123), WasmEdgeValueGenI32(456)];
-        let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<1>();
-        let result = vm.run_wasm_from_file(TPATH, func_name, &params, &mut buf);
+        let params = [Val::I32(123), Val::I32(456)];
+        let result = vm.run_wasm_from_file(TPATH, func_name, &params, 1);
         assert!(result.is_ok());
         // Discard result
-        let params = [WasmEdgeValueGenI32(123), WasmEdgeValueGenI32(456)];
-        let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<0>();
-        let result = vm.run_wasm_from_file(TPATH, func_name, &params, &mut buf);
+        let params = [Val::I32(123), Val::I32(456)];
+        let result = vm.run_wasm_from_file(TPATH, func_name, &params, 0);
         assert!(result.is_ok());
 
         // VM run wasm from AST module
-        let params = [WasmEdgeValueGenI32(123), WasmEdgeValueGenI32(456)];
-        let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<2>();
-        let result = vm.run_wasm_from_ast(&ast_mod, func_name, &params, &mut buf);
+        let params = [Val::I32(123), Val::I32(456)];
+        let result = vm.run_wasm_from_ast(&ast_mod, func_name, &params, 2);
         assert!(result.is_ok());
         let returns = result.unwrap();
-        assert_eq!(246, WasmEdgeValueGetI32(returns[0]));
-        assert_eq!(912, WasmEdgeValueGetI32(returns[1]));
+        assert_eq!(Val::I32(246), returns[0]);
+        assert_eq!(Val::I32(912), returns[1]);
 
         // Function type mismatch
         // ! error: execution failed: function signature mismatch, Code: 0x83
-        // let params = [WasmEdgeValueGenI32(123)];
-        // let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<2>();
-        // let result = vm.run_wasm_from_ast(&ast_mod, func_name, &params, &mut buf);
+        // let params = [Val::I32(123)];
+        // let result = vm.run_wasm_from_ast(&ast_mod, func_name, &params, 2);
         // assert!(result.is_err());
 
         // Function type mismatch
         // ! error: execution failed: function signature mismatch, Code: 0x83
         // let params = [];
-        // let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<2>();
-        // let result = vm.run_wasm_from_ast(&ast_mod, func_name, &params, &mut buf);
+        // let result = vm.run_wasm_from_ast(&ast_mod, func_name, &params, 2);
         // assert!(result.is_err());
 
         // Function type mismatch
         // ! error: execution failed: function signature mismatch, Code: 0x83
-        // let params = [WasmEdgeValueGenI64(123), WasmEdgeValueGenI32(456)];
-        // let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<2>();
-        // let result = vm.run_wasm_from_ast(&ast_mod, func_name, &params, &mut buf);
+        // let params = [Val::I64(123), Val::I32(456)];
+        // let result = vm.run_wasm_from_ast(&ast_mod, func_name, &params, 2);
         // assert!(result.is_err());
 
         // Function not found
         // ! error: wasmedge runtime failed: wasm function not found, Code: 0x05
-        // let params = [WasmEdgeValueGenI64(123), WasmEdgeValueGenI32(456)];
-        // let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<2>();
-        // let result = vm.run_wasm_from_ast(&ast_mod, func_name2, &params, &mut buf);
+        // let params = [Val::I64(123), Val::I32(456)];
+        // let result = vm.run_wasm_from_ast(&ast_mod, func_name2, &params, 2);
         // assert!(result.is_err());
 
         // Discard result
         // ! error: execution failed: function signature mismatch, Code: 0x83
-        // let params = [WasmEdgeValueGenI64(123), WasmEdgeValueGenI32(456)];
-        // let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<1>();
-        // let result = vm.run_wasm_from_ast(&ast_mod, func_name, &params, &mut buf);
+        // let params = [Val::I64(123), Val::I32(456)];
+        // let result = vm.run_wasm_from_ast(&ast_mod, func_name, &params, 1);
         // assert!(result.is_ok());
 
         // VM load wasm from file
@@ -573,83 +961,76 @@ mod tests {
 
         // VM execute
         vm.cleanup();
-        let params = [WasmEdgeValueGenI32(123), WasmEdgeValueGenI32(456)];
-        let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<2>();
-        let result = vm.run_wasm_from_ast(&ast_mod, func_name, &params, &mut buf);
+        let params = [Val::I32(123), Val::I32(456)];
+        let result = vm.run_wasm_from_ast(&ast_mod, func_name, &params, 2);
         assert!(result.is_ok());
         // Inited phase
         // ! error: should be Err
-        assert!(vm.execute(func_name, &params, &mut buf).is_ok());
+        assert!(vm.execute(func_name, &params, 2).is_ok());
         // loaded phase
         assert!(vm.load_from_ast(&ast_mod).is_ok());
         // ! error: should be Err
-        assert!(vm.execute(func_name, &params, &mut buf).is_ok());
+        assert!(vm.execute(func_name, &params, 2).is_ok());
         // validated phase
         assert!(vm.validate().is_ok());
         // ! error: should be Err
-        assert!(vm.execute(func_name, &params, &mut buf).is_ok());
+        assert!(vm.execute(func_name, &params, 2).is_ok());
         // Instantiated phase
         assert!(vm.instantiate().is_ok());
-        let result = vm.execute(func_name, &params, &mut buf);
+        let result = vm.execute(func_name, &params, 2);
         assert!(result.is_ok());
         let returns = result.unwrap();
-        assert_eq!(246, WasmEdgeValueGetI32(returns[0]));
-        assert_eq!(912, WasmEdgeValueGetI32(returns[1]));
+        assert_eq!(Val::I32(246), returns[0]);
+        assert_eq!(Val::I32(912), returns[1]);
         // ! error: should be Err
-        assert!(vm.execute(func_name, &params, &mut buf).is_ok());
+        assert!(vm.execute(func_name, &params, 2).is_ok());
 
         // Function type mismatch
         // ! error: execution failed: function signature mismatch, Code: 0x83
-        // let params = [WasmEdgeValueGenI32(123)];
-        // let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<2>();
+        // let params = [Val::I32(123)];
         // assert!(vm
-        //     .run_wasm_from_ast(&ast_mod, func_name, &params, &mut buf)
+        //     .run_wasm_from_ast(&ast_mod, func_name, &params, 2)
         //     .is_err());
 
         // Function type mismatch
         // ! error: execution failed: function signature mismatch, Code: 0x83
         // let params = [];
-        // let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<2>();
         // assert!(vm
-        //     .run_wasm_from_ast(&ast_mod, func_name, &params, &mut buf)
+        //     .run_wasm_from_ast(&ast_mod, func_name, &params, 2)
         //     .is_err());
 
         // Function type mismatch
         // ! error: execution failed: function signature mismatch, Code: 0x83
-        // let params = [WasmEdgeValueGenI64(123), WasmEdgeValueGenI32(456)];
-        // let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<2>();
+        // let params = [Val::I64(123), Val::I32(456)];
         // assert!(vm
-        //     .run_wasm_from_ast(&ast_mod, func_name, &params, &mut buf)
+        //     .run_wasm_from_ast(&ast_mod, func_name, &params, 2)
         //     .is_err());
 
         // Function not found
         // ! error: wasmedge runtime failed: wasm function not found, Code: 0x05
-        // let params = [WasmEdgeValueGenI32(123), WasmEdgeValueGenI32(456)];
-        // let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<2>();
+        // let params = [Val::I32(123), Val::I32(456)];
         // assert!(vm
-        //     .run_wasm_from_ast(&ast_mod, func_name2, &params, &mut buf)
+        //     .run_wasm_from_ast(&ast_mod, func_name2, &params, 2)
         //     .is_err());
 
         // Discard result
-        let params = [WasmEdgeValueGenI32(123), WasmEdgeValueGenI32(456)];
-        let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<1>();
+        let params = [Val::I32(123), Val::I32(456)];
         assert!(vm
-            .run_wasm_from_ast(&ast_mod, func_name, &params, &mut buf)
+            .run_wasm_from_ast(&ast_mod, func_name, &params, 1)
             .is_ok());
 
         // Discard result
-        let params = [WasmEdgeValueGenI32(123), WasmEdgeValueGenI32(456)];
-        let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<0>();
+        let params = [Val::I32(123), Val::I32(456)];
         assert!(vm
-            .run_wasm_from_ast(&ast_mod, func_name, &params, &mut buf)
+            .run_wasm_from_ast(&ast_mod, func_name, &params, 0)
             .is_ok());
     }
 
     #[test]
     fn test_context_vm_execute_registered() {
         let mut conf = ConfigureContext::create();
-        conf.add_proposal(WasmEdgeProposal::WasmEdge_Proposal_ReferenceTypes);
-        conf.add_host_registration(HostRegistration::WasmEdge_HostRegistration_Wasi);
+        conf.add_proposal(Proposal::ReferenceTypes);
+        conf.add_host_registration(HostRegistration::Wasi);
         let mut store = StoreContext::create();
 
         // WASM from file
@@ -688,59 +1069,51 @@ mod tests {
         let result = vm.register_module_from_buffer(mod_name, &mod_buf);
         assert!(result.is_ok());
 
-        let params = [WasmEdgeValueGenI32(123), WasmEdgeValueGenI32(456)];
-        let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<2>();
-        let result = vm.execute_registered(mod_name, func_name, &params, &mut buf);
+        let params = [Val::I32(123), Val::I32(456)];
+        let result = vm.execute_registered(mod_name, func_name, &params, 2);
         assert!(result.is_ok());
         let returns = result.unwrap();
-        assert_eq!(246, WasmEdgeValueGetI32(returns[0]));
-        assert_eq!(912, WasmEdgeValueGetI32(returns[1]));
+        assert_eq!(Val::I32(246), returns[0]);
+        assert_eq!(Val::I32(912), returns[1]);
 
         // Function type mismatch
         // ! error: execution failed: function signature mismatch, Code: 0x83
-        // let params = [WasmEdgeValueGenI32(123)];
-        // let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<2>();
-        // let result = vm.execute_registered(mod_name, func_name, &params, &mut buf);
+        // let params = [Val::I32(123)];
+        // let result = vm.execute_registered(mod_name, func_name, &params, 2);
         // assert!(result.is_err());
 
         // Function type mismatch
         // ! error: execution failed: function signature mismatch, Code: 0x83
         // let params = [];
-        // let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<2>();
-        // let result = vm.execute_registered(mod_name, func_name, &params, &mut buf);
+        // let result = vm.execute_registered(mod_name, func_name, &params, 2);
         // assert!(result.is_err());
 
         // Function type mismatch
         // ! error: execution failed: function signature mismatch, Code: 0x83
-        // let params = [WasmEdgeValueGenI64(123), WasmEdgeValueGenI32(456)];
-        // let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<2>();
-        // let result = vm.execute_registered(mod_name, func_name, &params, &mut buf);
+        // let params = [Val::I64(123), Val::I32(456)];
+        // let result = vm.execute_registered(mod_name, func_name, &params, 2);
         // assert!(result.is_err());
 
         // Module not found
         // ! error: execution failed: wrong instance address, Code: 0x80
-        // let params = [WasmEdgeValueGenI32(123), WasmEdgeValueGenI32(456)];
-        // let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<2>();
-        // let result = vm.execute_registered(mod_name2, func_name, &params, &mut buf);
+        // let params = [Val::I32(123), Val::I32(456)];
+        // let result = vm.execute_registered(mod_name2, func_name, &params, 2);
         // assert!(result.is_err());
 
         // Function not found
         // ! error: wasmedge runtime failed: wasm function not found, Code: 0x05
-        // let params = [WasmEdgeValueGenI32(123), WasmEdgeValueGenI32(456)];
-        // let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<2>();
-        // let result = vm.execute_registered(mod_name, func_name2, &params, &mut buf);
+        // let params = [Val::I32(123), Val::I32(456)];
+        // let result = vm.execute_registered(mod_name, func_name2, &params, 2);
         // assert!(result.is_err());
 
         // Discard result
-        let params = [WasmEdgeValueGenI32(123), WasmEdgeValueGenI32(456)];
-        let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<1>();
-        let result = vm.execute_registered(mod_name, func_name, &params, &mut buf);
+        let params = [Val::I32(123), Val::I32(456)];
+        let result = vm.execute_registered(mod_name, func_name, &params, 1);
         assert!(result.is_ok());
 
         // Discard result
-        let params = [WasmEdgeValueGenI32(123), WasmEdgeValueGenI32(456)];
-        let mut buf = mem::MaybeUninit::<WasmEdgeValue>::uninit_array::<0>();
-        let result = vm.execute_registered(mod_name, func_name, &params, &mut buf);
+        let params = [Val::I32(123), Val::I32(456)];
+        let result = vm.execute_registered(mod_name, func_name, &params, 0);
         assert!(result.is_ok());
     }
 
@@ -752,8 +1125,8 @@ mod tests {
         let func_name2 = "func-mul-3";
 
         let mut conf = ConfigureContext::create();
-        conf.add_proposal(WasmEdgeProposal::WasmEdge_Proposal_ReferenceTypes);
-        conf.add_host_registration(HostRegistration::WasmEdge_HostRegistration_Wasi);
+        conf.add_proposal(Proposal::ReferenceTypes);
+        conf.add_host_registration(HostRegistration::Wasi);
         let mut store = StoreContext::create();
 
         // create extern import object module
@@ -832,8 +1205,8 @@ mod tests {
         let mod_name = "reg-wasm-buffer";
 
         let mut conf = ConfigureContext::create();
-        conf.add_proposal(WasmEdgeProposal::WasmEdge_Proposal_ReferenceTypes);
-        conf.add_host_registration(HostRegistration::WasmEdge_HostRegistration_Wasi);
+        conf.add_proposal(Proposal::ReferenceTypes);
+        conf.add_host_registration(HostRegistration::Wasi);
         let mut store = StoreContext::create();
 
         // create extern import object module
@@ -875,11 +1248,9 @@ mod tests {
         assert!(vm.instantiate().is_ok());
 
         // VM get import module
+        assert!(vm.import_object(HostRegistration::Wasi).is_some());
         assert!(vm
-            .import_object(HostRegistration::WasmEdge_HostRegistration_Wasi)
-            .is_some());
-        assert!(vm
-            .import_object(HostRegistration::WasmEdge_HostRegistration_WasmEdge_Process)
+            .import_object(HostRegistration::WasmEdgeProcess)
             .is_none());
 
         // VM get store
@@ -889,17 +1260,205 @@ mod tests {
         assert!(vm.statistics_context().is_some());
     }
 
+    #[cfg(feature = "wat")]
+    #[test]
+    fn test_context_vm_load_from_wat() {
+        let mut vm = VMContext::create(None, None).unwrap();
+        let text = r#"
+            (module
+              (func (export "add") (param i32 i32) (result i32)
+                local.get 0
+                local.get 1
+                i32.add))"#;
+        assert!(vm.load_from_wat(text).is_ok());
+        assert!(vm.validate().is_ok());
+        assert!(vm.instantiate().is_ok());
+        let result = vm.execute("add", &[Val::I32(1), Val::I32(2)], 1);
+        assert_eq!(result.unwrap(), vec![Val::I32(3)]);
+
+        let err = vm
+            .load_from_wat("(module\n  (func (export \"add\"))")
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            crate::error::WasmEdgeError::Text { line: 2, .. }
+        ));
+    }
+
+    #[test]
+    fn test_context_vm_execution_report() {
+        let mut conf = ConfigureContext::create();
+        conf.set_instruction_counting(true);
+        conf.set_cost_measuring(true);
+        let mut vm = VMContext::create(Some(&conf), None).unwrap();
+        assert!(vm.load_from_buffer(ADD_WASM).is_ok());
+        assert!(vm.validate().is_ok());
+        assert!(vm.instantiate().is_ok());
+
+        let params = [Val::I32(1), Val::I32(2)];
+        let (result, first) = vm.execute_with_report("add", &params, 1);
+        assert_eq!(result.unwrap(), vec![Val::I32(3)]);
+        assert!(first.instr_count > 0);
+        let (_, second) = vm.execute_with_report("add", &params, 1);
+        assert_eq!(
+            (second.instr_count, second.cost),
+            (first.instr_count, first.cost)
+        );
+        assert_eq!(
+            vm.statistics_since_reset().instr_count,
+            2 * first.instr_count
+        );
+
+        // A reset only moves the baseline; the context stays cumulative
+        vm.reset_statistics();
+        assert_eq!(vm.statistics_since_reset().instr_count, 0);
+        let instr_count = vm.statistics_context().unwrap().get_instr_count();
+        assert_eq!(instr_count as u64, 2 * first.instr_count);
+
+        let (result, report) = vm.measure(|vm| {
+            vm.execute("add", &params, 1)?;
+            vm.execute("sub", &params, 1)
+        });
+        assert!(result.is_err());
+        assert_eq!(report.instr_count, first.instr_count);
+    }
+
+    #[test]
+    fn test_context_vm_fuel() {
+        let mut conf = ConfigureContext::create();
+        conf.set_cost_measuring(true);
+        let mut vm = VMContext::create(Some(&conf), None).unwrap();
+        assert!(vm.load_from_buffer(ADD_WASM).is_ok());
+        assert!(vm.validate().is_ok());
+        assert!(vm.instantiate().is_ok());
+        assert_eq!(vm.remaining_fuel(), None);
+
+        let params = [Val::I32(1), Val::I32(2)];
+        let (_, report) = vm.execute_with_report("add", &params, 1);
+        let call_cost = report.cost;
+        assert!(call_cost > 0);
+
+        assert!(vm.set_fuel(call_cost).is_ok());
+        assert_eq!(vm.remaining_fuel(), Some(call_cost));
+        assert!(vm.execute("add", &params, 1).is_ok());
+        assert_eq!(vm.remaining_fuel(), Some(0));
+        assert_eq!(vm.consumed_fuel(), Some(call_cost));
+        let err = vm.execute("add", &params, 1).unwrap_err();
+        assert!(matches!(err, WasmEdgeError::OutOfFuel { .. }));
+
+        // Fuel can be added between calls
+        assert!(vm.add_fuel(call_cost).is_ok());
+        assert_eq!(vm.remaining_fuel(), Some(call_cost));
+        assert!(vm.execute("add", &params, 1).is_ok());
+
+        // The handler tops up the empty tank before each call, including plain `execute`
+        let mut grants = 1;
+        vm.set_pre_call_top_up(move |remaining| match (remaining, grants) {
+            (0, 1) => {
+                grants -= 1;
+                call_cost
+            }
+            _ => 0,
+        });
+        assert_eq!(vm.execute("add", &params, 1).unwrap(), vec![Val::I32(3)]);
+        let (result, _) = vm.execute_with_report("add", &params, 1);
+        assert!(matches!(result, Err(WasmEdgeError::OutOfFuel { .. })));
+        vm.remove_pre_call_top_up();
+
+        vm.remove_fuel();
+        assert_eq!(vm.remaining_fuel(), None);
+        assert!(vm.execute("add", &params, 1).is_ok());
+
+        // Without cost measuring WasmEdge does not count the cost, so fuel is refused
+        let mut vm = VMContext::create(None, None).unwrap();
+        assert!(vm.set_fuel(call_cost).is_err());
+        assert!(vm.add_fuel(call_cost).is_err());
+        assert_eq!(vm.remaining_fuel(), None);
+    }
+
+    #[test]
+    fn test_context_vm_profile() {
+        const WASM: &[u8] = &[
+            /* WASM header */
+            0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00, /* Type section: () -> (i32) */
+            0x01, 0x05, 0x01, 0x60, 0x00, 0x01, 0x7F, /* Function section */
+            0x03, 0x03, 0x02, 0x00, 0x00, /* Export section: "run" func 1 */
+            0x07, 0x07, 0x01, 0x03, 0x72, 0x75, 0x6E, 0x00, 0x01, /* Code section */
+            0x0A, 0x0F, 0x02, /* func 0: i32.const 7, return */
+            0x05, 0x00, 0x41, 0x07, 0x0F, 0x0B, /* func 1: call 0, call 0, i32.add */
+            0x07, 0x00, 0x10, 0x00, 0x10, 0x00, 0x6A, 0x0B,
+            /* Name section: func 0 is "seven" */
+            0x00, 0x0F, 0x04, 0x6E, 0x61, 0x6D, 0x65, /* function names */
+            0x01, 0x08, 0x01, 0x00, 0x05, 0x73, 0x65, 0x76, 0x65, 0x6E,
+        ];
+        let mut conf = ConfigureContext::create();
+        conf.set_instruction_counting(true);
+        conf.set_cost_measuring(true);
+        let mut vm = VMContext::create(Some(&conf), None).unwrap();
+        assert!(vm.profile().is_none());
+        assert!(vm.enable_profiling().is_ok());
+        assert!(vm.load_from_buffer(WASM).is_ok());
+        assert!(vm.validate().is_ok());
+        assert!(vm.instantiate().is_ok());
+        assert_eq!(vm.execute("run", &[], 1).unwrap(), vec![Val::I32(14)]);
+
+        let profile = vm.profile().unwrap();
+        let run = profile.function("run").unwrap();
+        let seven = profile.function("seven").unwrap();
+        assert_eq!((run.calls, seven.calls), (1, 2));
+        assert!(!run.host && run.self_instr_count > 0 && seven.self_cost > 0);
+        assert!(run.inclusive_time >= seven.inclusive_time);
+        let folded = profile.to_folded(ProfileWeight::Instructions);
+        let stacks: Vec<_> = folded
+            .lines()
+            .map(|line| line.split(' ').next().unwrap())
+            .collect();
+        assert_eq!(stacks, ["run", "run;seven"]);
+        assert!(profile.to_json_string().unwrap().contains("\"seven\""));
+
+        vm.reset_profile();
+        assert_eq!(vm.profile().unwrap().functions().count(), 0);
+
+        // Native artifacts are loaded without instrumentation
+        let mut compiler =
+            crate::context::aot::Compiler::create(ConfigureContext::create()).unwrap();
+        let artifact = compiler.compile_buffer(WASM).unwrap();
+        let dir = TempDir::new().unwrap();
+        let path = dir
+            .create_file(&format!("run{}", std::env::consts::DLL_SUFFIX), &artifact)
+            .unwrap();
+        let mut vm = VMContext::create(None, None).unwrap();
+        assert!(vm.enable_profiling().is_ok());
+        assert!(vm.load_from_file(&path).is_ok());
+
+        // The probes would outlive a store the VM does not own
+        let mut store = StoreContext::create();
+        let mut vm = VMContext::create(None, Some(&mut store)).unwrap();
+        assert!(vm.enable_profiling().is_err());
+    }
+
+    #[test]
+    fn test_context_vm_load_from_aot_buffer() {
+        use crate::context::aot::Compiler;
+
+        let wasm = std::fs::read(TPATH).unwrap();
+        let mut compiler = Compiler::create(ConfigureContext::create()).unwrap();
+        let artifact = compiler.compile_buffer(&wasm).unwrap();
+
+        let mut vm = VMContext::create(None, None).unwrap();
+        assert!(vm.load_from_aot_buffer(&artifact).is_ok());
+        assert!(vm.validate().is_ok());
+        assert!(vm.load_from_aot_buffer(&[]).is_err());
+    }
+
     fn create_extern_module(name: &str) -> Option<ImportObjectContext<'_>> {
         // create import object
         let result = ImportObjectContext::create(name, ptr::null_mut());
-        assert!(result.is_some());
+        assert!(result.is_ok());
         let mut imp_obj = result.unwrap();
 
-        let params = [
-            WasmEdgeValType::WasmEdge_ValType_ExternRef,
-            WasmEdgeValType::WasmEdge_ValType_I32,
-        ];
-        let returns = [WasmEdgeValType::WasmEdge_ValType_I32];
+        let params = [ValType::ExternRef, ValType::I32];
+        let returns = [ValType::I32];
         let result = FunctionTypeContext::create(Some(&params), Some(&returns));
         assert!(result.is_some());
         let host_ftype = result.unwrap();
@@ -910,30 +1469,30 @@ mod tests {
         let result = HostFunctionContext::create(&host_ftype, Some(extern_add_vm), 0);
         assert!(result.is_some());
         let mut host_func = result.unwrap();
-        imp_obj.add_host_function(host_name, &mut host_func);
+        assert!(imp_obj.add_host_function(host_name, &mut host_func).is_ok());
 
         // add host function "func-sub"
         let host_name = "func-sub";
         let result = HostFunctionContext::create(&host_ftype, Some(extern_sub_vm), 0);
         assert!(result.is_some());
         let mut host_func = result.unwrap();
-        imp_obj.add_host_function(host_name, &mut host_func);
+        assert!(imp_obj.add_host_function(host_name, &mut host_func).is_ok());
 
         // add host function "func-mul"
         let host_name = "func-mul";
         let result = HostFunctionContext::create(&host_ftype, Some(extern_mul_vm), 0);
         assert!(result.is_some());
         let mut host_func = result.unwrap();
-        imp_obj.add_host_function(host_name, &mut host_func);
+        assert!(imp_obj.add_host_function(host_name, &mut host_func).is_ok());
 
         // add host function "func-div"
         let host_name = "func-div";
         let result = HostFunctionContext::create(&host_ftype, Some(extern_div_vm), 0);
         assert!(result.is_some());
         let mut host_func = result.unwrap();
-        imp_obj.add_host_function(host_name, &mut host_func);
+        assert!(imp_obj.add_host_function(host_name, &mut host_func).is_ok());
 
-        let returns = [WasmEdgeValType::WasmEdge_ValType_I32];
+        let returns = [ValType::I32];
         let result = FunctionTypeContext::create(None, Some(&returns));
         assert!(result.is_some());
         let host_ftype = result.unwrap();
@@ -944,14 +1503,14 @@ mod tests {
         let result = HostFunctionContext::create(&host_ftype, Some(extern_term_vm), 0);
         assert!(result.is_some());
         let mut host_func = result.unwrap();
-        imp_obj.add_host_function(host_name, &mut host_func);
+        assert!(imp_obj.add_host_function(host_name, &mut host_func).is_ok());
 
         // add host function "func-fail"
         let host_name = "func-fail";
         let result = HostFunctionContext::create(&host_ftype, Some(extern_fail_vm), 0);
         assert!(result.is_some());
         let mut host_func = result.unwrap();
-        imp_obj.add_host_function(host_name, &mut host_func);
+        assert!(imp_obj.add_host_function(host_name, &mut host_func).is_ok());
 
         Some(imp_obj)
     }
@@ -988,9 +1547,12 @@ mod tests {
         returns: *mut WasmEdgeValue,
     ) -> we_ffi::WasmEdge_Result {
         let params = std::slice::from_raw_parts(params, 2);
-        let val1 = *(WasmEdgeValueGetExternRef(params[0]) as *const ::std::os::raw::c_int);
-        let val2 = WasmEdgeValueGetI32(params[1]);
-        let res = WasmEdgeValueGenI32(val1 + val2);
+        let val1 = match ExternRef::<i32>::try_from(Val::from(params[0])) {
+            Ok(val) => *val,
+            Err(_) => return we_ffi::WasmEdge_Result { Code: 2 },
+        };
+        let val2 = Val::from(params[1]).i32().unwrap_or_default();
+        let res = WasmEdgeValue::from(Val::I32(val1 + val2));
         returns.write(res);
 
         we_ffi::WasmEdge_Result { Code: 0 }
@@ -1004,9 +1566,12 @@ mod tests {
         returns: *mut WasmEdgeValue,
     ) -> we_ffi::WasmEdge_Result {
         let params = std::slice::from_raw_parts(params, 2);
-        let val1 = *(WasmEdgeValueGetExternRef(params[0]) as *const ::std::os::raw::c_int);
-        let val2 = WasmEdgeValueGetI32(params[1]);
-        let res = WasmEdgeValueGenI32(val1 - val2);
+        let val1 = match ExternRef::<i32>::try_from(Val::from(params[0])) {
+            Ok(val) => *val,
+            Err(_) => return we_ffi::WasmEdge_Result { Code: 2 },
+        };
+        let val2 = Val::from(params[1]).i32().unwrap_or_default();
+        let res = WasmEdgeValue::from(Val::I32(val1 - val2));
         returns.write(res);
 
         we_ffi::WasmEdge_Result { Code: 0 }
@@ -1020,9 +1585,12 @@ mod tests {
         returns: *mut WasmEdgeValue,
     ) -> we_ffi::WasmEdge_Result {
         let params = std::slice::from_raw_parts(params, 2);
-        let val1 = *(WasmEdgeValueGetExternRef(params[0]) as *const ::std::os::raw::c_int);
-        let val2 = WasmEdgeValueGetI32(params[1]);
-        let res = WasmEdgeValueGenI32(val1 * val2);
+        let val1 = match ExternRef::<i32>::try_from(Val::from(params[0])) {
+            Ok(val) => *val,
+            Err(_) => return we_ffi::WasmEdge_Result { Code: 2 },
+        };
+        let val2 = Val::from(params[1]).i32().unwrap_or_default();
+        let res = WasmEdgeValue::from(Val::I32(val1 * val2));
         returns.write(res);
 
         we_ffi::WasmEdge_Result { Code: 0 }
@@ -1036,9 +1604,12 @@ mod tests {
         returns: *mut WasmEdgeValue,
     ) -> we_ffi::WasmEdge_Result {
         let params = std::slice::from_raw_parts(params, 2);
-        let val1 = *(WasmEdgeValueGetExternRef(params[0]) as *const ::std::os::raw::c_int);
-        let val2 = WasmEdgeValueGetI32(params[1]);
-        let res = WasmEdgeValueGenI32(val1 / val2);
+        let val1 = match ExternRef::<i32>::try_from(Val::from(params[0])) {
+            Ok(val) => *val,
+            Err(_) => return we_ffi::WasmEdge_Result { Code: 2 },
+        };
+        let val2 = Val::from(params[1]).i32().unwrap_or_default();
+        let res = WasmEdgeValue::from(Val::I32(val1 / val2));
         returns.write(res);
 
         we_ffi::WasmEdge_Result { Code: 0 }
@@ -1051,7 +1622,7 @@ mod tests {
         _params: *const WasmEdgeValue,
         returns: *mut WasmEdgeValue,
     ) -> we_ffi::WasmEdge_Result {
-        let res = WasmEdgeValueGenI32(1234);
+        let res = WasmEdgeValue::from(Val::I32(1234));
         returns.write(res);
 
         we_ffi::WasmEdge_Result { Code: 1 }
@@ -1064,7 +1635,7 @@ mod tests {
         _params: *const WasmEdgeValue,
         returns: *mut WasmEdgeValue,
     ) -> we_ffi::WasmEdge_Result {
-        let res = WasmEdgeValueGenI32(5678);
+        let res = WasmEdgeValue::from(Val::I32(5678));
         returns.write(res);
 
         we_ffi::WasmEdge_Result { Code: 2 }
@@ -0,0 +1,380 @@
+//! Instruction costs for `StatisticsContext`, set by instruction name or class.
+
+use crate::error::{WasmEdgeError, WasmEdgeResult};
+use crate::opcode::{self, PREFIX_SIMD};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// The length of the cost table WasmEdge expects: one entry per 16-bit opcode.
+const COST_TABLE_LEN: usize = 1 << 16;
+
+/// A group of instructions, following the sections of the specification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum InstrClass {
+    /// Blocks, branches and calls, e.g. `br_if` or `call_indirect`.
+    Control,
+    /// `ref.null`, `ref.is_null` and `ref.func`.
+    Reference,
+    /// `drop` and `select`.
+    Parametric,
+    /// Local and global accesses.
+    Variable,
+    /// Table accesses and bulk table operations.
+    Table,
+    /// Loads, stores and bulk memory operations.
+    Memory,
+    /// Constants, arithmetic, comparisons and conversions.
+    Numeric,
+    /// All SIMD instructions, which can only be priced as a class.
+    Simd,
+}
+impl InstrClass {
+    const ALL: [InstrClass; 8] = [
+        InstrClass::Control,
+        InstrClass::Reference,
+        InstrClass::Parametric,
+        InstrClass::Variable,
+        InstrClass::Table,
+        InstrClass::Memory,
+        InstrClass::Numeric,
+        InstrClass::Simd,
+    ];
+
+    /// Returns an iterator over all classes.
+    pub fn all() -> impl Iterator<Item = InstrClass> {
+        Self::ALL.iter().copied()
+    }
+
+    /// Returns the class of a WasmEdge opcode, or `None` for unknown opcodes.
+    fn of(code: u16) -> Option<InstrClass> {
+        let class = match code {
+            _ if code >> 8 == u16::from(PREFIX_SIMD) => InstrClass::Simd,
+            _ if opcode::info(code).is_none() => return None,
+            0x00..=0x11 => InstrClass::Control,
+            0x1A..=0x1C => InstrClass::Parametric,
+            0x20..=0x24 => InstrClass::Variable,
+            0x25 | 0x26 | 0xFC0C..=0xFC11 => InstrClass::Table,
+            0x28..=0x40 | 0xFC08..=0xFC0B => InstrClass::Memory,
+            0xD0..=0xD2 => InstrClass::Reference,
+            _ => InstrClass::Numeric,
+        };
+        Some(class)
+    }
+
+    /// Returns the WasmEdge opcodes in the class.
+    fn opcodes(self) -> impl Iterator<Item = u16> {
+        // SIMD opcodes are not in the opcode table, so the whole prefix range is taken
+        let simd_len: u16 = match self {
+            InstrClass::Simd => 0x100,
+            _ => 0,
+        };
+        opcode::OPCODES
+            .iter()
+            .map(|op| op.code)
+            .filter(move |code| InstrClass::of(*code) == Some(self))
+            .chain((0..simd_len).map(|sub| u16::from(PREFIX_SIMD) << 8 | sub))
+    }
+}
+impl fmt::Display for InstrClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            InstrClass::Control => "control",
+            InstrClass::Reference => "reference",
+            InstrClass::Parametric => "parametric",
+            InstrClass::Variable => "variable",
+            InstrClass::Table => "table",
+            InstrClass::Memory => "memory",
+            InstrClass::Numeric => "numeric",
+            InstrClass::Simd => "simd",
+        };
+        f.write_str(name)
+    }
+}
+impl FromStr for InstrClass {
+    type Err = WasmEdgeError;
+
+    fn from_str(s: &str) -> WasmEdgeResult<Self> {
+        match InstrClass::all().find(|class| class.to_string() == s) {
+            Some(class) => Ok(class),
+            None => Err(WasmEdgeError::from(format!(
+                "Unknown instruction class '{}'",
+                s
+            ))),
+        }
+    }
+}
+
+/// The cost of each instruction, for `StatisticsContext::set_costs`.
+///
+/// An instruction costs what is set for its name, else for its class, else the default. A table
+/// can be built in code or read from JSON or TOML:
+///
+/// ```toml
+/// default = 1
+///
+/// [classes]
+/// memory = 4
+/// simd = 8
+///
+/// [instructions]
+/// "i32.div_s" = 10
+/// "call_indirect" = 20
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CostTable {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    default: Option<u64>,
+    /// Costs by class name; the names are checked by `validate`.
+    classes: BTreeMap<String, u64>,
+    /// Costs by instruction name; the names are checked by `validate`.
+    instructions: BTreeMap<String, u64>,
+}
+impl CostTable {
+    pub fn new() -> Self {
+        CostTable::default()
+    }
+
+    pub fn from_json_str(s: &str) -> WasmEdgeResult<Self> {
+        serde_json::from_str(s)
+            .map_err(|e| WasmEdgeError::from(format!("Invalid cost table: {}", e)))
+    }
+
+    pub fn from_toml_str(s: &str) -> WasmEdgeResult<Self> {
+        toml::from_str(s).map_err(|e| WasmEdgeError::from(format!("Invalid cost table: {}", e)))
+    }
+
+    /// Reads a cost table from a `.json` or `.toml` file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> WasmEdgeResult<Self> {
+        let path = path.as_ref();
+        let s = match std::fs::read_to_string(path) {
+            Ok(s) => s,
+            Err(e) => {
+                return Err(WasmEdgeError::from(format!(
+                    "Failed to read cost table file '{}': {}",
+                    path.display(),
+                    e
+                )))
+            }
+        };
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => Self::from_json_str(&s),
+            Some("toml") => Self::from_toml_str(&s),
+            _ => Err(WasmEdgeError::from(format!(
+                "Unknown cost table format of '{}', expected .json or .toml",
+                path.display()
+            ))),
+        }
+    }
+
+    pub fn to_json_string(&self) -> WasmEdgeResult<String> {
+        serde_json::to_string_pretty(self).map_err(|e| WasmEdgeError::from(e.to_string()))
+    }
+
+    pub fn to_toml_string(&self) -> WasmEdgeResult<String> {
+        toml::to_string(self).map_err(|e| WasmEdgeError::from(e.to_string()))
+    }
+
+    /// Sets the cost of instructions that have no cost by name or class.
+    pub fn default_cost(&mut self, cost: u64) -> &mut Self {
+        self.default = Some(cost);
+        self
+    }
+
+    pub fn class(&mut self, class: InstrClass, cost: u64) -> &mut Self {
+        self.classes.insert(class.to_string(), cost);
+        self
+    }
+
+    /// Sets the cost of an instruction by its name in the text format, e.g. `i32.add`.
+    ///
+    /// Unknown names are reported by `validate`.
+    pub fn instruction(&mut self, name: impl Into<String>, cost: u64) -> &mut Self {
+        self.instructions.insert(name.into(), cost);
+        self
+    }
+
+    /// Returns the cost of an instruction by name, or `None` if the name is unknown or has no
+    /// cost.
+    pub fn cost(&self, name: &str) -> Option<u64> {
+        let op = opcode::OPCODES.iter().find(|op| op.name == name)?;
+        self.cost_of(op.code)
+    }
+
+    /// Returns the cost of the class, or `None` if it has no cost of its own or by default.
+    pub fn class_cost(&self, class: InstrClass) -> Option<u64> {
+        self.classes
+            .get(&class.to_string())
+            .copied()
+            .or(self.default)
+    }
+
+    fn cost_of(&self, code: u16) -> Option<u64> {
+        if let Some(op) = opcode::info(code) {
+            if let Some(cost) = self.instructions.get(op.name) {
+                return Some(*cost);
+            }
+        }
+        self.class_cost(InstrClass::of(code)?)
+    }
+
+    /// Checks that every name is known and that every instruction has a cost.
+    pub fn validate(&self) -> WasmEdgeResult<()> {
+        let mut problems = vec![];
+        for name in self.classes.keys() {
+            if name.parse::<InstrClass>().is_err() {
+                problems.push(format!("unknown instruction class '{}'", name));
+            }
+        }
+        for name in self.instructions.keys() {
+            if !opcode::OPCODES.iter().any(|op| op.name == name) {
+                problems.push(format!("unknown instruction '{}'", name));
+            }
+        }
+        let uncovered: Vec<_> = opcode::OPCODES
+            .iter()
+            .filter(|op| self.cost_of(op.code).is_none())
+            .map(|op| op.name)
+            .chain(match self.class_cost(InstrClass::Simd) {
+                Some(_) => None,
+                None => Some("simd"),
+            })
+            .collect();
+        if !uncovered.is_empty() {
+            problems.push(format!("no cost for {}", uncovered.join(", ")));
+        }
+        match problems.is_empty() {
+            true => Ok(()),
+            false => Err(WasmEdgeError::from(format!(
+                "Invalid cost table: {}",
+                problems.join("; ")
+            ))),
+        }
+    }
+
+    /// Validates the table and lowers it to the array WasmEdge expects, indexed by opcode.
+    ///
+    /// Opcodes that do not exist cost 0.
+    pub fn to_array(&self) -> WasmEdgeResult<Vec<u64>> {
+        self.validate()?;
+        let mut costs = vec![0; COST_TABLE_LEN];
+        for class in InstrClass::all() {
+            for code in class.opcodes() {
+                costs[usize::from(code)] = self.cost_of(code).unwrap_or_default();
+            }
+        }
+        Ok(costs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instr_class() {
+        assert_eq!(InstrClass::of(0x0E), Some(InstrClass::Control));
+        assert_eq!(InstrClass::of(0x1C), Some(InstrClass::Parametric));
+        assert_eq!(InstrClass::of(0x23), Some(InstrClass::Variable));
+        assert_eq!(InstrClass::of(0xFC0E), Some(InstrClass::Table));
+        assert_eq!(InstrClass::of(0x36), Some(InstrClass::Memory));
+        assert_eq!(InstrClass::of(0xFC0A), Some(InstrClass::Memory));
+        assert_eq!(InstrClass::of(0xFC00), Some(InstrClass::Numeric));
+        assert_eq!(InstrClass::of(0xD2), Some(InstrClass::Reference));
+        assert_eq!(InstrClass::of(0xFD0C), Some(InstrClass::Simd));
+        assert_eq!(InstrClass::of(0x06), None);
+        assert_eq!("memory".parse::<InstrClass>().unwrap(), InstrClass::Memory);
+        assert!("memories".parse::<InstrClass>().is_err());
+
+        // Every known opcode is in exactly one class
+        let total: usize = InstrClass::all().map(|class| class.opcodes().count()).sum();
+        assert_eq!(total, opcode::OPCODES.len() + 0x100);
+    }
+
+    #[test]
+    fn test_cost_table() {
+        let mut table = CostTable::new();
+        table
+            .class(InstrClass::Memory, 4)
+            .instruction("i32.div_s", 10);
+        let err = table.validate().unwrap_err();
+        assert!(err.message().contains("no cost for unreachable"));
+        assert!(err.message().ends_with("simd"));
+        assert!(table.to_array().is_err());
+
+        table.default_cost(1);
+        assert!(table.validate().is_ok());
+        assert_eq!(table.cost("i32.div_s"), Some(10));
+        assert_eq!(table.cost("i64.store"), Some(4));
+        assert_eq!(table.cost("i32.add"), Some(1));
+        assert_eq!(table.cost("i32.plus"), None);
+        assert_eq!(table.class_cost(InstrClass::Simd), Some(1));
+
+        let costs = table.to_array().unwrap();
+        assert_eq!(costs.len(), 0x10000);
+        assert_eq!(costs[0x6D], 10);
+        assert_eq!(costs[0x37], 4);
+        assert_eq!(costs[0xFC0B], 4);
+        assert_eq!(costs[0x6A], 1);
+        assert_eq!(costs[0xFD0C], 1);
+        assert_eq!(costs[0x06], 0);
+
+        table.instruction("i32.plus", 1);
+        let err = table.validate().unwrap_err();
+        assert!(err.message().contains("unknown instruction 'i32.plus'"));
+    }
+
+    #[test]
+    fn test_cost_table_serde() {
+        let mut table = CostTable::new();
+        table
+            .default_cost(1)
+            .class(InstrClass::Simd, 8)
+            .instruction("call_indirect", 20);
+
+        let json = table.to_json_string().unwrap();
+        assert_eq!(CostTable::from_json_str(&json).unwrap(), table);
+        let toml = table.to_toml_string().unwrap();
+        assert_eq!(CostTable::from_toml_str(&toml).unwrap(), table);
+
+        let parsed = CostTable::from_toml_str(
+            r#"
+            default = 1
+
+            [classes]
+            simd = 8
+
+            [instructions]
+            "call_indirect" = 20
+            "#,
+        )
+        .unwrap();
+        assert_eq!(parsed, table);
+        let parsed = CostTable::from_json_str(
+            r#"{"default": 1, "classes": {"simd": 8}, "instructions": {"call_indirect": 20}}"#,
+        )
+        .unwrap();
+        assert_eq!(parsed, table);
+
+        assert!(CostTable::from_json_str(r#"{"defaults": 1}"#).is_err());
+        assert!(CostTable::from_toml_str("default = -1").is_err());
+        let parsed = CostTable::from_toml_str("[classes]\nvector = 1").unwrap();
+        assert!(parsed.validate().is_err());
+        assert!(CostTable::from_file("costs.yaml").is_err());
+    }
+
+    #[test]
+    fn test_cost_table_statistics() {
+        use crate::context::statistics::StatisticsContext;
+
+        let mut stat = StatisticsContext::create().unwrap();
+        let mut table = CostTable::new();
+        table.class(InstrClass::Control, 1);
+        assert!(stat.set_costs(&table).is_err());
+        table.default_cost(2);
+        assert!(stat.set_costs(&table).is_ok());
+    }
+}
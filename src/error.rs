@@ -1,43 +1,281 @@
+use crate::context::{aot::Incompatibility, validator::ValidationDiagnostic};
+use crate::policy::PolicyViolation;
 use std::ffi::CStr;
+use std::fmt;
 use wasmedge_sys::{WasmEdge_Result, WasmEdge_ResultGetCode, WasmEdge_ResultGetMessage};
 
-#[derive(Debug)]
-pub struct WasmEdgeError {
-    /// 0x00: Success
-    /// 0x01: Terminated -> Success
-    /// 0x02: Failed
-    /// 0x03: NullError
-    /// 0x20: File not found
-    pub code: usize,
-    pub message: String,
+/// The phase of the WasmEdge workflow in which an error occurred.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorPhase {
+    Load,
+    Validation,
+    Instantiation,
+    Execution,
+    /// A WasmEdge error not tied to one phase, e.g. a wrong VM workflow, an unknown function or a
+    /// failed host function. (0x01 - 0x1F)
+    General,
+    /// The error was raised by the crate before or after calling into WasmEdge.
+    Crate,
 }
-impl std::fmt::Display for WasmEdgeError {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::result::Result<(), std::fmt::Error> {
-        self.message.fmt(f)
+impl ErrorPhase {
+    /// Derives the phase from the range a raw WasmEdge error code falls in.
+    fn from_code(code: u32) -> ErrorPhase {
+        match code {
+            0x03 | 0x07 => ErrorPhase::Execution,
+            0x20..=0x3F => ErrorPhase::Load,
+            0x40..=0x5F => ErrorPhase::Validation,
+            0x60..=0x7F => ErrorPhase::Instantiation,
+            0x80..=u32::MAX => ErrorPhase::Execution,
+            _ => ErrorPhase::General,
+        }
+    }
+}
+impl fmt::Display for ErrorPhase {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let phase = match self {
+            ErrorPhase::Load => "loading",
+            ErrorPhase::Validation => "validation",
+            ErrorPhase::Instantiation => "instantiation",
+            ErrorPhase::Execution => "execution",
+            ErrorPhase::General => "general",
+            ErrorPhase::Crate => "crate",
+        };
+        f.write_str(phase)
+    }
+}
+
+/// The reason a WebAssembly execution trapped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TrapKind {
+    /// An `unreachable` instruction was executed. (0x89)
+    Unreachable,
+    /// A memory access was out of bounds. (0x88)
+    MemoryOutOfBounds,
+    /// A table access was out of bounds. (0x87)
+    TableOutOfBounds,
+    /// An integer operation overflowed, e.g. `i32.div_s` of `INT_MIN` by `-1`. (0x85)
+    IntegerOverflow,
+    /// An integer was divided by zero. (0x84)
+    DivideByZero,
+    /// A float could not be converted to an integer. (0x86)
+    InvalidConversionToInt,
+    /// An indirect call hit an uninitialized table element. (0x8A)
+    UninitializedElement,
+    /// An indirect call hit an undefined table element. (0x8B)
+    UndefinedElement,
+    /// An indirect call did not match the expected function type. (0x8C)
+    IndirectCallTypeMismatch,
+    /// The function signature did not match the arguments. (0x83)
+    FuncSignatureMismatch,
+    /// A reference had an unexpected type. (0x8E)
+    RefTypeMismatch,
+    /// The execution was interrupted. (0x07)
+    Interrupted,
+    /// Any other execution failure, e.g. a wrong instance address.
+    Other,
+}
+impl TrapKind {
+    fn from_code(code: u32) -> Option<TrapKind> {
+        let kind = match code {
+            0x07 => TrapKind::Interrupted,
+            0x83 => TrapKind::FuncSignatureMismatch,
+            0x84 => TrapKind::DivideByZero,
+            0x85 => TrapKind::IntegerOverflow,
+            0x86 => TrapKind::InvalidConversionToInt,
+            0x87 => TrapKind::TableOutOfBounds,
+            0x88 => TrapKind::MemoryOutOfBounds,
+            0x89 => TrapKind::Unreachable,
+            0x8A => TrapKind::UninitializedElement,
+            0x8B => TrapKind::UndefinedElement,
+            0x8C => TrapKind::IndirectCallTypeMismatch,
+            0x8E => TrapKind::RefTypeMismatch,
+            0x80..=0x9F => TrapKind::Other,
+            _ => return None,
+        };
+        Some(kind)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WasmEdgeError {
+    /// Loading failed, e.g. the file path is invalid or the binary is malformed. (0x20 - 0x3F)
+    Load { code: u32, message: String },
+    /// The module failed validation. (0x40 - 0x5F)
+    ///
+    /// Errors from `Validator::validate` carry a diagnostic locating the failure when the crate
+    /// could find it.
+    Validation {
+        code: u32,
+        message: String,
+        diagnostic: Option<Box<ValidationDiagnostic>>,
+    },
+    /// The module could not be instantiated, e.g. an import is unknown. (0x60 - 0x7F)
+    Instantiation { code: u32, message: String },
+    /// The execution trapped. (0x07, 0x80 - 0x9F)
+    Trap {
+        kind: TrapKind,
+        code: u32,
+        message: String,
+    },
+    /// The execution used up its fuel, i.e. the cost limit of the statistics context. (0x03)
+    OutOfFuel { code: u32, message: String },
+    /// Any other error reported by WasmEdge, e.g. a wrong VM workflow or an unknown function.
+    ///
+    /// A failed host function is reported as a generic runtime error. (0x02)
+    Runtime { code: u32, message: String },
+    /// The module is valid but breaks the rules of a `ValidationPolicy`.
+    Policy(Vec<PolicyViolation>),
+    /// An AOT artifact was compiled by another runtime version or with another configuration.
+    IncompatibleArtifact(Vec<Incompatibility>),
+    /// A module in the text format could not be assembled. `line` and `column` are 1-based.
+    Text {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// An error raised by the crate itself, e.g. an invalid argument.
+    Crate(String),
+}
+impl WasmEdgeError {
+    /// Creates an error from a raw WasmEdge error code and message.
+    pub fn from_code(code: u32, message: String) -> Self {
+        if let Some(kind) = TrapKind::from_code(code) {
+            return WasmEdgeError::Trap {
+                kind,
+                code,
+                message,
+            };
+        }
+        match code {
+            0x03 => WasmEdgeError::OutOfFuel { code, message },
+            0x20..=0x3F => WasmEdgeError::Load { code, message },
+            0x40..=0x5F => WasmEdgeError::Validation {
+                code,
+                message,
+                diagnostic: None,
+            },
+            0x60..=0x7F => WasmEdgeError::Instantiation { code, message },
+            _ => WasmEdgeError::Runtime { code, message },
+        }
+    }
+
+    /// Returns the raw WasmEdge error code, or `None` for errors raised by the crate.
+    pub fn code(&self) -> Option<u32> {
+        match self {
+            WasmEdgeError::Load { code, .. }
+            | WasmEdgeError::Validation { code, .. }
+            | WasmEdgeError::Instantiation { code, .. }
+            | WasmEdgeError::Trap { code, .. }
+            | WasmEdgeError::OutOfFuel { code, .. }
+            | WasmEdgeError::Runtime { code, .. } => Some(*code),
+            WasmEdgeError::Policy(_)
+            | WasmEdgeError::IncompatibleArtifact(_)
+            | WasmEdgeError::Text { .. }
+            | WasmEdgeError::Crate(_) => None,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            WasmEdgeError::Policy(_) => "validation policy violated",
+            WasmEdgeError::IncompatibleArtifact(_) => "incompatible AOT artifact",
+            WasmEdgeError::Load { message, .. }
+            | WasmEdgeError::Validation { message, .. }
+            | WasmEdgeError::Instantiation { message, .. }
+            | WasmEdgeError::Trap { message, .. }
+            | WasmEdgeError::OutOfFuel { message, .. }
+            | WasmEdgeError::Runtime { message, .. }
+            | WasmEdgeError::Text { message, .. }
+            | WasmEdgeError::Crate(message) => message,
+        }
+    }
+
+    pub fn phase(&self) -> ErrorPhase {
+        match self {
+            WasmEdgeError::Load { .. }
+            | WasmEdgeError::IncompatibleArtifact(_)
+            | WasmEdgeError::Text { .. } => ErrorPhase::Load,
+            WasmEdgeError::Validation { .. } | WasmEdgeError::Policy(_) => ErrorPhase::Validation,
+            WasmEdgeError::Instantiation { .. } => ErrorPhase::Instantiation,
+            WasmEdgeError::Trap { .. } | WasmEdgeError::OutOfFuel { .. } => ErrorPhase::Execution,
+            WasmEdgeError::Runtime { code, .. } => ErrorPhase::from_code(*code),
+            WasmEdgeError::Crate(_) => ErrorPhase::Crate,
+        }
+    }
+
+    /// Returns the diagnostic of a validation error, if one was found.
+    pub fn diagnostic(&self) -> Option<&ValidationDiagnostic> {
+        match self {
+            WasmEdgeError::Validation { diagnostic, .. } => diagnostic.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Returns the trap kind if the execution trapped.
+    pub fn trap_kind(&self) -> Option<TrapKind> {
+        match self {
+            WasmEdgeError::Trap { kind, .. } => Some(*kind),
+            _ => None,
+        }
+    }
+}
+impl fmt::Display for WasmEdgeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WasmEdgeError::Text {
+                line,
+                column,
+                message,
+            } => write!(f, "{} (at line {}, column {})", message, line, column),
+            WasmEdgeError::Validation {
+                code,
+                message,
+                diagnostic: Some(diagnostic),
+            } => write!(f, "{} (code: {:#04x}) {}", message, code, diagnostic),
+            WasmEdgeError::Policy(violations) => {
+                write!(f, "{}: ", self.message())?;
+                write_list(f, violations)
+            }
+            WasmEdgeError::IncompatibleArtifact(incompatibilities) => {
+                write!(f, "{}: ", self.message())?;
+                write_list(f, incompatibilities)
+            }
+            _ => match self.code() {
+                Some(code) => write!(f, "{} (code: {:#04x})", self.message(), code),
+                None => f.write_str(self.message()),
+            },
+        }
     }
 }
 impl std::error::Error for WasmEdgeError {}
+
+fn write_list<T: fmt::Display>(f: &mut fmt::Formatter, items: &[T]) -> fmt::Result {
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            f.write_str("; ")?;
+        }
+        write!(f, "{}", item)?;
+    }
+    Ok(())
+}
 impl From<String> for WasmEdgeError {
     fn from(message: String) -> WasmEdgeError {
-        WasmEdgeError { code: 49, message }
+        WasmEdgeError::Crate(message)
     }
 }
 impl From<std::ffi::NulError> for WasmEdgeError {
     fn from(e: std::ffi::NulError) -> WasmEdgeError {
-        WasmEdgeError {
-            code: 3,
-            message: e.to_string(),
-        }
+        WasmEdgeError::Crate(e.to_string())
     }
 }
 impl From<WasmEdge_Result> for WasmEdgeError {
     fn from(result: WasmEdge_Result) -> WasmEdgeError {
-        let code = unsafe { WasmEdge_ResultGetCode(result) as usize };
+        let code = unsafe { WasmEdge_ResultGetCode(result) };
         let message = unsafe {
             let c_str = CStr::from_ptr(WasmEdge_ResultGetMessage(result));
             c_str.to_string_lossy().into_owned()
         };
-        WasmEdgeError { code, message }
+        WasmEdgeError::from_code(code, message)
     }
 }
 
@@ -54,15 +292,54 @@ mod tests {
         let mut err: WasmEdgeError;
         res = WasmEdge_Result { Code: 0 };
         err = WasmEdgeError::from(res);
-        assert_eq!(err.code, 0);
-        assert!(!err.message.is_empty());
+        assert_eq!(err.code(), Some(0));
+        assert!(!err.message().is_empty());
         res = WasmEdge_Result { Code: 1 };
         err = WasmEdgeError::from(res);
-        assert_eq!(err.code, 1);
-        assert!(!err.message.is_empty());
+        assert_eq!(err.code(), Some(1));
+        assert!(!err.message().is_empty());
         res = WasmEdge_Result { Code: 2 };
         err = WasmEdgeError::from(res);
-        assert_eq!(err.code, 2);
-        assert!(!err.message.is_empty());
+        assert_eq!(err.code(), Some(2));
+        assert!(!err.message().is_empty());
+        assert!(matches!(err, WasmEdgeError::Runtime { code: 2, .. }));
+        assert_eq!(err.phase(), ErrorPhase::General);
+    }
+
+    #[test]
+    fn test_error_categories() {
+        let err = WasmEdgeError::from(WasmEdge_Result { Code: 0x20 });
+        assert!(matches!(err, WasmEdgeError::Load { code: 0x20, .. }));
+        assert_eq!(err.phase(), ErrorPhase::Load);
+        assert_eq!(err.trap_kind(), None);
+
+        let err = WasmEdgeError::from(WasmEdge_Result { Code: 0x41 });
+        assert_eq!(err.phase(), ErrorPhase::Validation);
+
+        let err = WasmEdgeError::from(WasmEdge_Result { Code: 0x62 });
+        assert_eq!(err.phase(), ErrorPhase::Instantiation);
+
+        let err = WasmEdgeError::from(WasmEdge_Result { Code: 0x88 });
+        assert_eq!(err.trap_kind(), Some(TrapKind::MemoryOutOfBounds));
+        assert_eq!(err.phase(), ErrorPhase::Execution);
+        assert_eq!(err.code(), Some(0x88));
+
+        let err = WasmEdgeError::from(WasmEdge_Result { Code: 0x03 });
+        assert!(matches!(err, WasmEdgeError::OutOfFuel { code: 0x03, .. }));
+        assert_eq!(err.trap_kind(), None);
+        assert_eq!(err.phase(), ErrorPhase::Execution);
+
+        let err = WasmEdgeError::from(WasmEdge_Result { Code: 0x05 });
+        assert!(matches!(err, WasmEdgeError::Runtime { code: 0x05, .. }));
+        assert_eq!(err.phase(), ErrorPhase::General);
+
+        let err = WasmEdgeError::from(WasmEdge_Result { Code: 0xA0 });
+        assert!(matches!(err, WasmEdgeError::Runtime { code: 0xA0, .. }));
+        assert_eq!(err.phase(), ErrorPhase::Execution);
+
+        let err = WasmEdgeError::from(String::from("bad argument"));
+        assert_eq!(err.code(), None);
+        assert_eq!(err.phase(), ErrorPhase::Crate);
+        assert_eq!(err.to_string(), "bad argument");
     }
 }
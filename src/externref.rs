@@ -0,0 +1,215 @@
+use crate::context::store::StoreContext;
+use crate::error::{WasmEdgeError, WasmEdgeResult};
+use crate::value::Val;
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::num::NonZeroUsize;
+use std::ops::Deref;
+use std::rc::Rc;
+
+struct Entry {
+    value: Rc<dyn Any>,
+    store: usize,
+}
+
+/// Host-side slab holding the values behind every live `externref`.
+///
+/// Keys are never reused, so a handle that outlives its entry can never alias a newer value.
+#[derive(Default)]
+struct Slab {
+    entries: HashMap<usize, Entry>,
+    next_key: usize,
+}
+impl Slab {
+    fn insert(&mut self, store: usize, value: Rc<dyn Any>) -> ExternHandle {
+        self.next_key += 1;
+        let key = self.next_key;
+        self.entries.insert(key, Entry { value, store });
+        ExternHandle(NonZeroUsize::new(key).expect("slab keys start at 1"))
+    }
+
+    fn get(&self, handle: ExternHandle) -> Option<Rc<dyn Any>> {
+        self.entries
+            .get(&handle.0.get())
+            .map(|entry| entry.value.clone())
+    }
+
+    fn release_store(&mut self, store: usize) {
+        self.entries.retain(|_, entry| entry.store != store);
+    }
+}
+
+thread_local! {
+    static SLAB: RefCell<Slab> = RefCell::new(Slab::default());
+}
+
+/// Drops every value registered against the given store.
+pub(crate) fn release_store(store: usize) {
+    SLAB.with(|slab| slab.borrow_mut().release_store(store));
+}
+
+#[cfg(test)]
+pub(crate) fn live_count(store: usize) -> usize {
+    SLAB.with(|slab| {
+        slab.borrow()
+            .entries
+            .values()
+            .filter(|entry| entry.store == store)
+            .count()
+    })
+}
+
+/// An untyped handle to a host value, as carried by an `externref` value.
+///
+/// The handle is what crosses into WebAssembly; the value itself stays in a host-side slab
+/// until the store it was registered with is dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ExternHandle(NonZeroUsize);
+impl ExternHandle {
+    pub(crate) fn from_raw(ptr: *mut std::os::raw::c_void) -> Option<Self> {
+        NonZeroUsize::new(ptr as usize).map(ExternHandle)
+    }
+
+    pub(crate) fn as_raw(&self) -> *mut std::os::raw::c_void {
+        self.0.get() as *mut _
+    }
+
+    /// Returns `true` if the value behind this handle has not been released yet.
+    pub fn is_live(&self) -> bool {
+        SLAB.with(|slab| slab.borrow().entries.contains_key(&self.0.get()))
+    }
+
+    /// Returns a typed reference to the value behind this handle, or `None` if the value has been
+    /// released or is not a `T`.
+    pub fn downcast<T: 'static>(&self) -> Option<ExternRef<T>> {
+        let value = SLAB.with(|slab| slab.borrow().get(*self))?;
+        let value = value.downcast::<T>().ok()?;
+        Some(ExternRef {
+            handle: *self,
+            value,
+        })
+    }
+}
+
+/// An owned, typed reference to a host value that can be passed to WebAssembly as an `externref`.
+///
+/// The value is registered with a store and stays reachable from WebAssembly, e.g. from a table
+/// or a global, until that store is dropped. The `ExternRef` itself keeps the value alive on the
+/// host side for as long as it exists.
+pub struct ExternRef<T: 'static> {
+    handle: ExternHandle,
+    value: Rc<T>,
+}
+impl<T: 'static> ExternRef<T> {
+    pub fn new(store: &StoreContext, value: T) -> Self {
+        let value = Rc::new(value);
+        let handle = SLAB.with(|slab| {
+            slab.borrow_mut()
+                .insert(store.raw as usize, value.clone() as Rc<dyn Any>)
+        });
+        ExternRef { handle, value }
+    }
+
+    pub fn handle(&self) -> ExternHandle {
+        self.handle
+    }
+}
+impl<T: 'static> Deref for ExternRef<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+impl<T: 'static> Clone for ExternRef<T> {
+    fn clone(&self) -> Self {
+        ExternRef {
+            handle: self.handle,
+            value: self.value.clone(),
+        }
+    }
+}
+impl<T: 'static + fmt::Debug> fmt::Debug for ExternRef<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ExternRef")
+            .field("handle", &self.handle)
+            .field("value", &self.value)
+            .finish()
+    }
+}
+impl<T: 'static> From<&ExternRef<T>> for Val {
+    fn from(r: &ExternRef<T>) -> Self {
+        Val::ExternRef(Some(r.handle))
+    }
+}
+impl<T: 'static> From<ExternRef<T>> for Val {
+    fn from(r: ExternRef<T>) -> Self {
+        Val::ExternRef(Some(r.handle))
+    }
+}
+impl<T: 'static> TryFrom<Val> for ExternRef<T> {
+    type Error = WasmEdgeError;
+
+    fn try_from(val: Val) -> WasmEdgeResult<Self> {
+        match val {
+            Val::ExternRef(Some(handle)) => handle.downcast::<T>().ok_or_else(|| {
+                WasmEdgeError::from(format!(
+                    "The externref does not refer to a live value of type '{}'",
+                    std::any::type_name::<T>()
+                ))
+            }),
+            Val::ExternRef(None) => Err(WasmEdgeError::from(String::from(
+                "Cannot convert a null externref into an ExternRef",
+            ))),
+            _ => Err(WasmEdgeError::from(format!(
+                "Mismatched value type. Expected: externref, Got: {}",
+                val.ty()
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::WasmEdgeValue;
+
+    #[test]
+    fn test_externref_downcast() {
+        let store = StoreContext::create();
+        let r = ExternRef::new(&store, String::from("hello"));
+        assert_eq!(r.as_str(), "hello");
+
+        // Round trip through the raw C value
+        let raw = WasmEdgeValue::from(Val::from(&r));
+        let val = Val::from(raw);
+        assert_eq!(val, Val::ExternRef(Some(r.handle())));
+        let back = ExternRef::<String>::try_from(val).unwrap();
+        assert_eq!(*back, "hello");
+
+        // Wrong type, null and non-reference values
+        assert!(ExternRef::<i32>::try_from(val).is_err());
+        assert!(ExternRef::<String>::try_from(Val::ExternRef(None)).is_err());
+        assert!(ExternRef::<String>::try_from(Val::I32(1)).is_err());
+    }
+
+    #[test]
+    fn test_externref_released_with_store() {
+        let store = StoreContext::create();
+        let store_id = store.raw as usize;
+        let r = ExternRef::new(&store, 42i32);
+        let handle = r.handle();
+        assert_eq!(live_count(store_id), 1);
+        assert!(handle.is_live());
+
+        drop(store);
+        assert_eq!(live_count(store_id), 0);
+        assert!(!handle.is_live());
+        assert!(handle.downcast::<i32>().is_none());
+        // The host side still owns its copy
+        assert_eq!(*r, 42);
+    }
+}
@@ -0,0 +1,15 @@
+//! Modules shared by the tests of several modules.
+
+/// A module exporting `add`, which returns the sum of its two `i32` parameters.
+pub(crate) const ADD_WASM: &[u8] = &[
+    /* WASM header */
+    0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00, /* Type section */
+    0x01, 0x07, 0x01, /* function type {i32, i32} -> {i32} */
+    0x60, 0x02, 0x7F, 0x7F, 0x01, 0x7F, /* Function section */
+    0x03, 0x02, 0x01, 0x00, /* Export section */
+    0x07, 0x07, 0x01, /* export name: "add" */
+    0x03, 0x61, 0x64, 0x64, /* export desc: func 0 */
+    0x00, 0x00, /* Code section */
+    0x0A, 0x09, 0x01, 0x07, 0x00, /* local.get 0, local.get 1, i32.add */
+    0x20, 0x00, 0x20, 0x01, 0x6A, 0x0B,
+];
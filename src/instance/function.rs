@@ -1,23 +1,29 @@
+use crate::context::{ast::FuncType, store::StoreContext};
 use crate::error::{WasmEdgeError, WasmEdgeResult};
 use crate::types::*;
+use crate::value::Val;
+use std::marker::PhantomData;
 use std::{mem, ptr};
 use wasmedge_sys::ffi as we_ffi;
 
 pub struct FunctionTypeContext {
     pub(crate) raw: *mut we_ffi::WasmEdge_FunctionTypeContext,
+    pub(crate) _drop: bool,
 }
 impl FunctionTypeContext {
     pub fn create(
-        params: Option<&[WasmEdgeValType]>,
-        returns: Option<&[WasmEdgeValType]>,
+        params: Option<&[ValType]>,
+        returns: Option<&[ValType]>,
     ) -> Option<FunctionTypeContext> {
-        let (param_len, params) = match params {
-            Some(params) => (params.len(), params.as_ptr()),
-            None => (0, ptr::null()),
+        let params = to_raw_types(params.unwrap_or_default());
+        let returns = to_raw_types(returns.unwrap_or_default());
+        let (param_len, params) = match params.is_empty() {
+            true => (0, ptr::null()),
+            false => (params.len(), params.as_ptr()),
         };
-        let (ret_len, returns) = match returns {
-            Some(returns) => (returns.len(), returns.as_ptr()),
-            None => (0, ptr::null()),
+        let (ret_len, returns) = match returns.is_empty() {
+            true => (0, ptr::null()),
+            false => (returns.len(), returns.as_ptr()),
         };
 
         let raw = unsafe {
@@ -31,7 +37,7 @@ impl FunctionTypeContext {
 
         match raw.is_null() {
             true => None,
-            false => Some(FunctionTypeContext { raw }),
+            false => Some(FunctionTypeContext { raw, _drop: true }),
         }
     }
 
@@ -41,24 +47,22 @@ impl FunctionTypeContext {
 
     pub fn parameters(
         &self,
-        buf: &mut [mem::MaybeUninit<WasmEdgeValType>],
-    ) -> WasmEdgeResult<(usize, Vec<WasmEdgeValType>)> {
+        buf: &mut [mem::MaybeUninit<ValType>],
+    ) -> WasmEdgeResult<(usize, Vec<ValType>)> {
         let max_len = self.parameters_len();
         match 0 < buf.len() && buf.len() <= max_len {
             true => {
+                let mut raw_types = vec![we_ffi::WasmEdge_ValType::WasmEdge_ValType_I32; buf.len()];
                 let len = unsafe {
                     we_ffi::WasmEdge_FunctionTypeGetParameters(
                         self.raw,
-                        buf.as_mut_ptr() as *mut _,
-                        buf.len() as u32,
+                        raw_types.as_mut_ptr(),
+                        raw_types.len() as u32,
                     )
                 };
-                let ty_vec = unsafe {
-                    mem::MaybeUninit::slice_assume_init_ref(&buf[..buf.len()])
-                };
                 let mut val_types = vec![];
-                for ty in ty_vec {
-                    val_types.push(ty.clone());
+                for (slot, ty) in buf.iter_mut().zip(raw_types) {
+                    val_types.push(*slot.write(ValType::from(ty)));
                 }
 
                 Ok((len as usize, val_types))
@@ -76,24 +80,22 @@ impl FunctionTypeContext {
 
     pub fn returns(
         &self,
-        buf: &mut [mem::MaybeUninit<WasmEdgeValType>],
-    ) -> WasmEdgeResult<(usize, Vec<WasmEdgeValType>)> {
+        buf: &mut [mem::MaybeUninit<ValType>],
+    ) -> WasmEdgeResult<(usize, Vec<ValType>)> {
         let max_len = self.returns_len();
         match 0 < buf.len() && buf.len() <= max_len {
             true => {
+                let mut raw_types = vec![we_ffi::WasmEdge_ValType::WasmEdge_ValType_I32; buf.len()];
                 let len = unsafe {
                     we_ffi::WasmEdge_FunctionTypeGetReturns(
                         self.raw,
-                        buf.as_mut_ptr() as *mut _,
-                        buf.len() as u32,
+                        raw_types.as_mut_ptr(),
+                        raw_types.len() as u32,
                     )
                 };
-                let ty_vec = unsafe {
-                    mem::MaybeUninit::slice_assume_init_ref(&buf[..buf.len()])
-                };
                 let mut val_types = vec![];
-                for ty in ty_vec {
-                    val_types.push(ty.clone());
+                for (slot, ty) in buf.iter_mut().zip(raw_types) {
+                    val_types.push(*slot.write(ValType::from(ty)));
                 }
 
                 Ok((len as usize, val_types))
@@ -104,22 +106,72 @@ impl FunctionTypeContext {
             ))),
         }
     }
+
+    /// Copies the parameter and return types out of the context.
+    pub(crate) fn to_func_type(&self) -> FuncType {
+        let mut params =
+            vec![we_ffi::WasmEdge_ValType::WasmEdge_ValType_I32; self.parameters_len()];
+        let mut returns = vec![we_ffi::WasmEdge_ValType::WasmEdge_ValType_I32; self.returns_len()];
+        unsafe {
+            we_ffi::WasmEdge_FunctionTypeGetParameters(
+                self.raw,
+                params.as_mut_ptr(),
+                params.len() as u32,
+            );
+            we_ffi::WasmEdge_FunctionTypeGetReturns(
+                self.raw,
+                returns.as_mut_ptr(),
+                returns.len() as u32,
+            );
+        }
+        FuncType {
+            params: params.into_iter().map(ValType::from).collect(),
+            returns: returns.into_iter().map(ValType::from).collect(),
+        }
+    }
 }
 impl Drop for FunctionTypeContext {
     fn drop(&mut self) {
-        unsafe { we_ffi::WasmEdge_FunctionTypeDelete(self.raw) }
+        if !self.raw.is_null() {
+            if self._drop {
+                unsafe { we_ffi::WasmEdge_FunctionTypeDelete(self.raw) }
+            } else {
+                self.raw = std::ptr::null_mut();
+            }
+        }
     }
 }
 impl Default for FunctionTypeContext {
     fn default() -> Self {
         FunctionTypeContext {
             raw: std::ptr::null_mut(),
+            _drop: false,
         }
     }
 }
+impl PartialEq for FunctionTypeContext {
+    fn eq(&self, other: &Self) -> bool {
+        let mut params1 = vec![mem::MaybeUninit::uninit(); self.parameters_len()];
+        let mut params2 = vec![mem::MaybeUninit::uninit(); other.parameters_len()];
+        let mut returns1 = vec![mem::MaybeUninit::uninit(); self.returns_len()];
+        let mut returns2 = vec![mem::MaybeUninit::uninit(); other.returns_len()];
+        params1.len() == params2.len()
+            && returns1.len() == returns2.len()
+            && self.parameters(&mut params1).ok().map(|(_, v)| v)
+                == other.parameters(&mut params2).ok().map(|(_, v)| v)
+            && self.returns(&mut returns1).ok().map(|(_, v)| v)
+                == other.returns(&mut returns2).ok().map(|(_, v)| v)
+    }
+}
+
+fn to_raw_types(types: &[ValType]) -> Vec<we_ffi::WasmEdge_ValType> {
+    types.iter().map(|ty| (*ty).into()).collect()
+}
 
 pub struct HostFunctionContext {
     pub(crate) raw: *mut we_ffi::WasmEdge_HostFunctionContext,
+    /// The type the function was created with, recorded for `link_check`.
+    pub(crate) ty: FuncType,
 }
 impl HostFunctionContext {
     pub fn create(
@@ -130,7 +182,10 @@ impl HostFunctionContext {
         let raw = unsafe { we_ffi::WasmEdge_HostFunctionCreate(func_type.raw, host_func, cost) };
         match raw.is_null() {
             true => None,
-            false => Some(HostFunctionContext { raw }),
+            false => Some(HostFunctionContext {
+                raw,
+                ty: func_type.to_func_type(),
+            }),
         }
     }
 }
@@ -140,15 +195,30 @@ impl Drop for HostFunctionContext {
     }
 }
 
-#[allow(dead_code)]
-pub struct FunctionInstanceContext {
+pub struct FunctionInstanceContext<'store, 'vm: 'store> {
     pub(crate) raw: *mut we_ffi::WasmEdge_FunctionInstanceContext,
+    pub(crate) _marker: PhantomData<&'store StoreContext<'vm>>,
+}
+impl<'store, 'vm: 'store> FunctionInstanceContext<'store, 'vm> {
+    /// Returns the type of this function instance.
+    ///
+    /// The returned context is owned by the function instance.
+    pub fn function_type(&self) -> Option<FunctionTypeContext> {
+        let raw = unsafe { we_ffi::WasmEdge_FunctionInstanceGetFunctionType(self.raw) };
+        match raw.is_null() {
+            true => None,
+            false => Some(FunctionTypeContext {
+                raw: raw as *mut _,
+                _drop: false,
+            }),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{context::import_object::ImportObjectContext, context::vm::VMContext, value::*};
+    use crate::{context::import_object::ImportObjectContext, context::vm::VMContext, value::Val};
     use std::mem;
 
     #[test]
@@ -161,10 +231,10 @@ mod tests {
             returns: *mut WasmEdgeValue,
         ) -> we_ffi::WasmEdge_Result {
             let params = std::slice::from_raw_parts(params, 2);
-            let val1 = WasmEdgeValueGetI32(params[0]);
-            let val2 = WasmEdgeValueGetI32(params[1]);
+            let val1 = Val::from(params[0]).i32().unwrap_or_default();
+            let val2 = Val::from(params[1]).i32().unwrap_or_default();
             println!("Host function \"Add\": {} + {}\n", val1, val2);
-            let res = WasmEdgeValueGenI32(val1 + val2);
+            let res = WasmEdgeValue::from(Val::I32(val1 + val2));
             returns.write(res);
 
             we_ffi::WasmEdge_Result { Code: 0 }
@@ -199,34 +269,31 @@ mod tests {
         let mut imp_obj = ImportObjectContext::create("extern", ptr::null_mut()).unwrap();
 
         // Create and add a host function instance into the import object.
-        let params = [
-            WasmEdgeValType::WasmEdge_ValType_I32,
-            WasmEdgeValType::WasmEdge_ValType_I32,
-        ];
-        let returns = [WasmEdgeValType::WasmEdge_ValType_I32];
+        let params = [ValType::I32, ValType::I32];
+        let returns = [ValType::I32];
         let result = FunctionTypeContext::create(Some(&params), Some(&returns));
         assert!(result.is_some());
         let func_type = result.unwrap();
         assert!(!func_type.raw.is_null());
         let res = HostFunctionContext::create(&func_type, Some(Add), 0);
         let mut host_func = res.unwrap();
-        imp_obj.add_host_function("func-add", &mut host_func);
+        assert!(imp_obj
+            .add_host_function("func-add", &mut host_func)
+            .is_ok());
 
         // register import-object
         vm.register_module_from_import_object(&imp_obj).unwrap();
 
         // The parameters and returns arrays.
-        let params = vec![WasmEdgeValueGenI32(1234), WasmEdgeValueGenI32(5678)];
-        let mut out: [mem::MaybeUninit<WasmEdgeValue>; 1] = mem::MaybeUninit::uninit_array();
+        let params = vec![Val::I32(1234), Val::I32(5678)];
 
         // Run the WASM function from file.
-        let result =
-            vm.run_wasm_from_buffer(wasm_buf.as_slice(), "addTwo", params.as_slice(), &mut out);
+        let result = vm.run_wasm_from_buffer(wasm_buf.as_slice(), "addTwo", params.as_slice(), 1);
 
         assert!(result.is_ok());
         let values = result.unwrap();
         assert_eq!(values.len(), 1);
-        assert_eq!(WasmEdgeValueGetI32(values[0]), 6912);
+        assert_eq!(values[0], Val::I32(6912));
 
         let result = vm.function_type("addTwo");
         assert!(result.is_some());
@@ -234,29 +301,25 @@ mod tests {
         assert_eq!(1, func_type.returns_len());
         assert_eq!(2, func_type.parameters_len());
 
-        let mut buf: [mem::MaybeUninit<WasmEdgeValType>; 1] = mem::MaybeUninit::uninit_array();
+        let mut buf: [mem::MaybeUninit<ValType>; 1] = mem::MaybeUninit::uninit_array();
         let result = func_type.returns(&mut buf);
         assert!(result.is_ok());
         let (size, returns_type) = result.unwrap();
         assert_eq!(1, size);
-        assert_eq!(WasmEdgeValType::WasmEdge_ValType_I32, returns_type[0]);
+        assert_eq!(ValType::I32, returns_type[0]);
     }
 
     #[test]
     fn test_instance_function_function_type() {
         let params = vec![
-            WasmEdgeValType::WasmEdge_ValType_I32,
-            WasmEdgeValType::WasmEdge_ValType_I64,
-            WasmEdgeValType::WasmEdge_ValType_ExternRef,
-            WasmEdgeValType::WasmEdge_ValType_V128,
-            WasmEdgeValType::WasmEdge_ValType_F64,
-            WasmEdgeValType::WasmEdge_ValType_F32,
-        ];
-        let returns = vec![
-            WasmEdgeValType::WasmEdge_ValType_FuncRef,
-            WasmEdgeValType::WasmEdge_ValType_ExternRef,
-            WasmEdgeValType::WasmEdge_ValType_V128,
+            ValType::I32,
+            ValType::I64,
+            ValType::ExternRef,
+            ValType::V128,
+            ValType::F64,
+            ValType::F32,
         ];
+        let returns = vec![ValType::FuncRef, ValType::ExternRef, ValType::V128];
         let result = FunctionTypeContext::create(Some(&params), Some(&returns));
         assert!(result.is_some());
         let func_type = result.unwrap();
@@ -264,27 +327,27 @@ mod tests {
         assert_eq!(func_type.parameters_len(), 6 as usize);
         assert_eq!(func_type.returns_len(), 3 as usize);
 
-        let mut buf1 = mem::MaybeUninit::<WasmEdgeValType>::uninit_array::<6>();
+        let mut buf1 = mem::MaybeUninit::<ValType>::uninit_array::<6>();
         let result = func_type.parameters(&mut buf1);
         assert!(result.is_ok());
         let (len, val_types) = result.unwrap();
         assert_eq!(len, 6);
         assert_eq!(params, val_types);
 
-        let mut buf2 = mem::MaybeUninit::<WasmEdgeValType>::uninit_array::<2>();
+        let mut buf2 = mem::MaybeUninit::<ValType>::uninit_array::<2>();
         let result = func_type.parameters(&mut buf2);
         assert!(result.is_ok());
         let (len, val_types) = result.unwrap();
         assert_eq!(len, 6);
         assert_eq!(&params[..2], &val_types);
 
-        let mut buf1 = mem::MaybeUninit::<WasmEdgeValType>::uninit_array::<3>();
+        let mut buf1 = mem::MaybeUninit::<ValType>::uninit_array::<3>();
         let result = func_type.returns(&mut buf1);
         assert!(result.is_ok());
         let (len, val_types) = result.unwrap();
         assert_eq!(len, 3);
         assert_eq!(returns, val_types);
-        let mut buf1 = mem::MaybeUninit::<WasmEdgeValType>::uninit_array::<2>();
+        let mut buf1 = mem::MaybeUninit::<ValType>::uninit_array::<2>();
         let result = func_type.returns(&mut buf1);
         assert!(result.is_ok());
         let (len, val_types) = result.unwrap();
@@ -1,5 +1,7 @@
-use crate::context::store::StoreContext;
+use crate::context::{ast::GlobalType, store::StoreContext};
+use crate::error::{WasmEdgeError, WasmEdgeResult};
 use crate::types::*;
+use crate::value::Val;
 use std::marker::PhantomData;
 use wasmedge_sys::ffi as we_ffi;
 
@@ -9,8 +11,8 @@ pub struct GlobalInstanceContext<'store, 'vm: 'store> {
     pub(crate) _drop: bool,
 }
 impl<'store, 'vm: 'store> GlobalInstanceContext<'store, 'vm> {
-    pub fn create(value: WasmEdgeValue, mutable: WasmEdgeMutability) -> Option<Self> {
-        let raw = unsafe { we_ffi::WasmEdge_GlobalInstanceCreate(value, mutable) };
+    pub fn create(value: Val, mutable: Mutability) -> Option<Self> {
+        let raw = unsafe { we_ffi::WasmEdge_GlobalInstanceCreate(value.into(), mutable.into()) };
         match raw.is_null() {
             true => None,
             false => Some(GlobalInstanceContext {
@@ -21,20 +23,42 @@ impl<'store, 'vm: 'store> GlobalInstanceContext<'store, 'vm> {
         }
     }
 
-    pub fn val_type(&self) -> WasmEdgeValType {
-        unsafe { we_ffi::WasmEdge_GlobalInstanceGetValType(self.raw) }
+    pub fn val_type(&self) -> ValType {
+        ValType::from(unsafe { we_ffi::WasmEdge_GlobalInstanceGetValType(self.raw) })
     }
 
-    pub fn mutability(&self) -> WasmEdgeMutability {
-        unsafe { we_ffi::WasmEdge_GlobalInstanceGetMutability(self.raw) }
+    pub fn mutability(&self) -> Mutability {
+        Mutability::from(unsafe { we_ffi::WasmEdge_GlobalInstanceGetMutability(self.raw) })
     }
 
-    pub fn value(&self) -> WasmEdgeValue {
-        unsafe { we_ffi::WasmEdge_GlobalInstanceGetValue(self.raw) }
+    pub(crate) fn global_type(&self) -> GlobalType {
+        GlobalType {
+            val_type: self.val_type(),
+            mutability: self.mutability(),
+        }
+    }
+
+    pub fn value(&self) -> Val {
+        Val::from(unsafe { we_ffi::WasmEdge_GlobalInstanceGetValue(self.raw) })
     }
 
-    pub fn value_mut(&self, value: WasmEdgeValue) {
-        unsafe { we_ffi::WasmEdge_GlobalInstanceSetValue(self.raw, value) }
+    /// Sets the value of a mutable global. Fails if the global is constant or `value` has
+    /// another type.
+    pub fn value_mut(&self, value: Val) -> WasmEdgeResult<()> {
+        if self.mutability() == Mutability::Const {
+            return Err(WasmEdgeError::from(String::from(
+                "Cannot set the value of a constant global",
+            )));
+        }
+        if value.ty() != self.val_type() {
+            return Err(WasmEdgeError::from(format!(
+                "Mismatched value type. Expected: {}, Got: {}",
+                self.val_type(),
+                value.ty()
+            )));
+        }
+        unsafe { we_ffi::WasmEdge_GlobalInstanceSetValue(self.raw, value.into()) }
+        Ok(())
     }
 }
 impl<'store, 'vm: 'store> Drop for GlobalInstanceContext<'store, 'vm> {
@@ -52,58 +76,55 @@ impl<'store, 'vm: 'store> Drop for GlobalInstanceContext<'store, 'vm> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::value::*;
+    use crate::value::V128;
 
     #[test]
     fn test_instance_global() {
         // Global instance creation
-        let result = GlobalInstanceContext::create(
-            WasmEdgeValueGenI64(55555555555),
-            WasmEdgeMutability::WasmEdge_Mutability_Const,
-        );
+        let result = GlobalInstanceContext::create(Val::I64(55555555555), Mutability::Const);
         assert!(result.is_some());
         let glob_c_ctx = result.unwrap();
         assert!(!glob_c_ctx.raw.is_null());
-        let result = GlobalInstanceContext::create(
-            WasmEdgeValueGenI64(66666666666),
-            WasmEdgeMutability::WasmEdge_Mutability_Var,
-        );
+        let result = GlobalInstanceContext::create(Val::I64(66666666666), Mutability::Var);
         assert!(result.is_some());
         let glob_v_ctx = result.unwrap();
         assert!(!glob_v_ctx.raw.is_null());
 
         // Global instance get value type
-        assert_eq!(glob_c_ctx.val_type(), WasmEdgeValType::WasmEdge_ValType_I64);
-        assert_eq!(glob_c_ctx.val_type(), WasmEdgeValType::WasmEdge_ValType_I64);
+        assert_eq!(glob_c_ctx.val_type(), ValType::I64);
+        assert_eq!(glob_c_ctx.val_type(), ValType::I64);
 
         // Global instance get mutability
-        assert_eq!(
-            glob_c_ctx.mutability(),
-            WasmEdgeMutability::WasmEdge_Mutability_Const
-        );
-        assert_eq!(
-            glob_v_ctx.mutability(),
-            WasmEdgeMutability::WasmEdge_Mutability_Var
-        );
+        assert_eq!(glob_c_ctx.mutability(), Mutability::Const);
+        assert_eq!(glob_v_ctx.mutability(), Mutability::Var);
 
         // Global instance get value
-        let val = glob_c_ctx.value();
-        assert_eq!(WasmEdgeValueGetI64(val), 55555555555);
-        let val = glob_v_ctx.value();
-        assert_eq!(WasmEdgeValueGetI64(val), 66666666666);
+        assert_eq!(glob_c_ctx.value(), Val::I64(55555555555));
+        assert_eq!(glob_v_ctx.value(), Val::I64(66666666666));
 
         // Global instance set value
-        let val = WasmEdgeValueGenI64(77777777777);
-        glob_c_ctx.value_mut(val);
-        let val = glob_c_ctx.value();
-        assert_eq!(WasmEdgeValueGetI64(val), 55555555555);
-        let val = WasmEdgeValueGenI64(88888888888);
-        glob_v_ctx.value_mut(val);
-        let val = glob_v_ctx.value();
-        assert_eq!(WasmEdgeValueGetI64(val), 88888888888);
-        let val = WasmEdgeValueGenF32(12.345);
-        glob_v_ctx.value_mut(val);
-        let val = glob_v_ctx.value();
-        assert_eq!(WasmEdgeValueGetI64(val), 88888888888);
+        let val = Val::I64(77777777777);
+        assert!(glob_c_ctx.value_mut(val).is_err());
+        assert_eq!(glob_c_ctx.value(), Val::I64(55555555555));
+        let val = Val::I64(88888888888);
+        assert!(glob_v_ctx.value_mut(val).is_ok());
+        assert_eq!(glob_v_ctx.value(), Val::I64(88888888888));
+        let val = Val::F32(12.345);
+        let err = glob_v_ctx.value_mut(val).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Mismatched value type. Expected: i64, Got: f32"
+        );
+        assert_eq!(glob_v_ctx.value(), Val::I64(88888888888));
+
+        // Global instance with v128 value
+        let lanes = [1.0f32, 2.0, 3.0, 4.0];
+        let result = GlobalInstanceContext::create(Val::from(lanes), Mutability::Var);
+        assert!(result.is_some());
+        let glob_simd_ctx = result.unwrap();
+        assert_eq!(glob_simd_ctx.val_type(), ValType::V128);
+        assert_eq!(glob_simd_ctx.value().v128().unwrap().to_f32x4(), lanes);
+        assert!(glob_simd_ctx.value_mut(Val::from([-1i8; 16])).is_ok());
+        assert_eq!(glob_simd_ctx.value(), Val::V128(V128::from_u128(u128::MAX)));
     }
 }
@@ -1,6 +1,12 @@
 use crate::error::WasmEdgeResult;
 use crate::utils::check;
-use crate::{context::store::StoreContext, types::*};
+use crate::{
+    context::{
+        ast::{Limits, MemoryType},
+        store::StoreContext,
+    },
+    types::*,
+};
 use std::marker::PhantomData;
 use wasmedge_sys::ffi as we_ffi;
 
@@ -56,6 +62,19 @@ impl<'store, 'vm: 'store> MemoryInstanceContext<'store, 'vm> {
             ))
         }
     }
+
+    /// Returns the type of the memory, with its current page size as the minimum.
+    ///
+    /// WasmEdge does not expose the maximum of a memory instance, so it is always `None`.
+    pub(crate) fn memory_type(&self) -> MemoryType {
+        MemoryType {
+            limits: Limits {
+                min: self.page_size() as u32,
+                max: None,
+            },
+            shared: false,
+        }
+    }
 }
 impl<'store, 'vm: 'store> Drop for MemoryInstanceContext<'store, 'vm> {
     fn drop(&mut self) {
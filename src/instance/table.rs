@@ -1,7 +1,11 @@
-use crate::context::store::StoreContext;
+use crate::context::{
+    ast::{Limits, TableType},
+    store::StoreContext,
+};
 use crate::error::WasmEdgeResult;
 use crate::types::*;
 use crate::utils::check;
+use crate::value::Val;
 use std::marker::PhantomData;
 use wasmedge_sys::ffi as we_ffi;
 
@@ -11,8 +15,8 @@ pub struct TableInstanceContext<'store, 'vm: 'store> {
     pub(crate) _drop: bool,
 }
 impl<'store, 'vm: 'store> TableInstanceContext<'store, 'vm> {
-    pub fn create(ref_type: WasmEdgeRefType, limit: WasmEdgeLimit) -> Option<Self> {
-        let raw = unsafe { we_ffi::WasmEdge_TableInstanceCreate(ref_type, limit) };
+    pub fn create(ref_type: RefType, limit: WasmEdgeLimit) -> Option<Self> {
+        let raw = unsafe { we_ffi::WasmEdge_TableInstanceCreate(ref_type.into(), limit) };
         match raw.is_null() {
             true => None,
             false => Some(TableInstanceContext {
@@ -23,28 +27,30 @@ impl<'store, 'vm: 'store> TableInstanceContext<'store, 'vm> {
         }
     }
 
-    pub fn get_ref_type(&self) -> WasmEdgeRefType {
-        unsafe { we_ffi::WasmEdge_TableInstanceGetRefType(self.raw) }
+    pub fn get_ref_type(&self) -> RefType {
+        RefType::from(unsafe { we_ffi::WasmEdge_TableInstanceGetRefType(self.raw) })
     }
 
-    pub fn set_data(&mut self, data: WasmEdgeValue, offset: usize) -> WasmEdgeResult<()> {
+    pub fn set_data(&mut self, data: Val, offset: usize) -> WasmEdgeResult<()> {
         unsafe {
             check(we_ffi::WasmEdge_TableInstanceSetData(
                 self.raw,
-                data,
+                data.into(),
                 offset as u32,
             ))
         }
     }
 
-    pub fn get_data(&self, data: &mut WasmEdgeValue, offset: usize) -> WasmEdgeResult<()> {
+    pub fn get_data(&self, offset: usize) -> WasmEdgeResult<Val> {
+        let mut data = WasmEdgeValue::from(Val::I32(0));
         unsafe {
             check(we_ffi::WasmEdge_TableInstanceGetData(
                 self.raw,
-                data as *mut _,
+                &mut data as *mut _,
                 offset as u32,
-            ))
+            ))?;
         }
+        Ok(Val::from(data))
     }
 
     pub fn get_size(&self) -> usize {
@@ -54,6 +60,19 @@ impl<'store, 'vm: 'store> TableInstanceContext<'store, 'vm> {
     pub fn grow(&mut self, size: usize) -> WasmEdgeResult<()> {
         unsafe { check(we_ffi::WasmEdge_TableInstanceGrow(self.raw, size as u32)) }
     }
+
+    /// Returns the type of the table, with its current size as the minimum.
+    ///
+    /// WasmEdge does not expose the maximum of a table instance, so it is always `None`.
+    pub(crate) fn table_type(&self) -> TableType {
+        TableType {
+            ref_type: self.get_ref_type(),
+            limits: Limits {
+                min: self.get_size() as u32,
+                max: None,
+            },
+        }
+    }
 }
 impl<'store, 'vm: 'store> Drop for TableInstanceContext<'store, 'vm> {
     fn drop(&mut self) {
@@ -70,8 +89,8 @@ impl<'store, 'vm: 'store> Drop for TableInstanceContext<'store, 'vm> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::value::*;
-    use std::os::raw::c_void;
+    use crate::externref::ExternRef;
+    use std::convert::TryFrom;
 
     #[test]
     fn test_instance_table() {
@@ -81,8 +100,7 @@ mod tests {
             Min: 10,
             Max: 0,
         };
-        let result =
-            TableInstanceContext::create(WasmEdgeRefType::WasmEdge_RefType_ExternRef, limit);
+        let result = TableInstanceContext::create(RefType::ExternRef, limit);
         assert!(result.is_some());
         let tab_ctx = result.unwrap();
         assert!(!tab_ctx.raw.is_null());
@@ -92,37 +110,35 @@ mod tests {
             Min: 10,
             Max: 0,
         };
-        let result =
-            TableInstanceContext::create(WasmEdgeRefType::WasmEdge_RefType_ExternRef, limit);
+        let result = TableInstanceContext::create(RefType::ExternRef, limit);
         assert!(result.is_some());
         let mut tab_ctx = result.unwrap();
         assert!(!tab_ctx.raw.is_null());
 
         // Table instance get reference type
-        assert_eq!(
-            tab_ctx.get_ref_type(),
-            WasmEdgeRefType::WasmEdge_RefType_ExternRef
-        );
+        assert_eq!(tab_ctx.get_ref_type(), RefType::ExternRef);
 
         // Table instance set data
-        let ptr = &mut tab_ctx as *mut _ as *mut c_void;
-        let val = WasmEdgeValueGenExternRef(ptr);
+        let store = StoreContext::create();
+        let data = ExternRef::new(&store, String::from("table data"));
+        let val = Val::from(&data);
         let result = tab_ctx.set_data(val, 5);
         assert!(result.is_ok());
         // ? error: Mismatched value type. Expected: externref , Got: funcref
-        // let tmp_val = WasmEdgeValueGenFuncRef(2);
+        // let tmp_val = Val::FuncRef(Some(2));
         // let result = tab_ctx.set_data(tmp_val, 6);
         // assert!(result.is_err());
 
         // Table instance get data
-        let mut val = WasmEdgeValueGenI32(0);
-        let result = tab_ctx.get_data(&mut val, 5);
+        let result = tab_ctx.get_data(5);
         assert!(result.is_ok());
-        let x = WasmEdgeValueGetExternRef(val) as *const TableInstanceContext;
-        let y = &tab_ctx as *const TableInstanceContext;
-        assert!(std::ptr::eq(x, y));
+        let got = result.unwrap();
+        assert_eq!(got, val);
+        let got = ExternRef::<String>::try_from(got);
+        assert!(got.is_ok());
+        assert_eq!(got.unwrap().as_str(), "table data");
         // ! error: execution failed: out of bounds table access, Code: 0x87
-        // let result = tab_ctx.get_data(&mut val, 15);
+        // let result = tab_ctx.get_data(15);
         // assert!(result.is_err());
 
         // Table instance get size and grow
@@ -134,14 +150,24 @@ mod tests {
         assert!(result.is_ok());
         assert_eq!(tab_ctx.get_size(), 26);
 
-        let val = WasmEdgeValueGenExternRef((&mut tab_ctx) as *mut _ as *mut c_void);
+        let val = Val::from(ExternRef::new(&store, 15i64));
         let result = tab_ctx.set_data(val, 15);
         assert!(result.is_ok());
-        let mut val = WasmEdgeValueGenI32(0);
-        let result = tab_ctx.get_data(&mut val, 15);
+        let result = tab_ctx.get_data(15);
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), val);
+
+        // Function references
+        let result = TableInstanceContext::create(RefType::FuncRef, limit);
+        assert!(result.is_some());
+        let mut tab_ctx = result.unwrap();
+        assert_eq!(tab_ctx.get_ref_type(), RefType::FuncRef);
+        let result = tab_ctx.get_data(3);
+        assert!(result.is_ok());
+        assert!(result.unwrap().is_null_ref());
+        assert!(tab_ctx.set_data(Val::FuncRef(Some(2)), 3).is_ok());
+        let result = tab_ctx.get_data(3);
         assert!(result.is_ok());
-        let x = WasmEdgeValueGetExternRef(val) as *const TableInstanceContext;
-        let y = &tab_ctx as *const TableInstanceContext;
-        assert!(std::ptr::eq(x, y));
+        assert_eq!(result.unwrap(), Val::FuncRef(Some(2)));
     }
 }
@@ -0,0 +1,608 @@
+//! Instrumentation of modules for the profiler.
+//!
+//! WasmEdge has no hooks for function entry and exit, so the profiler rewrites a module to call
+//! two imported probes, `enter` and `exit`, with the index of the function being entered or left:
+//!
+//! * around the body of every defined function, which is wrapped in a block so that branches to
+//!   the function label still reach the `exit` probe, and before every `return`;
+//! * around every direct `call` of an imported function, as host functions cannot be rewritten.
+//!
+//! The probes are imported after the other functions, so the indices of the defined functions
+//! shift by two. Every function index in the module is remapped accordingly, including the ones
+//! in the `name` section. Indirect calls of imported functions are not probed.
+
+use crate::binary::{
+    self, binary_error, ModuleInfo, Reader, SECTION_CODE, SECTION_CUSTOM, SECTION_EXPORT,
+    SECTION_GLOBAL, SECTION_IMPORT, SECTION_START, SECTION_TYPE,
+};
+use crate::context::ast::{ExternType, FuncType};
+use crate::error::WasmEdgeResult;
+use crate::opcode::{Imm, Instr};
+use crate::types::ValType;
+
+/// The module name the probes are imported from.
+pub(crate) const PROBE_MODULE: &str = "wasmedge_profiler";
+pub(crate) const PROBE_ENTER: &str = "enter";
+pub(crate) const PROBE_EXIT: &str = "exit";
+
+const SECTION_ELEMENT: u8 = 9;
+
+const OP_END: u16 = 0x0B;
+const OP_RETURN: u16 = 0x0F;
+const OP_CALL: u16 = 0x10;
+const OP_REF_FUNC: u16 = 0xD2;
+
+/// A function of the original module, as reported in profiles.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ProbedFunction {
+    /// The name from the `name` section, an export or the import, or `func[N]`.
+    pub(crate) name: String,
+    pub(crate) host: bool,
+}
+
+/// An instrumented module and the functions its probes report, by original function index.
+#[derive(Debug, Clone)]
+pub(crate) struct Instrumented {
+    pub(crate) bytes: Vec<u8>,
+    pub(crate) functions: Vec<ProbedFunction>,
+}
+
+/// Rewrites a module to report function entries and exits to the profiler probes.
+pub(crate) fn instrument(bytes: &[u8]) -> WasmEdgeResult<Instrumented> {
+    let info = ModuleInfo::parse(bytes)?;
+    let rewriter = Rewriter::new(bytes, &info);
+    let mut out = bytes[..8].to_vec();
+    let (mut types_written, mut imports_written) = (false, false);
+    for section in info.sections.iter() {
+        if section.id != SECTION_CUSTOM {
+            if !types_written && section.id != SECTION_TYPE {
+                write_section(&mut out, SECTION_TYPE, &rewriter.type_section(None)?);
+                types_written = true;
+            }
+            if !imports_written && section.id != SECTION_TYPE && section.id != SECTION_IMPORT {
+                write_section(&mut out, SECTION_IMPORT, &rewriter.import_section(None)?);
+                imports_written = true;
+            }
+        }
+        let mut reader = Reader::new(&bytes[section.range.clone()], section.range.start);
+        let payload = match section.id {
+            SECTION_TYPE => {
+                types_written = true;
+                rewriter.type_section(Some(&mut reader))?
+            }
+            SECTION_IMPORT => {
+                imports_written = true;
+                rewriter.import_section(Some(&mut reader))?
+            }
+            SECTION_GLOBAL => rewriter.global_section(&mut reader)?,
+            SECTION_EXPORT => rewriter.export_section(&mut reader)?,
+            SECTION_START => {
+                let mut payload = vec![];
+                write_u32(&mut payload, rewriter.remap(reader.u32()?));
+                payload
+            }
+            SECTION_ELEMENT => rewriter.element_section(&mut reader)?,
+            SECTION_CODE => rewriter.code_section(&mut reader)?,
+            SECTION_CUSTOM => rewriter.custom_section(&mut reader)?,
+            _ => reader.rest().to_vec(),
+        };
+        write_section(&mut out, section.id, &payload);
+    }
+    if !types_written {
+        write_section(&mut out, SECTION_TYPE, &rewriter.type_section(None)?);
+    }
+    if !imports_written {
+        write_section(&mut out, SECTION_IMPORT, &rewriter.import_section(None)?);
+    }
+    Ok(Instrumented {
+        bytes: out,
+        functions: rewriter.functions()?,
+    })
+}
+
+struct Rewriter<'a> {
+    bytes: &'a [u8],
+    info: &'a ModuleInfo,
+    /// The number of imported functions, whose indices do not move.
+    imported: u32,
+    /// The types appended to the type section: the probe type, then block types.
+    extra_types: Vec<FuncType>,
+}
+impl<'a> Rewriter<'a> {
+    fn new(bytes: &'a [u8], info: &'a ModuleInfo) -> Self {
+        let imported = info
+            .imports
+            .iter()
+            .filter(|import| matches!(import.ty, ExternType::Function(_)))
+            .count() as u32;
+        let mut extra_types = vec![FuncType {
+            params: vec![ValType::I32],
+            returns: vec![],
+        }];
+        // Blocks with several results need a type index
+        for ty in info.funcs.iter().skip(imported as usize) {
+            let block_type = FuncType {
+                params: vec![],
+                returns: ty.returns.clone(),
+            };
+            if ty.returns.len() > 1 && !extra_types.contains(&block_type) {
+                extra_types.push(block_type);
+            }
+        }
+        Rewriter {
+            bytes,
+            info,
+            imported,
+            extra_types,
+        }
+    }
+
+    fn probe_type(&self) -> u32 {
+        self.info.types.len() as u32
+    }
+
+    fn enter_probe(&self) -> u32 {
+        self.imported
+    }
+
+    fn exit_probe(&self) -> u32 {
+        self.imported + 1
+    }
+
+    /// Maps a function index of the original module to the instrumented one.
+    fn remap(&self, func_idx: u32) -> u32 {
+        match func_idx < self.imported {
+            true => func_idx,
+            false => func_idx + 2,
+        }
+    }
+
+    fn functions(&self) -> WasmEdgeResult<Vec<ProbedFunction>> {
+        let names = match binary::custom_sections(self.bytes)?
+            .into_iter()
+            .find(|section| section.name == "name")
+        {
+            Some(section) => binary::name_section(&section.data)?.functions,
+            None => Default::default(),
+        };
+        let imports = self
+            .info
+            .imports
+            .iter()
+            .filter(|import| matches!(import.ty, ExternType::Function(_)));
+        let mut functions: Vec<_> = imports
+            .map(|import| ProbedFunction {
+                name: format!("{}.{}", import.module, import.name),
+                host: true,
+            })
+            .collect();
+        for idx in functions.len()..self.info.funcs.len() {
+            functions.push(ProbedFunction {
+                name: format!("func[{}]", idx),
+                host: false,
+            });
+        }
+        // Walk the export section again, as `ModuleInfo` does not keep the export indices
+        for section in self.info.sections.iter() {
+            if section.id != SECTION_EXPORT {
+                continue;
+            }
+            let mut reader = Reader::new(&self.bytes[section.range.clone()], section.range.start);
+            for _ in 0..reader.vec_len()? {
+                let name = reader.name()?;
+                let kind = reader.u8()?;
+                let idx = reader.u32()? as usize;
+                match functions.get_mut(idx) {
+                    Some(func) if kind == 0x00 && !func.host && func.name.starts_with("func[") => {
+                        func.name = name
+                    }
+                    _ => {}
+                }
+            }
+        }
+        for (idx, name) in names.into_iter() {
+            if let Some(func) = functions.get_mut(idx as usize) {
+                if !func.host {
+                    func.name = name;
+                }
+            }
+        }
+        Ok(functions)
+    }
+
+    fn type_section(&self, reader: Option<&mut Reader>) -> WasmEdgeResult<Vec<u8>> {
+        let mut payload = vec![];
+        write_u32(
+            &mut payload,
+            (self.info.types.len() + self.extra_types.len()) as u32,
+        );
+        if let Some(reader) = reader {
+            reader.vec_len()?;
+            payload.extend_from_slice(reader.rest());
+        }
+        for ty in self.extra_types.iter() {
+            payload.push(0x60);
+            for types in [&ty.params, &ty.returns].iter() {
+                write_u32(&mut payload, types.len() as u32);
+                payload.extend(types.iter().map(|ty| val_type_byte(*ty)));
+            }
+        }
+        Ok(payload)
+    }
+
+    fn import_section(&self, reader: Option<&mut Reader>) -> WasmEdgeResult<Vec<u8>> {
+        let mut payload = vec![];
+        write_u32(&mut payload, self.info.imports.len() as u32 + 2);
+        if let Some(reader) = reader {
+            reader.vec_len()?;
+            payload.extend_from_slice(reader.rest());
+        }
+        for name in [PROBE_ENTER, PROBE_EXIT].iter() {
+            write_name(&mut payload, PROBE_MODULE);
+            write_name(&mut payload, name);
+            payload.push(0x00);
+            write_u32(&mut payload, self.probe_type());
+        }
+        Ok(payload)
+    }
+
+    fn global_section(&self, reader: &mut Reader) -> WasmEdgeResult<Vec<u8>> {
+        let mut payload = vec![];
+        let len = reader.vec_len()?;
+        write_u32(&mut payload, len as u32);
+        for _ in 0..len {
+            let start = reader.offset();
+            reader.global_type()?;
+            payload.extend_from_slice(&self.bytes[start..reader.offset()]);
+            self.const_expr(reader, &mut payload)?;
+        }
+        Ok(payload)
+    }
+
+    fn export_section(&self, reader: &mut Reader) -> WasmEdgeResult<Vec<u8>> {
+        let mut payload = vec![];
+        let len = reader.vec_len()?;
+        write_u32(&mut payload, len as u32);
+        for _ in 0..len {
+            write_name(&mut payload, &reader.name()?);
+            let kind = reader.u8()?;
+            let idx = reader.u32()?;
+            payload.push(kind);
+            match kind {
+                0x00 => write_u32(&mut payload, self.remap(idx)),
+                _ => write_u32(&mut payload, idx),
+            }
+        }
+        Ok(payload)
+    }
+
+    fn element_section(&self, reader: &mut Reader) -> WasmEdgeResult<Vec<u8>> {
+        let mut payload = vec![];
+        let len = reader.vec_len()?;
+        write_u32(&mut payload, len as u32);
+        for _ in 0..len {
+            let offset = reader.offset();
+            let flags = reader.u32()?;
+            write_u32(&mut payload, flags);
+            // Bit 0: passive or declarative, bit 1: explicit table or declarative, bit 2: exprs
+            if flags & 0b011 == 0b010 {
+                write_u32(&mut payload, reader.u32()?);
+            }
+            if flags & 0b001 == 0 {
+                self.const_expr(reader, &mut payload)?;
+            }
+            if flags & 0b011 != 0 {
+                // The element kind or reference type
+                payload.push(reader.u8()?);
+            }
+            let count = reader.vec_len()?;
+            write_u32(&mut payload, count as u32);
+            match flags {
+                0..=3 => {
+                    for _ in 0..count {
+                        write_u32(&mut payload, self.remap(reader.u32()?));
+                    }
+                }
+                4..=7 => {
+                    for _ in 0..count {
+                        self.const_expr(reader, &mut payload)?;
+                    }
+                }
+                _ => {
+                    return Err(binary_error(
+                        0x25,
+                        format!(
+                            "malformed element segment flags {} at offset {:#x}",
+                            flags, offset
+                        ),
+                    ))
+                }
+            }
+        }
+        Ok(payload)
+    }
+
+    fn code_section(&self, reader: &mut Reader) -> WasmEdgeResult<Vec<u8>> {
+        let mut payload = vec![];
+        let len = reader.vec_len()?;
+        write_u32(&mut payload, len as u32);
+        for (i, body) in self.info.bodies.iter().enumerate() {
+            let size = reader.vec_len()?;
+            let start = reader.offset();
+            reader.bytes(size)?;
+            let func_idx = self.imported + i as u32;
+            let mut code = self.bytes[start..body.code.start].to_vec();
+            self.function_body(func_idx, body.code.clone(), &mut code)?;
+            write_u32(&mut payload, code.len() as u32);
+            payload.extend(code);
+        }
+        Ok(payload)
+    }
+
+    fn function_body(
+        &self,
+        func_idx: u32,
+        code: std::ops::Range<usize>,
+        out: &mut Vec<u8>,
+    ) -> WasmEdgeResult<()> {
+        self.probe(out, self.enter_probe(), func_idx);
+        out.push(0x02);
+        let returns = &self.info.funcs[func_idx as usize].returns;
+        match returns.as_slice() {
+            [] => out.push(0x40),
+            [ty] => out.push(val_type_byte(*ty)),
+            _ => {
+                let block_type = FuncType {
+                    params: vec![],
+                    returns: returns.clone(),
+                };
+                let idx = self.extra_types.iter().position(|ty| *ty == block_type);
+                write_u32(out, self.probe_type() + idx.unwrap_or_default() as u32);
+            }
+        }
+
+        let mut reader = Reader::new(&self.bytes[code.clone()], code.start);
+        while !reader.is_empty() {
+            let instr = Instr::read(&mut reader)?;
+            let raw = &self.bytes[instr.offset..reader.offset()];
+            match (instr.code, &instr.imm) {
+                // The final `end` closes the wrapping block instead
+                (OP_END, _) if reader.is_empty() => out.push(0x0B),
+                (OP_RETURN, _) => {
+                    self.probe(out, self.exit_probe(), func_idx);
+                    out.push(0x0F);
+                }
+                (OP_CALL, Imm::Index(callee)) if *callee < self.imported => {
+                    self.probe(out, self.enter_probe(), *callee);
+                    out.extend_from_slice(raw);
+                    self.probe(out, self.exit_probe(), *callee);
+                }
+                (OP_CALL, Imm::Index(callee)) => {
+                    out.push(0x10);
+                    write_u32(out, self.remap(*callee));
+                }
+                (OP_REF_FUNC, Imm::Index(func)) => {
+                    out.push(0xD2);
+                    write_u32(out, self.remap(*func));
+                }
+                _ => out.extend_from_slice(raw),
+            }
+        }
+        self.probe(out, self.exit_probe(), func_idx);
+        out.push(0x0B);
+        Ok(())
+    }
+
+    /// Copies a constant expression, remapping `ref.func`.
+    fn const_expr(&self, reader: &mut Reader, out: &mut Vec<u8>) -> WasmEdgeResult<()> {
+        loop {
+            let instr = Instr::read(reader)?;
+            match (instr.code, &instr.imm) {
+                (OP_REF_FUNC, Imm::Index(func)) => {
+                    out.push(0xD2);
+                    write_u32(out, self.remap(*func));
+                }
+                _ => out.extend_from_slice(&self.bytes[instr.offset..reader.offset()]),
+            }
+            if instr.code == OP_END {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Copies a custom section, remapping the function indices of the `name` section.
+    fn custom_section(&self, reader: &mut Reader) -> WasmEdgeResult<Vec<u8>> {
+        let name = reader.name()?;
+        let mut payload = vec![];
+        write_name(&mut payload, &name);
+        if name != "name" {
+            payload.extend_from_slice(reader.rest());
+            return Ok(payload);
+        }
+        while !reader.is_empty() {
+            let id = reader.u8()?;
+            let len = reader.vec_len()?;
+            let start = reader.offset();
+            let mut sub = Reader::new(reader.bytes(len)?, start);
+            let data = match id {
+                // Function names, and local and label names by function
+                1..=3 => {
+                    let mut data = vec![];
+                    let count = sub.vec_len()?;
+                    write_u32(&mut data, count as u32);
+                    for _ in 0..count {
+                        write_u32(&mut data, self.remap(sub.u32()?));
+                        let start = sub.offset();
+                        match id {
+                            1 => {
+                                sub.name()?;
+                            }
+                            _ => {
+                                for _ in 0..sub.vec_len()? {
+                                    sub.u32()?;
+                                    sub.name()?;
+                                }
+                            }
+                        }
+                        data.extend_from_slice(&self.bytes[start..sub.offset()]);
+                    }
+                    data
+                }
+                _ => sub.rest().to_vec(),
+            };
+            payload.push(id);
+            write_u32(&mut payload, data.len() as u32);
+            payload.extend(data);
+        }
+        Ok(payload)
+    }
+
+    fn probe(&self, out: &mut Vec<u8>, probe: u32, func_idx: u32) {
+        out.push(0x41);
+        write_i32(out, func_idx as i32);
+        out.push(0x10);
+        write_u32(out, probe);
+    }
+}
+
+fn val_type_byte(ty: ValType) -> u8 {
+    match ty {
+        ValType::I32 => 0x7F,
+        ValType::I64 => 0x7E,
+        ValType::F32 => 0x7D,
+        ValType::F64 => 0x7C,
+        ValType::V128 => 0x7B,
+        ValType::FuncRef => 0x70,
+        ValType::ExternRef => 0x6F,
+    }
+}
+
+fn write_u32(out: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn write_i32(out: &mut Vec<u8>, mut value: i32) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        let done = (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0);
+        if done {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn write_name(out: &mut Vec<u8>, name: &str) {
+    write_u32(out, name.len() as u32);
+    out.extend_from_slice(name.as_bytes());
+}
+
+fn write_section(out: &mut Vec<u8>, id: u8, payload: &[u8]) {
+    out.push(id);
+    write_u32(out, payload.len() as u32);
+    out.extend_from_slice(payload);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instrument_leb() {
+        let mut out = vec![];
+        write_u32(&mut out, 624485);
+        write_i32(&mut out, -123456);
+        write_i32(&mut out, 64);
+        assert_eq!(out, [0xE5, 0x8E, 0x26, 0xC0, 0xBB, 0x78, 0xC0, 0x00]);
+        let mut reader = Reader::new(&out, 0);
+        assert_eq!(reader.u32().unwrap(), 624485);
+        assert_eq!(reader.s32().unwrap(), -123456);
+        assert_eq!(reader.s32().unwrap(), 64);
+    }
+
+    #[test]
+    fn test_instrument_module() {
+        const WASM: &[u8] = &[
+            0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00, /* Type section */
+            0x01, 0x05, 0x01, /* () -> (i32) */
+            0x60, 0x00, 0x01, 0x7F, /* Import section */
+            0x02, 0x0C, 0x01, /* "env" "host" func 0 */
+            0x03, 0x65, 0x6E, 0x76, 0x04, 0x68, 0x6F, 0x73, 0x74, 0x00, 0x00,
+            /* Function section */
+            0x03, 0x03, 0x02, 0x00, 0x00, /* Export section: "run" func 2 */
+            0x07, 0x07, 0x01, 0x03, 0x72, 0x75, 0x6E, 0x00, 0x02, /* Code section */
+            0x0A, 0x0F, 0x02, /* func 1: i32.const 7, return */
+            0x05, 0x00, 0x41, 0x07, 0x0F, 0x0B, /* func 2: call 1, call 0, i32.add */
+            0x07, 0x00, 0x10, 0x01, 0x10, 0x00, 0x6A, 0x0B,
+            /* Name section: func 1 is "seven" */
+            0x00, 0x0F, 0x04, 0x6E, 0x61, 0x6D, 0x65, /* function names */
+            0x01, 0x08, 0x01, 0x01, 0x05, 0x73, 0x65, 0x76, 0x65, 0x6E,
+        ];
+        let instrumented = instrument(WASM).unwrap();
+        let names: Vec<_> = instrumented
+            .functions
+            .iter()
+            .map(|func| (func.name.as_str(), func.host))
+            .collect();
+        assert_eq!(
+            names,
+            [("env.host", true), ("seven", false), ("run", false)]
+        );
+
+        let info = ModuleInfo::parse(&instrumented.bytes).unwrap();
+        assert_eq!(info.types.len(), 2);
+        assert_eq!(info.types[1].params, [ValType::I32]);
+        let probes: Vec<_> = info.imports[1..]
+            .iter()
+            .map(|import| (import.module.as_str(), import.name.as_str()))
+            .collect();
+        assert_eq!(
+            probes,
+            [(PROBE_MODULE, PROBE_ENTER), (PROBE_MODULE, PROBE_EXIT)]
+        );
+        assert_eq!(info.funcs.len(), 5);
+
+        // "run" and its name moved two indices up
+        let customs = binary::custom_sections(&instrumented.bytes).unwrap();
+        let names = binary::name_section(&customs[0].data).unwrap();
+        assert_eq!(names.function(3), Some("seven"));
+        let exports = &info.sections[3];
+        assert_eq!(exports.id, SECTION_EXPORT);
+        assert_eq!(instrumented.bytes[exports.range.end - 1], 0x04);
+
+        let run = &info.bodies[1];
+        assert_eq!(
+            &instrumented.bytes[run.code.clone()],
+            &[
+                0x41, 0x02, 0x10, 0x01, /* enter 2 */
+                0x02, 0x7F, /* block (result i32) */
+                0x10, 0x03, /* call seven */
+                0x41, 0x00, 0x10, 0x01, /* enter 0 */
+                0x10, 0x00, /* call env.host */
+                0x41, 0x00, 0x10, 0x02, /* exit 0 */
+                0x6A, 0x0B, /* i32.add, end */
+                0x41, 0x02, 0x10, 0x02, /* exit 2 */
+                0x0B,
+            ][..]
+        );
+        let seven = &info.bodies[0];
+        assert_eq!(
+            &instrumented.bytes[seven.code.clone()],
+            &[
+                0x41, 0x01, 0x10, 0x01, 0x02, 0x7F, 0x41, 0x07, /* i32.const 7 */
+                0x41, 0x01, 0x10, 0x02, 0x0F, /* exit 1, return */
+                0x0B, 0x41, 0x01, 0x10, 0x02, 0x0B,
+            ][..]
+        );
+    }
+}
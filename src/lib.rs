@@ -1,9 +1,24 @@
 #![feature(maybe_uninit_uninit_array, maybe_uninit_extra, maybe_uninit_slice)]
 
+mod binary;
+pub mod cache;
 pub mod context;
+pub mod cost;
 pub mod error;
+pub mod externref;
+#[cfg(test)]
+mod fixtures;
 pub mod instance;
+mod instrument;
+pub mod link;
+mod opcode;
+pub mod policy;
+pub mod profile;
 pub mod system;
+mod typecheck;
 pub mod types;
 pub mod utils;
 pub mod value;
+pub mod wasi;
+#[cfg(feature = "wat")]
+mod wat;
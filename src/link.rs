@@ -0,0 +1,355 @@
+//! Checking the imports of a module before it is instantiated.
+
+use crate::context::{
+    ast::{ASTModuleContext, ExternType, Limits},
+    import_object::ImportObjectContext,
+    store::StoreContext,
+};
+use crate::error::WasmEdgeResult;
+use std::fmt;
+
+/// An import of a module that would fail to link.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkError {
+    /// Nothing with the name is registered under the module name.
+    Unresolved {
+        module: String,
+        name: String,
+        expected: ExternType,
+    },
+    /// An item is registered under the name, but its type does not match the import.
+    TypeMismatch {
+        module: String,
+        name: String,
+        expected: ExternType,
+        found: ExternType,
+    },
+}
+impl LinkError {
+    pub fn module(&self) -> &str {
+        match self {
+            LinkError::Unresolved { module, .. } | LinkError::TypeMismatch { module, .. } => module,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            LinkError::Unresolved { name, .. } | LinkError::TypeMismatch { name, .. } => name,
+        }
+    }
+}
+impl fmt::Display for LinkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LinkError::Unresolved {
+                module,
+                name,
+                expected,
+            } => write!(
+                f,
+                "unresolved import {}::{}: expected {}",
+                module, name, expected
+            ),
+            LinkError::TypeMismatch {
+                module,
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "incompatible import {}::{}: expected {}, found {}",
+                module, name, expected, found
+            ),
+        }
+    }
+}
+
+/// Returns every import of `ast_mod` that is not provided, or is provided with an incompatible
+/// type, by the modules registered in `store` and by `import_objects`.
+///
+/// The import objects are searched before the store, by their module name. The contents of the
+/// built-in import objects, e.g. WASI, are unknown to the crate, so any import from them is
+/// taken as resolved. WasmEdge does not expose the maximum size of registered tables and
+/// memories, so only their current size is compared with the minimum of the import.
+pub fn link_check(
+    ast_mod: &ASTModuleContext,
+    store: &StoreContext,
+    import_objects: &[&ImportObjectContext],
+) -> WasmEdgeResult<Vec<LinkError>> {
+    let mut errors = vec![];
+    for import in ast_mod.imports()? {
+        let found = match resolve(store, import_objects, &import.module, &import.name) {
+            Resolved::Opaque => continue,
+            Resolved::Found(found) => found,
+            Resolved::Missing => {
+                errors.push(LinkError::Unresolved {
+                    module: import.module,
+                    name: import.name,
+                    expected: import.ty,
+                });
+                continue;
+            }
+        };
+        // Prefer an item of the expected kind when several kinds share the name.
+        let found = match found.iter().find(|ty| ty.kind() == import.kind()) {
+            Some(ty) => ty.clone(),
+            None => found[0].clone(),
+        };
+        if !is_compatible(&import.ty, &found) {
+            errors.push(LinkError::TypeMismatch {
+                module: import.module,
+                name: import.name,
+                expected: import.ty,
+                found,
+            });
+        }
+    }
+    Ok(errors)
+}
+
+enum Resolved {
+    /// The items registered under the name; never empty.
+    Found(Vec<ExternType>),
+    /// The import object of the module cannot be inspected.
+    Opaque,
+    Missing,
+}
+
+fn resolve(
+    store: &StoreContext,
+    import_objects: &[&ImportObjectContext],
+    module: &str,
+    name: &str,
+) -> Resolved {
+    for import_object in import_objects.iter().filter(|obj| obj.module == module) {
+        match &import_object.exports {
+            None => return Resolved::Opaque,
+            Some(exports) => {
+                let found: Vec<_> = exports
+                    .iter()
+                    .filter(|(export, _)| export == name)
+                    .map(|(_, ty)| ty.clone())
+                    .collect();
+                if !found.is_empty() {
+                    return Resolved::Found(found);
+                }
+            }
+        }
+    }
+
+    let mut found = vec![];
+    if let Some(func) = store.find_function_registered(module, name) {
+        if let Some(func_type) = func.function_type() {
+            found.push(ExternType::Function(func_type.to_func_type()));
+        }
+    }
+    if let Some(table) = store.find_table_registered(module, name) {
+        found.push(ExternType::Table(table.table_type()));
+    }
+    if let Some(memory) = store.find_memory_registered(module, name) {
+        found.push(ExternType::Memory(memory.memory_type()));
+    }
+    if let Some(global) = store.find_global_registered(module, name) {
+        found.push(ExternType::Global(global.global_type()));
+    }
+    match found.is_empty() {
+        true => Resolved::Missing,
+        false => Resolved::Found(found),
+    }
+}
+
+/// Whether `found` can be imported as `expected`, following the import matching rules of the
+/// specification.
+fn is_compatible(expected: &ExternType, found: &ExternType) -> bool {
+    match (expected, found) {
+        (ExternType::Function(expected), ExternType::Function(found)) => expected == found,
+        (ExternType::Table(expected), ExternType::Table(found)) => {
+            expected.ref_type == found.ref_type && limits_match(&expected.limits, &found.limits)
+        }
+        (ExternType::Memory(expected), ExternType::Memory(found)) => {
+            expected.shared == found.shared && limits_match(&expected.limits, &found.limits)
+        }
+        (ExternType::Global(expected), ExternType::Global(found)) => expected == found,
+        _ => false,
+    }
+}
+
+/// An unknown maximum of `found` is not held against it, see `link_check`.
+fn limits_match(expected: &Limits, found: &Limits) -> bool {
+    found.min >= expected.min
+        && match (expected.max, found.max) {
+            (Some(expected), Some(found)) => found <= expected,
+            _ => true,
+        }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{
+        ast::{FuncType, GlobalType, MemoryType},
+        configure::ConfigureContext,
+        loader::LoaderContext,
+        vm::VMContext,
+    };
+    use crate::instance::{
+        function::{FunctionTypeContext, HostFunctionContext},
+        global::GlobalInstanceContext,
+        memory::MemoryInstanceContext,
+    };
+    use crate::types::*;
+    use crate::value::Val;
+    use crate::wasi::WasiConfig;
+    use std::ptr;
+
+    const LINK_WASM: &[u8] = &[
+        /* WASM header */
+        0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00, /* Type section */
+        0x01, 0x0A, 0x02, /* function type {i32, i32} -> {i32} */
+        0x60, 0x02, 0x7F, 0x7F, 0x01, 0x7F, /* function type {} -> {} */
+        0x60, 0x00, 0x00, /* Import section */
+        0x02, 0x50, 0x05, /* import: "env" "add" func 0 */
+        0x03, 0x65, 0x6E, 0x76, 0x03, 0x61, 0x64, 0x64, 0x00, 0x00,
+        /* import: "env" "g" global const i32 */
+        0x03, 0x65, 0x6E, 0x76, 0x01, 0x67, 0x03, 0x7F, 0x00,
+        /* import: "env" "mem" memory min 1 */
+        0x03, 0x65, 0x6E, 0x76, 0x03, 0x6D, 0x65, 0x6D, 0x02, 0x00, 0x01,
+        /* import: "env" "missing" func 1 */
+        0x03, 0x65, 0x6E, 0x76, 0x07, 0x6D, 0x69, 0x73, 0x73, 0x69, 0x6E, 0x67, 0x00, 0x01,
+        /* import: "wasi_snapshot_preview1" "proc_exit" func 1 */
+        0x16, 0x77, 0x61, 0x73, 0x69, 0x5F, 0x73, 0x6E, 0x61, 0x70, 0x73, 0x68, 0x6F, 0x74, 0x5F,
+        0x70, 0x72, 0x65, 0x76, 0x69, 0x65, 0x77, 0x31, 0x09, 0x70, 0x72, 0x6F, 0x63, 0x5F, 0x65,
+        0x78, 0x69, 0x74, 0x00, 0x01,
+    ];
+
+    fn load(buf: &[u8]) -> ASTModuleContext {
+        let conf = ConfigureContext::create();
+        let mut loader = LoaderContext::create(&conf).unwrap();
+        let mut ast_mod = ASTModuleContext::default();
+        assert!(loader.parse_from_buffer(&mut ast_mod, buf).is_ok());
+        ast_mod
+    }
+
+    /// An "env" module with "add": {i32, i32} -> {i64}, "g": const i32 and "mem": 1 page.
+    fn create_env<'a>() -> ImportObjectContext<'a> {
+        let mut imp_obj = ImportObjectContext::create("env", ptr::null_mut()).unwrap();
+        let params = [ValType::I32, ValType::I32];
+        let returns = [ValType::I64];
+        let func_type = FunctionTypeContext::create(Some(&params), Some(&returns)).unwrap();
+        let mut host_func =
+            HostFunctionContext::create(&func_type, Some(extern_add_link), 0).unwrap();
+        assert!(imp_obj.add_host_function("add", &mut host_func).is_ok());
+        let mut global = GlobalInstanceContext::create(Val::I32(1), Mutability::Const).unwrap();
+        assert!(imp_obj.add_global("g", &mut global).is_ok());
+        let limit = WasmEdgeLimit {
+            HasMax: false,
+            Min: 1,
+            Max: 1,
+        };
+        let mut memory = MemoryInstanceContext::create(limit).unwrap();
+        assert!(imp_obj.add_memory("mem", &mut memory).is_ok());
+        imp_obj
+    }
+
+    fn expected_errors() -> Vec<LinkError> {
+        let add = FuncType {
+            params: vec![ValType::I32, ValType::I32],
+            returns: vec![ValType::I32],
+        };
+        vec![
+            LinkError::TypeMismatch {
+                module: String::from("env"),
+                name: String::from("add"),
+                expected: ExternType::Function(add.clone()),
+                found: ExternType::Function(FuncType {
+                    returns: vec![ValType::I64],
+                    ..add
+                }),
+            },
+            LinkError::Unresolved {
+                module: String::from("env"),
+                name: String::from("missing"),
+                expected: ExternType::Function(FuncType {
+                    params: vec![],
+                    returns: vec![],
+                }),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_link_check_import_objects() {
+        let ast_mod = load(LINK_WASM);
+        let store = StoreContext::create();
+        let env = create_env();
+        let wasi = ImportObjectContext::create_wasi(&WasiConfig::new()).unwrap();
+
+        let errors = link_check(&ast_mod, &store, &[&env, &wasi]).unwrap();
+        assert_eq!(errors, expected_errors());
+        assert_eq!(
+            errors[0].to_string(),
+            "incompatible import env::add: expected (func (param i32 i32) (result i32)), \
+             found (func (param i32 i32) (result i64))"
+        );
+        assert_eq!(
+            errors[1].to_string(),
+            "unresolved import env::missing: expected (func)"
+        );
+
+        // Without the WASI import object, its import is unresolved too
+        let errors = link_check(&ast_mod, &store, &[&env]).unwrap();
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors[2].module(), "wasi_snapshot_preview1");
+        assert_eq!(errors[2].name(), "proc_exit");
+
+        assert!(link_check(&ASTModuleContext::default(), &store, &[]).is_err());
+    }
+
+    #[test]
+    fn test_link_check_store() {
+        let ast_mod = load(LINK_WASM);
+        let mut store = StoreContext::create();
+        let mut vm = VMContext::create(None, Some(&mut store)).unwrap();
+        let env = create_env();
+        assert!(vm.register_module_from_import_object(&env).is_ok());
+        let wasi = ImportObjectContext::create_wasi(&WasiConfig::new()).unwrap();
+
+        let errors = link_check(&ast_mod, &store, &[&wasi]).unwrap();
+        assert_eq!(errors, expected_errors());
+
+        // A global under the name of an imported memory is reported with its own type
+        let mut other = ImportObjectContext::create("env", ptr::null_mut()).unwrap();
+        let mut global = GlobalInstanceContext::create(Val::I64(1), Mutability::Var).unwrap();
+        assert!(other.add_global("mem", &mut global).is_ok());
+        let errors = link_check(&ast_mod, &store, &[&other, &wasi]).unwrap();
+        assert_eq!(
+            errors[1],
+            LinkError::TypeMismatch {
+                module: String::from("env"),
+                name: String::from("mem"),
+                expected: ExternType::Memory(MemoryType {
+                    limits: Limits { min: 1, max: None },
+                    shared: false,
+                }),
+                found: ExternType::Global(GlobalType {
+                    val_type: ValType::I64,
+                    mutability: Mutability::Var,
+                }),
+            }
+        );
+        assert_eq!(
+            errors[1].to_string(),
+            "incompatible import env::mem: expected (memory 1), found (global (mut i64))"
+        );
+    }
+
+    #[no_mangle]
+    unsafe extern "C" fn extern_add_link(
+        _data: *mut std::os::raw::c_void,
+        _mem_ctx: *mut wasmedge_sys::ffi::WasmEdge_MemoryInstanceContext,
+        _params: *const WasmEdgeValue,
+        _returns: *mut WasmEdgeValue,
+    ) -> wasmedge_sys::ffi::WasmEdge_Result {
+        wasmedge_sys::ffi::WasmEdge_Result { Code: 0 }
+    }
+}
@@ -0,0 +1,525 @@
+//! The WebAssembly instruction set: opcode names, immediates, operand types and proposals.
+//!
+//! Opcodes are numbered the way WasmEdge numbers them: single-byte opcodes keep their byte, and
+//! prefixed opcodes put the prefix in the high byte, e.g. `0xFC00` for `i32.trunc_sat_f32_s`.
+
+use crate::binary::Reader;
+use crate::error::WasmEdgeResult;
+use crate::types::{Proposal, RefType, ValType};
+
+/// The immediate operands that follow an opcode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ImmKind {
+    None,
+    BlockType,
+    Label,
+    BrTable,
+    Func,
+    CallIndirect,
+    Local,
+    Global,
+    Table,
+    MemArg,
+    /// A memory index byte.
+    Mem,
+    I32,
+    I64,
+    F32,
+    F64,
+    SelectT,
+    RefType,
+    /// A data segment index and a memory index byte.
+    MemInit,
+    Data,
+    /// Two memory index bytes.
+    MemCopy,
+    /// An element segment index and a table index.
+    TableInit,
+    Elem,
+    /// Two table indices.
+    TableCopy,
+    V128,
+    Lane,
+    MemArgLane,
+}
+
+/// The operand types an instruction pops and pushes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Sig {
+    Fixed(&'static [ValType], &'static [ValType]),
+    /// The types depend on the immediates or the context, e.g. `call` or `local.get`.
+    Special,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct OpInfo {
+    pub(crate) code: u16,
+    pub(crate) name: &'static str,
+    pub(crate) imm: ImmKind,
+    pub(crate) sig: Sig,
+    /// The proposal that introduced the instruction, or `None` for the MVP.
+    pub(crate) proposal: Option<Proposal>,
+}
+
+macro_rules! sig {
+    (_) => {
+        Sig::Special
+    };
+    (($($param:ident)* => $($result:ident)*)) => {
+        Sig::Fixed(&[$(ValType::$param),*], &[$(ValType::$result),*])
+    };
+}
+
+macro_rules! proposal {
+    (Mvp) => {
+        None
+    };
+    ($proposal:ident) => {
+        Some(Proposal::$proposal)
+    };
+}
+
+macro_rules! opcodes {
+    ($($code:literal $name:literal $imm:ident $sig:tt $proposal:ident;)*) => {
+        pub(crate) const OPCODES: &[OpInfo] = &[
+            $(OpInfo {
+                code: $code,
+                name: $name,
+                imm: ImmKind::$imm,
+                sig: sig!($sig),
+                proposal: proposal!($proposal),
+            },)*
+        ];
+    };
+}
+
+opcodes! {
+    0x00 "unreachable" None _ Mvp;
+    0x01 "nop" None (=>) Mvp;
+    0x02 "block" BlockType _ Mvp;
+    0x03 "loop" BlockType _ Mvp;
+    0x04 "if" BlockType _ Mvp;
+    0x05 "else" None _ Mvp;
+    0x0B "end" None _ Mvp;
+    0x0C "br" Label _ Mvp;
+    0x0D "br_if" Label _ Mvp;
+    0x0E "br_table" BrTable _ Mvp;
+    0x0F "return" None _ Mvp;
+    0x10 "call" Func _ Mvp;
+    0x11 "call_indirect" CallIndirect _ Mvp;
+    0x1A "drop" None _ Mvp;
+    0x1B "select" None _ Mvp;
+    0x1C "select_t" SelectT _ ReferenceTypes;
+    0x20 "local.get" Local _ Mvp;
+    0x21 "local.set" Local _ Mvp;
+    0x22 "local.tee" Local _ Mvp;
+    0x23 "global.get" Global _ Mvp;
+    0x24 "global.set" Global _ Mvp;
+    0x25 "table.get" Table _ ReferenceTypes;
+    0x26 "table.set" Table _ ReferenceTypes;
+    0x28 "i32.load" MemArg (I32 => I32) Mvp;
+    0x29 "i64.load" MemArg (I32 => I64) Mvp;
+    0x2A "f32.load" MemArg (I32 => F32) Mvp;
+    0x2B "f64.load" MemArg (I32 => F64) Mvp;
+    0x2C "i32.load8_s" MemArg (I32 => I32) Mvp;
+    0x2D "i32.load8_u" MemArg (I32 => I32) Mvp;
+    0x2E "i32.load16_s" MemArg (I32 => I32) Mvp;
+    0x2F "i32.load16_u" MemArg (I32 => I32) Mvp;
+    0x30 "i64.load8_s" MemArg (I32 => I64) Mvp;
+    0x31 "i64.load8_u" MemArg (I32 => I64) Mvp;
+    0x32 "i64.load16_s" MemArg (I32 => I64) Mvp;
+    0x33 "i64.load16_u" MemArg (I32 => I64) Mvp;
+    0x34 "i64.load32_s" MemArg (I32 => I64) Mvp;
+    0x35 "i64.load32_u" MemArg (I32 => I64) Mvp;
+    0x36 "i32.store" MemArg (I32 I32 =>) Mvp;
+    0x37 "i64.store" MemArg (I32 I64 =>) Mvp;
+    0x38 "f32.store" MemArg (I32 F32 =>) Mvp;
+    0x39 "f64.store" MemArg (I32 F64 =>) Mvp;
+    0x3A "i32.store8" MemArg (I32 I32 =>) Mvp;
+    0x3B "i32.store16" MemArg (I32 I32 =>) Mvp;
+    0x3C "i64.store8" MemArg (I32 I64 =>) Mvp;
+    0x3D "i64.store16" MemArg (I32 I64 =>) Mvp;
+    0x3E "i64.store32" MemArg (I32 I64 =>) Mvp;
+    0x3F "memory.size" Mem (=> I32) Mvp;
+    0x40 "memory.grow" Mem (I32 => I32) Mvp;
+    0x41 "i32.const" I32 (=> I32) Mvp;
+    0x42 "i64.const" I64 (=> I64) Mvp;
+    0x43 "f32.const" F32 (=> F32) Mvp;
+    0x44 "f64.const" F64 (=> F64) Mvp;
+    0x45 "i32.eqz" None (I32 => I32) Mvp;
+    0x46 "i32.eq" None (I32 I32 => I32) Mvp;
+    0x47 "i32.ne" None (I32 I32 => I32) Mvp;
+    0x48 "i32.lt_s" None (I32 I32 => I32) Mvp;
+    0x49 "i32.lt_u" None (I32 I32 => I32) Mvp;
+    0x4A "i32.gt_s" None (I32 I32 => I32) Mvp;
+    0x4B "i32.gt_u" None (I32 I32 => I32) Mvp;
+    0x4C "i32.le_s" None (I32 I32 => I32) Mvp;
+    0x4D "i32.le_u" None (I32 I32 => I32) Mvp;
+    0x4E "i32.ge_s" None (I32 I32 => I32) Mvp;
+    0x4F "i32.ge_u" None (I32 I32 => I32) Mvp;
+    0x50 "i64.eqz" None (I64 => I32) Mvp;
+    0x51 "i64.eq" None (I64 I64 => I32) Mvp;
+    0x52 "i64.ne" None (I64 I64 => I32) Mvp;
+    0x53 "i64.lt_s" None (I64 I64 => I32) Mvp;
+    0x54 "i64.lt_u" None (I64 I64 => I32) Mvp;
+    0x55 "i64.gt_s" None (I64 I64 => I32) Mvp;
+    0x56 "i64.gt_u" None (I64 I64 => I32) Mvp;
+    0x57 "i64.le_s" None (I64 I64 => I32) Mvp;
+    0x58 "i64.le_u" None (I64 I64 => I32) Mvp;
+    0x59 "i64.ge_s" None (I64 I64 => I32) Mvp;
+    0x5A "i64.ge_u" None (I64 I64 => I32) Mvp;
+    0x5B "f32.eq" None (F32 F32 => I32) Mvp;
+    0x5C "f32.ne" None (F32 F32 => I32) Mvp;
+    0x5D "f32.lt" None (F32 F32 => I32) Mvp;
+    0x5E "f32.gt" None (F32 F32 => I32) Mvp;
+    0x5F "f32.le" None (F32 F32 => I32) Mvp;
+    0x60 "f32.ge" None (F32 F32 => I32) Mvp;
+    0x61 "f64.eq" None (F64 F64 => I32) Mvp;
+    0x62 "f64.ne" None (F64 F64 => I32) Mvp;
+    0x63 "f64.lt" None (F64 F64 => I32) Mvp;
+    0x64 "f64.gt" None (F64 F64 => I32) Mvp;
+    0x65 "f64.le" None (F64 F64 => I32) Mvp;
+    0x66 "f64.ge" None (F64 F64 => I32) Mvp;
+    0x67 "i32.clz" None (I32 => I32) Mvp;
+    0x68 "i32.ctz" None (I32 => I32) Mvp;
+    0x69 "i32.popcnt" None (I32 => I32) Mvp;
+    0x6A "i32.add" None (I32 I32 => I32) Mvp;
+    0x6B "i32.sub" None (I32 I32 => I32) Mvp;
+    0x6C "i32.mul" None (I32 I32 => I32) Mvp;
+    0x6D "i32.div_s" None (I32 I32 => I32) Mvp;
+    0x6E "i32.div_u" None (I32 I32 => I32) Mvp;
+    0x6F "i32.rem_s" None (I32 I32 => I32) Mvp;
+    0x70 "i32.rem_u" None (I32 I32 => I32) Mvp;
+    0x71 "i32.and" None (I32 I32 => I32) Mvp;
+    0x72 "i32.or" None (I32 I32 => I32) Mvp;
+    0x73 "i32.xor" None (I32 I32 => I32) Mvp;
+    0x74 "i32.shl" None (I32 I32 => I32) Mvp;
+    0x75 "i32.shr_s" None (I32 I32 => I32) Mvp;
+    0x76 "i32.shr_u" None (I32 I32 => I32) Mvp;
+    0x77 "i32.rotl" None (I32 I32 => I32) Mvp;
+    0x78 "i32.rotr" None (I32 I32 => I32) Mvp;
+    0x79 "i64.clz" None (I64 => I64) Mvp;
+    0x7A "i64.ctz" None (I64 => I64) Mvp;
+    0x7B "i64.popcnt" None (I64 => I64) Mvp;
+    0x7C "i64.add" None (I64 I64 => I64) Mvp;
+    0x7D "i64.sub" None (I64 I64 => I64) Mvp;
+    0x7E "i64.mul" None (I64 I64 => I64) Mvp;
+    0x7F "i64.div_s" None (I64 I64 => I64) Mvp;
+    0x80 "i64.div_u" None (I64 I64 => I64) Mvp;
+    0x81 "i64.rem_s" None (I64 I64 => I64) Mvp;
+    0x82 "i64.rem_u" None (I64 I64 => I64) Mvp;
+    0x83 "i64.and" None (I64 I64 => I64) Mvp;
+    0x84 "i64.or" None (I64 I64 => I64) Mvp;
+    0x85 "i64.xor" None (I64 I64 => I64) Mvp;
+    0x86 "i64.shl" None (I64 I64 => I64) Mvp;
+    0x87 "i64.shr_s" None (I64 I64 => I64) Mvp;
+    0x88 "i64.shr_u" None (I64 I64 => I64) Mvp;
+    0x89 "i64.rotl" None (I64 I64 => I64) Mvp;
+    0x8A "i64.rotr" None (I64 I64 => I64) Mvp;
+    0x8B "f32.abs" None (F32 => F32) Mvp;
+    0x8C "f32.neg" None (F32 => F32) Mvp;
+    0x8D "f32.ceil" None (F32 => F32) Mvp;
+    0x8E "f32.floor" None (F32 => F32) Mvp;
+    0x8F "f32.trunc" None (F32 => F32) Mvp;
+    0x90 "f32.nearest" None (F32 => F32) Mvp;
+    0x91 "f32.sqrt" None (F32 => F32) Mvp;
+    0x92 "f32.add" None (F32 F32 => F32) Mvp;
+    0x93 "f32.sub" None (F32 F32 => F32) Mvp;
+    0x94 "f32.mul" None (F32 F32 => F32) Mvp;
+    0x95 "f32.div" None (F32 F32 => F32) Mvp;
+    0x96 "f32.min" None (F32 F32 => F32) Mvp;
+    0x97 "f32.max" None (F32 F32 => F32) Mvp;
+    0x98 "f32.copysign" None (F32 F32 => F32) Mvp;
+    0x99 "f64.abs" None (F64 => F64) Mvp;
+    0x9A "f64.neg" None (F64 => F64) Mvp;
+    0x9B "f64.ceil" None (F64 => F64) Mvp;
+    0x9C "f64.floor" None (F64 => F64) Mvp;
+    0x9D "f64.trunc" None (F64 => F64) Mvp;
+    0x9E "f64.nearest" None (F64 => F64) Mvp;
+    0x9F "f64.sqrt" None (F64 => F64) Mvp;
+    0xA0 "f64.add" None (F64 F64 => F64) Mvp;
+    0xA1 "f64.sub" None (F64 F64 => F64) Mvp;
+    0xA2 "f64.mul" None (F64 F64 => F64) Mvp;
+    0xA3 "f64.div" None (F64 F64 => F64) Mvp;
+    0xA4 "f64.min" None (F64 F64 => F64) Mvp;
+    0xA5 "f64.max" None (F64 F64 => F64) Mvp;
+    0xA6 "f64.copysign" None (F64 F64 => F64) Mvp;
+    0xA7 "i32.wrap_i64" None (I64 => I32) Mvp;
+    0xA8 "i32.trunc_f32_s" None (F32 => I32) Mvp;
+    0xA9 "i32.trunc_f32_u" None (F32 => I32) Mvp;
+    0xAA "i32.trunc_f64_s" None (F64 => I32) Mvp;
+    0xAB "i32.trunc_f64_u" None (F64 => I32) Mvp;
+    0xAC "i64.extend_i32_s" None (I32 => I64) Mvp;
+    0xAD "i64.extend_i32_u" None (I32 => I64) Mvp;
+    0xAE "i64.trunc_f32_s" None (F32 => I64) Mvp;
+    0xAF "i64.trunc_f32_u" None (F32 => I64) Mvp;
+    0xB0 "i64.trunc_f64_s" None (F64 => I64) Mvp;
+    0xB1 "i64.trunc_f64_u" None (F64 => I64) Mvp;
+    0xB2 "f32.convert_i32_s" None (I32 => F32) Mvp;
+    0xB3 "f32.convert_i32_u" None (I32 => F32) Mvp;
+    0xB4 "f32.convert_i64_s" None (I64 => F32) Mvp;
+    0xB5 "f32.convert_i64_u" None (I64 => F32) Mvp;
+    0xB6 "f32.demote_f64" None (F64 => F32) Mvp;
+    0xB7 "f64.convert_i32_s" None (I32 => F64) Mvp;
+    0xB8 "f64.convert_i32_u" None (I32 => F64) Mvp;
+    0xB9 "f64.convert_i64_s" None (I64 => F64) Mvp;
+    0xBA "f64.convert_i64_u" None (I64 => F64) Mvp;
+    0xBB "f64.promote_f32" None (F32 => F64) Mvp;
+    0xBC "i32.reinterpret_f32" None (F32 => I32) Mvp;
+    0xBD "i64.reinterpret_f64" None (F64 => I64) Mvp;
+    0xBE "f32.reinterpret_i32" None (I32 => F32) Mvp;
+    0xBF "f64.reinterpret_i64" None (I64 => F64) Mvp;
+    0xC0 "i32.extend8_s" None (I32 => I32) SignExtensionOperators;
+    0xC1 "i32.extend16_s" None (I32 => I32) SignExtensionOperators;
+    0xC2 "i64.extend8_s" None (I64 => I64) SignExtensionOperators;
+    0xC3 "i64.extend16_s" None (I64 => I64) SignExtensionOperators;
+    0xC4 "i64.extend32_s" None (I64 => I64) SignExtensionOperators;
+    0xD0 "ref.null" RefType _ ReferenceTypes;
+    0xD1 "ref.is_null" None _ ReferenceTypes;
+    0xD2 "ref.func" Func (=> FuncRef) ReferenceTypes;
+    0xFC00 "i32.trunc_sat_f32_s" None (F32 => I32) NonTrapFloatToIntConversions;
+    0xFC01 "i32.trunc_sat_f32_u" None (F32 => I32) NonTrapFloatToIntConversions;
+    0xFC02 "i32.trunc_sat_f64_s" None (F64 => I32) NonTrapFloatToIntConversions;
+    0xFC03 "i32.trunc_sat_f64_u" None (F64 => I32) NonTrapFloatToIntConversions;
+    0xFC04 "i64.trunc_sat_f32_s" None (F32 => I64) NonTrapFloatToIntConversions;
+    0xFC05 "i64.trunc_sat_f32_u" None (F32 => I64) NonTrapFloatToIntConversions;
+    0xFC06 "i64.trunc_sat_f64_s" None (F64 => I64) NonTrapFloatToIntConversions;
+    0xFC07 "i64.trunc_sat_f64_u" None (F64 => I64) NonTrapFloatToIntConversions;
+    0xFC08 "memory.init" MemInit (I32 I32 I32 =>) BulkMemoryOperations;
+    0xFC09 "data.drop" Data (=>) BulkMemoryOperations;
+    0xFC0A "memory.copy" MemCopy (I32 I32 I32 =>) BulkMemoryOperations;
+    0xFC0B "memory.fill" Mem (I32 I32 I32 =>) BulkMemoryOperations;
+    0xFC0C "table.init" TableInit (I32 I32 I32 =>) BulkMemoryOperations;
+    0xFC0D "elem.drop" Elem (=>) BulkMemoryOperations;
+    0xFC0E "table.copy" TableCopy (I32 I32 I32 =>) BulkMemoryOperations;
+    0xFC0F "table.grow" Table _ ReferenceTypes;
+    0xFC10 "table.size" Table (=> I32) ReferenceTypes;
+    0xFC11 "table.fill" Table _ ReferenceTypes;
+}
+
+/// Returns the description of an opcode, or `None` for unknown opcodes.
+///
+/// SIMD opcodes are decoded but not described individually; see `simd_imm`.
+pub(crate) fn info(code: u16) -> Option<&'static OpInfo> {
+    OPCODES
+        .binary_search_by_key(&code, |op| op.code)
+        .ok()
+        .map(|i| &OPCODES[i])
+}
+
+/// Returns the immediates of a SIMD opcode, given its code after the `0xFD` prefix.
+pub(crate) fn simd_imm(sub: u16) -> ImmKind {
+    match sub {
+        0x00..=0x0B | 0x5C | 0x5D => ImmKind::MemArg,
+        0x0C | 0x0D => ImmKind::V128,
+        0x15..=0x22 => ImmKind::Lane,
+        0x54..=0x5B => ImmKind::MemArgLane,
+        _ => ImmKind::None,
+    }
+}
+
+pub(crate) const PREFIX_MISC: u8 = 0xFC;
+pub(crate) const PREFIX_SIMD: u8 = 0xFD;
+
+/// The type of a `block`, `loop` or `if`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BlockType {
+    Empty,
+    Value(ValType),
+    /// A function type index, for blocks with parameters or several results.
+    Func(u32),
+}
+
+/// The decoded immediates of an instruction. Only the ones used by the crate are kept.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Imm {
+    None,
+    BlockType(BlockType),
+    /// A single index: a label, function, local, global, table, segment or type.
+    Index(u32),
+    /// `call_indirect`'s type index and table index, or `table.init`'s segment and table.
+    Pair(u32, u32),
+    BrTable(Vec<u32>, u32),
+    Types(Vec<ValType>),
+    RefType(RefType),
+}
+
+/// A decoded instruction.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Instr {
+    pub(crate) code: u16,
+    /// The offset of the opcode in the module bytes.
+    pub(crate) offset: usize,
+    pub(crate) imm: Imm,
+}
+impl Instr {
+    /// Decodes the next instruction.
+    pub(crate) fn read(reader: &mut Reader) -> WasmEdgeResult<Instr> {
+        let offset = reader.offset();
+        let byte = reader.u8()?;
+        let (code, kind) = match byte {
+            PREFIX_MISC | PREFIX_SIMD => {
+                let sub = reader.u32()?;
+                let code = (u16::from(byte) << 8) | (sub & 0xFF) as u16;
+                let kind = match (byte, info(code)) {
+                    (PREFIX_SIMD, _) => simd_imm(sub as u16),
+                    (_, Some(op)) if sub <= 0xFF => op.imm,
+                    _ => return Err(illegal_opcode(code, offset)),
+                };
+                (code, kind)
+            }
+            _ => match info(u16::from(byte)) {
+                Some(op) => (op.code, op.imm),
+                None => return Err(illegal_opcode(u16::from(byte), offset)),
+            },
+        };
+        let imm = match kind {
+            ImmKind::None => Imm::None,
+            ImmKind::BlockType => Imm::BlockType(match reader.peek() {
+                Some(0x40) => {
+                    reader.u8()?;
+                    BlockType::Empty
+                }
+                Some(0x7F) | Some(0x7E) | Some(0x7D) | Some(0x7C) | Some(0x7B) | Some(0x70)
+                | Some(0x6F) => BlockType::Value(reader.val_type()?),
+                _ => match reader.s33()? {
+                    idx if idx >= 0 && idx <= i64::from(u32::MAX) => BlockType::Func(idx as u32),
+                    _ => return Err(illegal_opcode(code, offset)),
+                },
+            }),
+            ImmKind::Label
+            | ImmKind::Func
+            | ImmKind::Local
+            | ImmKind::Global
+            | ImmKind::Table
+            | ImmKind::Data
+            | ImmKind::Elem => Imm::Index(reader.u32()?),
+            ImmKind::CallIndirect | ImmKind::TableInit | ImmKind::TableCopy => {
+                Imm::Pair(reader.u32()?, reader.u32()?)
+            }
+            ImmKind::BrTable => {
+                let mut labels = vec![];
+                for _ in 0..reader.vec_len()? {
+                    labels.push(reader.u32()?);
+                }
+                Imm::BrTable(labels, reader.u32()?)
+            }
+            ImmKind::MemArg => {
+                reader.u32()?;
+                reader.u32()?;
+                Imm::None
+            }
+            ImmKind::MemArgLane => {
+                reader.u32()?;
+                reader.u32()?;
+                reader.u8()?;
+                Imm::None
+            }
+            ImmKind::Mem | ImmKind::Lane => {
+                reader.u8()?;
+                Imm::None
+            }
+            ImmKind::MemInit => {
+                let idx = reader.u32()?;
+                reader.u8()?;
+                Imm::Index(idx)
+            }
+            ImmKind::MemCopy => {
+                reader.u8()?;
+                reader.u8()?;
+                Imm::None
+            }
+            ImmKind::I32 => {
+                reader.s32()?;
+                Imm::None
+            }
+            ImmKind::I64 => {
+                reader.s64()?;
+                Imm::None
+            }
+            ImmKind::F32 => {
+                reader.bytes(4)?;
+                Imm::None
+            }
+            ImmKind::F64 => {
+                reader.bytes(8)?;
+                Imm::None
+            }
+            ImmKind::V128 => {
+                reader.bytes(16)?;
+                Imm::None
+            }
+            ImmKind::SelectT => {
+                let mut types = vec![];
+                for _ in 0..reader.vec_len()? {
+                    types.push(reader.val_type()?);
+                }
+                Imm::Types(types)
+            }
+            ImmKind::RefType => Imm::RefType(reader.ref_type()?),
+        };
+        Ok(Instr { code, offset, imm })
+    }
+
+    pub(crate) fn info(&self) -> Option<&'static OpInfo> {
+        info(self.code)
+    }
+
+    pub(crate) fn is_simd(&self) -> bool {
+        self.code >> 8 == u16::from(PREFIX_SIMD)
+    }
+}
+
+fn illegal_opcode(code: u16, offset: usize) -> crate::error::WasmEdgeError {
+    crate::binary::binary_error(
+        0x37,
+        format!("illegal opcode {:#x} at offset {:#x}", code, offset),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opcode_table() {
+        // `info` relies on the table being sorted and free of duplicates
+        assert!(OPCODES.windows(2).all(|w| w[0].code < w[1].code));
+        let mut names: Vec<_> = OPCODES.iter().map(|op| op.name).collect();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), OPCODES.len());
+
+        assert_eq!(info(0x6A).unwrap().name, "i32.add");
+        assert_eq!(info(0xFC0A).unwrap().name, "memory.copy");
+        assert_eq!(
+            info(0xC0).unwrap().proposal,
+            Some(Proposal::SignExtensionOperators)
+        );
+        assert!(info(0x06).is_none());
+    }
+
+    #[test]
+    fn test_opcode_read() {
+        let bytes = [
+            0x02, 0x40, /* block */
+            0x41, 0x7F, /* i32.const -1 */
+            0x0E, 0x02, 0x00, 0x01, 0x00, /* br_table 0 1 0 */
+            0xFC, 0x0A, 0x00, 0x00, /* memory.copy */
+            0xFD, 0x0C, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,    /* v128.const */
+            0x06, /* illegal */
+        ];
+        let mut reader = Reader::new(&bytes, 0x10);
+        let instr = Instr::read(&mut reader).unwrap();
+        assert_eq!(instr.imm, Imm::BlockType(BlockType::Empty));
+        let instr = Instr::read(&mut reader).unwrap();
+        assert_eq!((instr.code, instr.offset), (0x41, 0x12));
+        let instr = Instr::read(&mut reader).unwrap();
+        assert_eq!(instr.imm, Imm::BrTable(vec![0, 1], 0));
+        let instr = Instr::read(&mut reader).unwrap();
+        assert_eq!(instr.info().unwrap().name, "memory.copy");
+        let instr = Instr::read(&mut reader).unwrap();
+        assert!(instr.is_simd());
+        assert_eq!(Instr::read(&mut reader).unwrap_err().code(), Some(0x37));
+    }
+}
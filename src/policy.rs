@@ -0,0 +1,370 @@
+//! Sandbox rules that a module must follow in addition to the specification.
+
+use crate::binary::{ModuleInfo, Reader};
+use crate::context::ast::{ASTModuleContext, Limits};
+use crate::error::{WasmEdgeError, WasmEdgeResult};
+use crate::opcode::Instr;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+/// A rule of a `ValidationPolicy` that a module breaks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PolicyViolation {
+    /// The module imports an item outside the allowlist.
+    ImportNotAllowed { module: String, name: String },
+    /// A function uses a float instruction. Only the first one of each function is reported.
+    FloatInstruction {
+        function: u32,
+        offset: usize,
+        instruction: &'static str,
+    },
+    /// A memory may grow beyond the page cap, or has no maximum at all.
+    MemoryTooLarge {
+        memory: u32,
+        limits: Limits,
+        cap: u32,
+    },
+    /// The module declares a start function.
+    StartFunction { function: u32 },
+}
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PolicyViolation::ImportNotAllowed { module, name } => {
+                write!(f, "import {}::{} is not allowed", module, name)
+            }
+            PolicyViolation::FloatInstruction {
+                function,
+                offset,
+                instruction,
+            } => write!(
+                f,
+                "function {} uses the float instruction {} at offset {:#x}",
+                function, instruction, offset
+            ),
+            PolicyViolation::MemoryTooLarge {
+                memory,
+                limits,
+                cap,
+            } => match limits.max {
+                Some(max) => write!(
+                    f,
+                    "memory {} may grow to {} pages, above the cap of {}",
+                    memory, max, cap
+                ),
+                None => write!(
+                    f,
+                    "memory {} has no maximum, but the cap is {} pages",
+                    memory, cap
+                ),
+            },
+            PolicyViolation::StartFunction { function } => {
+                write!(f, "start function {} is not allowed", function)
+            }
+        }
+    }
+}
+
+/// Rules for modules beyond spec validation, e.g. for sandboxes or deterministic workloads.
+///
+/// A policy can be built in code or read from TOML:
+///
+/// ```toml
+/// deny_float = true
+/// deny_start = true
+/// max_memory_pages = 256
+///
+/// # Allowed imports by module; "*" allows any name or any module
+/// [imports]
+/// wasi_snapshot_preview1 = ["fd_write", "proc_exit"]
+/// env = ["*"]
+/// ```
+///
+/// Without an `imports` table, any import is allowed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ValidationPolicy {
+    imports: Option<BTreeMap<String, Vec<String>>>,
+    deny_float: bool,
+    deny_start: bool,
+    max_memory_pages: Option<u32>,
+}
+impl ValidationPolicy {
+    pub fn new() -> Self {
+        ValidationPolicy::default()
+    }
+
+    pub fn from_toml_str(s: &str) -> WasmEdgeResult<Self> {
+        toml::from_str(s).map_err(|e| WasmEdgeError::from(format!("Invalid policy: {}", e)))
+    }
+
+    pub fn from_toml_file<P: AsRef<Path>>(path: P) -> WasmEdgeResult<Self> {
+        match std::fs::read_to_string(path.as_ref()) {
+            Ok(s) => Self::from_toml_str(&s),
+            Err(e) => Err(WasmEdgeError::from(format!(
+                "Failed to read policy file '{}': {}",
+                path.as_ref().display(),
+                e
+            ))),
+        }
+    }
+
+    /// Allows importing `name` from `module`. Either may be `"*"` to match anything.
+    ///
+    /// Once an import is allowed, all imports that are not allowed are violations.
+    pub fn allow_import(
+        &mut self,
+        module: impl Into<String>,
+        name: impl Into<String>,
+    ) -> &mut Self {
+        self.imports
+            .get_or_insert_with(BTreeMap::new)
+            .entry(module.into())
+            .or_insert_with(Vec::new)
+            .push(name.into());
+        self
+    }
+
+    /// Rejects instructions that take or produce floats, e.g. for deterministic workloads.
+    ///
+    /// SIMD instructions are rejected as a whole, since their float lanes are not told apart.
+    pub fn deny_float(&mut self, deny: bool) -> &mut Self {
+        self.deny_float = deny;
+        self
+    }
+
+    pub fn deny_start(&mut self, deny: bool) -> &mut Self {
+        self.deny_start = deny;
+        self
+    }
+
+    /// Rejects memories whose maximum is above `pages` or that have no maximum.
+    pub fn max_memory_pages(&mut self, pages: u32) -> &mut Self {
+        self.max_memory_pages = Some(pages);
+        self
+    }
+
+    fn is_import_allowed(&self, module: &str, name: &str) -> bool {
+        let imports = match &self.imports {
+            Some(imports) => imports,
+            None => return true,
+        };
+        [module, "*"].iter().any(|module| {
+            imports
+                .get(*module)
+                .map_or(false, |names| names.iter().any(|n| n == name || n == "*"))
+        })
+    }
+
+    /// Returns every violation of the policy by a module loaded by a `LoaderContext`.
+    ///
+    /// The module is checked as the loader parsed it, from the bytes captured at load time.
+    pub fn check(&self, ast_mod: &ASTModuleContext) -> WasmEdgeResult<Vec<PolicyViolation>> {
+        let bytes = ast_mod.bytes()?;
+        let info = ModuleInfo::parse(&bytes)?;
+        let mut violations = vec![];
+
+        for import in info.imports.iter() {
+            if !self.is_import_allowed(&import.module, &import.name) {
+                violations.push(PolicyViolation::ImportNotAllowed {
+                    module: import.module.clone(),
+                    name: import.name.clone(),
+                });
+            }
+        }
+
+        if self.deny_float {
+            let imported = info.funcs.len() - info.bodies.len().min(info.funcs.len());
+            for (i, body) in info.bodies.iter().enumerate() {
+                let mut reader = Reader::new(&bytes[body.code.clone()], body.code.start);
+                while !reader.is_empty() {
+                    let instr = Instr::read(&mut reader)?;
+                    let name = match (instr.is_simd(), instr.info()) {
+                        (true, _) => "simd",
+                        (false, Some(op)) if is_float(op.name) => op.name,
+                        _ => continue,
+                    };
+                    violations.push(PolicyViolation::FloatInstruction {
+                        function: (imported + i) as u32,
+                        offset: instr.offset,
+                        instruction: name,
+                    });
+                    break;
+                }
+            }
+        }
+
+        if let Some(cap) = self.max_memory_pages {
+            for (i, memory) in info.memories.iter().enumerate() {
+                if memory.limits.max.map_or(true, |max| max > cap) {
+                    violations.push(PolicyViolation::MemoryTooLarge {
+                        memory: i as u32,
+                        limits: memory.limits,
+                        cap,
+                    });
+                }
+            }
+        }
+
+        if let (true, Some(function)) = (self.deny_start, info.start) {
+            violations.push(PolicyViolation::StartFunction { function });
+        }
+        Ok(violations)
+    }
+}
+
+/// Whether an instruction takes or produces a float, e.g. `f32.add` or `i32.trunc_f64_s`.
+fn is_float(name: &str) -> bool {
+    name.starts_with("f32.")
+        || name.starts_with("f64.")
+        || name.contains("_f32")
+        || name.contains("_f64")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{configure::ConfigureContext, loader::LoaderContext};
+
+    const POLICY_WASM: &[u8] = &[
+        /* WASM header */
+        0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00, /* Type section */
+        0x01, 0x04, 0x01, /* function type {} -> {} */
+        0x60, 0x00, 0x00, /* Import section */
+        0x02, 0x19, 0x02, /* import: "env" "f" func 0 */
+        0x03, 0x65, 0x6E, 0x76, 0x01, 0x66, 0x00, 0x00,
+        /* import: "wasi" "fd_write" func 0 */
+        0x04, 0x77, 0x61, 0x73, 0x69, 0x08, 0x66, 0x64, 0x5F, 0x77, 0x72, 0x69, 0x74, 0x65, 0x00,
+        0x00, /* Function section */
+        0x03, 0x02, 0x01, 0x00, /* Memory section: min 2, no max */
+        0x05, 0x03, 0x01, 0x00, 0x02, /* Start section: func 2 */
+        0x08, 0x01, 0x02, /* Code section */
+        0x0A, 0x0A, 0x01, 0x08, 0x00, /* f32.const 0, drop */
+        0x43, 0x00, 0x00, 0x00, 0x00, 0x1A, 0x0B,
+    ];
+
+    fn load(buf: &[u8]) -> ASTModuleContext {
+        let conf = ConfigureContext::create();
+        let mut loader = LoaderContext::create(&conf).unwrap();
+        let mut ast_mod = ASTModuleContext::default();
+        assert!(loader.parse_from_buffer(&mut ast_mod, buf).is_ok());
+        ast_mod
+    }
+
+    #[test]
+    fn test_policy_check() {
+        let ast_mod = load(POLICY_WASM);
+        assert!(ValidationPolicy::new().check(&ast_mod).unwrap().is_empty());
+
+        let mut policy = ValidationPolicy::new();
+        policy
+            .allow_import("env", "*")
+            .deny_float(true)
+            .deny_start(true)
+            .max_memory_pages(16);
+        let violations = policy.check(&ast_mod).unwrap();
+        assert_eq!(
+            violations,
+            vec![
+                PolicyViolation::ImportNotAllowed {
+                    module: String::from("wasi"),
+                    name: String::from("fd_write"),
+                },
+                PolicyViolation::FloatInstruction {
+                    function: 2,
+                    offset: 58,
+                    instruction: "f32.const",
+                },
+                PolicyViolation::MemoryTooLarge {
+                    memory: 0,
+                    limits: Limits { min: 2, max: None },
+                    cap: 16,
+                },
+                PolicyViolation::StartFunction { function: 2 },
+            ]
+        );
+        assert_eq!(
+            violations[0].to_string(),
+            "import wasi::fd_write is not allowed"
+        );
+
+        let mut policy = ValidationPolicy::new();
+        policy
+            .allow_import("*", "fd_write")
+            .allow_import("env", "f");
+        assert!(policy.check(&ast_mod).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_policy_check_parsed_bytes() {
+        use crate::utils::TempDir;
+
+        // The policy sees the module that was parsed, even if the file changes afterwards
+        let dir = TempDir::new().unwrap();
+        let path = dir.create_file("policy.wasm", POLICY_WASM).unwrap();
+        let conf = ConfigureContext::create();
+        let mut loader = LoaderContext::create(&conf).unwrap();
+        let mut ast_mod = ASTModuleContext::default();
+        assert!(loader.parse_from_file(&mut ast_mod, &path).is_ok());
+        std::fs::write(&path, &POLICY_WASM[..8]).unwrap();
+
+        let mut policy = ValidationPolicy::new();
+        policy.deny_start(true);
+        assert_eq!(
+            policy.check(&ast_mod).unwrap(),
+            vec![PolicyViolation::StartFunction { function: 2 }]
+        );
+    }
+
+    #[test]
+    fn test_policy_toml() {
+        let policy = ValidationPolicy::from_toml_str(
+            r#"
+            deny_float = true
+            max_memory_pages = 1
+
+            [imports]
+            env = ["f"]
+            "#,
+        )
+        .unwrap();
+        let mut expected = ValidationPolicy::new();
+        expected
+            .allow_import("env", "f")
+            .deny_float(true)
+            .max_memory_pages(1);
+        assert_eq!(policy, expected);
+        assert_eq!(
+            ValidationPolicy::from_toml_str("").unwrap(),
+            ValidationPolicy::new()
+        );
+
+        assert!(ValidationPolicy::from_toml_str("deny_floats = true").is_err());
+        assert!(ValidationPolicy::from_toml_str("max_memory_pages = -1").is_err());
+        assert!(ValidationPolicy::from_toml_file("policy.toml").is_err());
+    }
+
+    #[test]
+    fn test_policy_validator() {
+        use crate::context::validator::Validator;
+
+        let ast_mod = load(POLICY_WASM);
+        let conf = ConfigureContext::create();
+        let validator = Validator::create(&conf).unwrap();
+        let mut policy = ValidationPolicy::new();
+        policy.deny_start(true);
+        let err = validator
+            .validate_with_policy(&ast_mod, &policy)
+            .unwrap_err();
+        assert_eq!(
+            err,
+            WasmEdgeError::Policy(vec![PolicyViolation::StartFunction { function: 2 }])
+        );
+        assert_eq!(err.phase(), crate::error::ErrorPhase::Validation);
+        assert!(validator
+            .validate_with_policy(&ast_mod, &ValidationPolicy::new())
+            .is_ok());
+    }
+}
@@ -0,0 +1,433 @@
+//! Per-function execution profiles.
+//!
+//! A VM in profiling mode instruments the modules it loads, see `VMContext::enable_profiling`.
+//! The probes read the statistics context of the VM on every function entry and exit, so the
+//! instruction counts and costs are only recorded when the VM is created with instruction
+//! counting and cost measuring enabled.
+
+use crate::{
+    context::{
+        import_object::ImportObjectContext,
+        statistics::{ExecutionReport, StatisticsContext, StatisticsSnapshot},
+    },
+    error::{WasmEdgeError, WasmEdgeResult},
+    instance::function::{FunctionTypeContext, HostFunctionContext},
+    instrument::{ProbedFunction, PROBE_ENTER, PROBE_EXIT, PROBE_MODULE},
+    types::{HostFunc, ValType, WasmEdgeValue},
+    value::Val,
+};
+use serde::{Serialize, Serializer};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::marker::PhantomData;
+use std::os::raw::c_void;
+use std::ptr;
+use std::time::Duration;
+use wasmedge_sys::ffi as we_ffi;
+
+/// What a function executed and how long it ran, over all of its calls.
+///
+/// The self counts exclude the callees. They include the few instructions of the profiling
+/// probes, which are the same for every call.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FunctionProfile {
+    pub name: String,
+    /// Whether the function is imported, e.g. a host function.
+    pub host: bool,
+    pub calls: u64,
+    pub self_instr_count: u64,
+    pub self_cost: u64,
+    #[serde(rename = "self_time_ns", serialize_with = "nanos")]
+    pub self_time: Duration,
+    /// The wall time of the calls including their callees. Recursive calls are counted once.
+    #[serde(rename = "inclusive_time_ns", serialize_with = "nanos")]
+    pub inclusive_time: Duration,
+}
+
+/// What was executed with a given call stack, excluding the callees of its innermost function.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StackProfile {
+    /// The function names, outermost first.
+    pub frames: Vec<String>,
+    pub self_instr_count: u64,
+    pub self_cost: u64,
+    #[serde(rename = "self_time_ns", serialize_with = "nanos")]
+    pub self_time: Duration,
+}
+
+/// The measure used to weigh the stacks of a folded-stack profile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProfileWeight {
+    Instructions,
+    Cost,
+    /// Wall time in nanoseconds.
+    Time,
+}
+
+/// A per-function profile of what a VM executed, see `VMContext::profile`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Profile {
+    functions: Vec<FunctionProfile>,
+    stacks: Vec<StackProfile>,
+}
+impl Profile {
+    /// Returns the functions that were called, in function index order.
+    pub fn functions(&self) -> impl Iterator<Item = &FunctionProfile> {
+        self.functions.iter()
+    }
+
+    pub fn function(&self, name: &str) -> Option<&FunctionProfile> {
+        self.functions.iter().find(|func| func.name == name)
+    }
+
+    /// Returns the call stacks that were executed, ordered by their frames.
+    pub fn stacks(&self) -> impl Iterator<Item = &StackProfile> {
+        self.stacks.iter()
+    }
+
+    /// Renders the stacks in the folded format of `flamegraph.pl` and `inferno`, one
+    /// `outer;inner weight` line per stack. Stacks with no weight are left out.
+    pub fn to_folded(&self, weight: ProfileWeight) -> String {
+        let mut folded = String::new();
+        for stack in self.stacks.iter() {
+            let value = match weight {
+                ProfileWeight::Instructions => stack.self_instr_count,
+                ProfileWeight::Cost => stack.self_cost,
+                ProfileWeight::Time => stack.self_time.as_nanos() as u64,
+            };
+            if value > 0 {
+                let _ = writeln!(folded, "{} {}", stack.frames.join(";"), value);
+            }
+        }
+        folded
+    }
+
+    pub fn to_json_string(&self) -> WasmEdgeResult<String> {
+        serde_json::to_string_pretty(self).map_err(|e| WasmEdgeError::from(e.to_string()))
+    }
+}
+
+fn nanos<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(duration.as_nanos() as u64)
+}
+
+/// The recording side of profiling mode: the probe import object and what the probes recorded.
+pub(crate) struct Profiler {
+    /// Boxed so that the probes can keep its address as their host data.
+    recorder: Box<RefCell<Recorder>>,
+    import: ImportObjectContext<'static>,
+}
+impl Profiler {
+    /// Creates the probes, which read the counters of `stat` if it is not null.
+    pub(crate) fn create(stat: *mut we_ffi::WasmEdge_StatisticsContext) -> WasmEdgeResult<Self> {
+        let recorder = Box::new(RefCell::new(Recorder {
+            stat,
+            ..Default::default()
+        }));
+        let data = &*recorder as *const RefCell<Recorder> as *mut c_void;
+        let mut import = ImportObjectContext::create(PROBE_MODULE, data)?;
+        let func_type =
+            FunctionTypeContext::create(Some(&[ValType::I32]), None).ok_or_else(|| {
+                WasmEdgeError::from(String::from("Failed to create the profiler probe type"))
+            })?;
+        let probes: [(&str, HostFunc); 2] = [
+            (PROBE_ENTER, Some(probe_enter)),
+            (PROBE_EXIT, Some(probe_exit)),
+        ];
+        for (name, probe) in probes.iter() {
+            let mut host_func =
+                HostFunctionContext::create(&func_type, *probe, 0).ok_or_else(|| {
+                    WasmEdgeError::from(String::from("Failed to create a profiler probe"))
+                })?;
+            import.add_host_function(name, &mut host_func)?;
+        }
+        Ok(Profiler { recorder, import })
+    }
+
+    pub(crate) fn import_object(&self) -> &ImportObjectContext<'static> {
+        &self.import
+    }
+
+    /// Starts a new profile for an instrumented module.
+    pub(crate) fn set_functions(&self, functions: Vec<ProbedFunction>) {
+        let mut recorder = self.recorder.borrow_mut();
+        recorder.functions = functions;
+        recorder.reset();
+    }
+
+    pub(crate) fn reset(&self) {
+        self.recorder.borrow_mut().reset();
+    }
+
+    /// Closes the frames left open by a call that trapped.
+    pub(crate) fn unwind(&self) {
+        self.recorder.borrow_mut().unwind();
+    }
+
+    pub(crate) fn profile(&self) -> Profile {
+        self.recorder.borrow().profile()
+    }
+}
+
+/// A function call in progress.
+#[derive(Debug, Clone, Copy)]
+struct Frame {
+    func: u32,
+    start: StatisticsSnapshot,
+    /// What the callees executed, including their own callees.
+    callees: ExecutionReport,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Totals {
+    calls: u64,
+    own: ExecutionReport,
+    inclusive_time: Duration,
+}
+
+struct Recorder {
+    stat: *mut we_ffi::WasmEdge_StatisticsContext,
+    functions: Vec<ProbedFunction>,
+    totals: BTreeMap<u32, Totals>,
+    stacks: BTreeMap<Vec<u32>, ExecutionReport>,
+    frames: Vec<Frame>,
+}
+impl Default for Recorder {
+    fn default() -> Self {
+        Recorder {
+            stat: ptr::null_mut(),
+            functions: vec![],
+            totals: BTreeMap::new(),
+            stacks: BTreeMap::new(),
+            frames: vec![],
+        }
+    }
+}
+impl Recorder {
+    fn snapshot(&self) -> StatisticsSnapshot {
+        match self.stat.is_null() {
+            true => StatisticsSnapshot::empty(),
+            false => StatisticsContext {
+                raw: self.stat,
+                _marker: PhantomData,
+                _drop: false,
+            }
+            .snapshot(),
+        }
+    }
+
+    fn enter(&mut self, func: u32) {
+        let start = self.snapshot();
+        self.frames.push(Frame {
+            func,
+            start,
+            callees: ExecutionReport::default(),
+        });
+    }
+
+    fn exit(&mut self, func: u32) {
+        // Frames above the one being left were abandoned, e.g. by a host function that failed
+        if !self.frames.iter().any(|frame| frame.func == func) {
+            return;
+        }
+        let now = self.snapshot();
+        while let Some(frame) = self.frames.pop() {
+            self.close(frame, &now);
+            if frame.func == func {
+                break;
+            }
+        }
+    }
+
+    fn unwind(&mut self) {
+        let now = self.snapshot();
+        while let Some(frame) = self.frames.pop() {
+            self.close(frame, &now);
+        }
+    }
+
+    /// Records a frame that was just popped.
+    fn close(&mut self, frame: Frame, now: &StatisticsSnapshot) {
+        let inclusive = now.since(&frame.start);
+        let own = ExecutionReport {
+            instr_count: inclusive
+                .instr_count
+                .saturating_sub(frame.callees.instr_count),
+            cost: inclusive.cost.saturating_sub(frame.callees.cost),
+            elapsed: inclusive.elapsed.saturating_sub(frame.callees.elapsed),
+        };
+        let recursive = self.frames.iter().any(|outer| outer.func == frame.func);
+        let totals = self.totals.entry(frame.func).or_default();
+        totals.calls += 1;
+        add(&mut totals.own, &own);
+        if !recursive {
+            totals.inclusive_time += inclusive.elapsed;
+        }
+
+        let mut stack: Vec<_> = self.frames.iter().map(|outer| outer.func).collect();
+        stack.push(frame.func);
+        add(self.stacks.entry(stack).or_default(), &own);
+        if let Some(caller) = self.frames.last_mut() {
+            add(&mut caller.callees, &inclusive);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.totals.clear();
+        self.stacks.clear();
+        self.frames.clear();
+    }
+
+    fn name(&self, func: u32) -> String {
+        match self.functions.get(func as usize) {
+            Some(probed) => probed.name.clone(),
+            None => format!("func[{}]", func),
+        }
+    }
+
+    fn profile(&self) -> Profile {
+        let functions = self
+            .totals
+            .iter()
+            .map(|(func, totals)| FunctionProfile {
+                name: self.name(*func),
+                host: self
+                    .functions
+                    .get(*func as usize)
+                    .map_or(false, |probed| probed.host),
+                calls: totals.calls,
+                self_instr_count: totals.own.instr_count,
+                self_cost: totals.own.cost,
+                self_time: totals.own.elapsed,
+                inclusive_time: totals.inclusive_time,
+            })
+            .collect();
+        let stacks = self
+            .stacks
+            .iter()
+            .map(|(stack, own)| StackProfile {
+                frames: stack.iter().map(|func| self.name(*func)).collect(),
+                self_instr_count: own.instr_count,
+                self_cost: own.cost,
+                self_time: own.elapsed,
+            })
+            .collect();
+        Profile { functions, stacks }
+    }
+}
+
+fn add(total: &mut ExecutionReport, report: &ExecutionReport) {
+    total.instr_count += report.instr_count;
+    total.cost += report.cost;
+    total.elapsed += report.elapsed;
+}
+
+unsafe extern "C" fn probe_enter(
+    data: *mut c_void,
+    _mem_ctx: *mut we_ffi::WasmEdge_MemoryInstanceContext,
+    params: *const WasmEdgeValue,
+    _returns: *mut WasmEdgeValue,
+) -> we_ffi::WasmEdge_Result {
+    let recorder = &*(data as *const RefCell<Recorder>);
+    let func = Val::from(*params).i32().unwrap_or_default() as u32;
+    recorder.borrow_mut().enter(func);
+    we_ffi::WasmEdge_Result { Code: 0 }
+}
+
+unsafe extern "C" fn probe_exit(
+    data: *mut c_void,
+    _mem_ctx: *mut we_ffi::WasmEdge_MemoryInstanceContext,
+    params: *const WasmEdgeValue,
+    _returns: *mut WasmEdgeValue,
+) -> we_ffi::WasmEdge_Result {
+    let recorder = &*(data as *const RefCell<Recorder>);
+    let func = Val::from(*params).i32().unwrap_or_default() as u32;
+    recorder.borrow_mut().exit(func);
+    we_ffi::WasmEdge_Result { Code: 0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recorder() -> Recorder {
+        let functions = [("env.log", true), ("main", false), ("fib", false)]
+            .iter()
+            .map(|(name, host)| ProbedFunction {
+                name: String::from(*name),
+                host: *host,
+            })
+            .collect();
+        Recorder {
+            functions,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_profile_recorder() {
+        let mut recorder = recorder();
+        recorder.enter(1);
+        recorder.enter(2);
+        recorder.enter(2);
+        recorder.exit(2);
+        recorder.enter(0);
+        recorder.exit(0);
+        recorder.exit(2);
+        recorder.exit(1);
+        assert!(recorder.frames.is_empty());
+
+        let profile = recorder.profile();
+        let calls: Vec<_> = profile
+            .functions()
+            .map(|func| (func.name.as_str(), func.host, func.calls))
+            .collect();
+        assert_eq!(
+            calls,
+            [("env.log", true, 1), ("main", false, 1), ("fib", false, 2)]
+        );
+        let main = profile.function("main").unwrap();
+        let fib = profile.function("fib").unwrap();
+        // Recursive calls do not add to the inclusive time twice
+        assert!(fib.inclusive_time <= main.inclusive_time);
+        let stacks: Vec<_> = profile
+            .stacks()
+            .map(|stack| stack.frames.join(";"))
+            .collect();
+        assert_eq!(
+            stacks,
+            ["main", "main;fib", "main;fib;env.log", "main;fib;fib"]
+        );
+
+        let json: serde_json::Value =
+            serde_json::from_str(&profile.to_json_string().unwrap()).unwrap();
+        assert_eq!(json["functions"][2]["name"], "fib");
+        assert_eq!(json["functions"][2]["calls"], 2);
+        assert!(json["functions"][2]["inclusive_time_ns"].is_u64());
+        assert_eq!(json["stacks"][3]["frames"][2], "fib");
+    }
+
+    #[test]
+    fn test_profile_unwind() {
+        let mut recorder = recorder();
+        recorder.enter(1);
+        recorder.enter(2);
+        // An exit of a function that is not running is ignored
+        recorder.exit(0);
+        assert_eq!(recorder.frames.len(), 2);
+        recorder.unwind();
+        assert!(recorder.frames.is_empty());
+        let profile = recorder.profile();
+        assert_eq!(profile.function("fib").unwrap().calls, 1);
+        assert_eq!(profile.function("main").unwrap().calls, 1);
+
+        // Without a statistics context only the time is weighed
+        assert_eq!(profile.to_folded(ProfileWeight::Instructions), "");
+        let folded = profile.to_folded(ProfileWeight::Time);
+        assert!(folded.lines().all(|line| line.starts_with("main")));
+
+        recorder.reset();
+        assert_eq!(recorder.profile(), Profile::default());
+    }
+}
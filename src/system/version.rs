@@ -4,8 +4,7 @@ use wasmedge_sys::ffi as we_ffi;
 pub fn version() -> String {
     let c_buf = unsafe { we_ffi::WasmEdge_VersionGet() };
     let slice: &CStr = unsafe { CStr::from_ptr(c_buf) };
-    let str_slice: &str = slice.to_str().unwrap();
-    str_slice.to_owned()
+    slice.to_string_lossy().into_owned()
 }
 
 pub fn major_version() -> usize {
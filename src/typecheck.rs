@@ -0,0 +1,554 @@
+//! An operand type checker for function bodies, used to explain validation failures.
+//!
+//! WasmEdge only reports an error code when a module fails validation. After a failure, the crate
+//! runs this checker over the module bytes to find the function and instruction at fault. It
+//! follows the validation algorithm of the specification appendix for the instructions described
+//! in `opcode`, and gives up on a function at the first SIMD instruction.
+
+use crate::binary::{FunctionBody, ModuleInfo, Reader};
+use crate::context::ast::FuncType;
+use crate::opcode::{BlockType, Imm, Instr, Sig};
+use crate::types::{Proposal, RefType, ValType};
+
+/// The first problem found in a module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Finding {
+    /// The index of the function in the function index space, imports included.
+    pub(crate) func: u32,
+    /// The offset of the offending instruction, or of the function body, in the module bytes.
+    pub(crate) offset: usize,
+    pub(crate) kind: FindingKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum FindingKind {
+    TypeMismatch {
+        expected: Vec<ValType>,
+        actual: Vec<ValType>,
+    },
+    /// The instruction or type needs a proposal that is not enabled.
+    Proposal(Proposal),
+    /// Anything else, e.g. an unknown index. Only the location is known.
+    Invalid,
+}
+
+/// Checks the function bodies of a module, returning the first problem found.
+///
+/// Returns `None` if the module cannot be decoded or no problem is found.
+pub(crate) fn check_module(bytes: &[u8], enabled: &[Proposal]) -> Option<Finding> {
+    let info = ModuleInfo::parse(bytes).ok()?;
+    let imported = info.funcs.len() - info.bodies.len().min(info.funcs.len());
+    for (i, body) in info.bodies.iter().enumerate() {
+        let func = (imported + i) as u32;
+        let ty = info.funcs.get(imported + i)?;
+        let checker = Checker {
+            info: &info,
+            enabled,
+            func_type: ty,
+        };
+        if let Err((offset, kind)) = checker.check(bytes, body) {
+            return Some(Finding { func, offset, kind });
+        }
+    }
+    None
+}
+
+type Failure = (usize, FindingKind);
+
+struct Frame {
+    opcode: u16,
+    params: Vec<ValType>,
+    results: Vec<ValType>,
+    height: usize,
+    unreachable: bool,
+}
+impl Frame {
+    /// The types a branch to this frame's label carries.
+    fn label_types(&self) -> &[ValType] {
+        match self.opcode {
+            LOOP => &self.params,
+            _ => &self.results,
+        }
+    }
+}
+
+const LOOP: u16 = 0x03;
+const IF: u16 = 0x04;
+
+/// The operand stack and control frames of one function body.
+#[derive(Default)]
+struct State {
+    /// `None` is an operand of unknown type, pushed in unreachable code.
+    operands: Vec<Option<ValType>>,
+    frames: Vec<Frame>,
+}
+impl State {
+    fn push(&mut self, ty: ValType) {
+        self.operands.push(Some(ty));
+    }
+
+    fn push_all(&mut self, types: &[ValType]) {
+        self.operands.extend(types.iter().copied().map(Some));
+    }
+
+    fn pop(
+        &mut self,
+        offset: usize,
+        expected: Option<ValType>,
+    ) -> Result<Option<ValType>, Failure> {
+        let frame = self.frames.last().ok_or((offset, FindingKind::Invalid))?;
+        if self.operands.len() == frame.height {
+            if frame.unreachable {
+                return Ok(expected);
+            }
+            return Err((
+                offset,
+                FindingKind::TypeMismatch {
+                    expected: expected.into_iter().collect(),
+                    actual: vec![],
+                },
+            ));
+        }
+        let actual = self.operands.pop().flatten();
+        match (actual, expected) {
+            (Some(actual), Some(expected)) if actual != expected => Err((
+                offset,
+                FindingKind::TypeMismatch {
+                    expected: vec![expected],
+                    actual: vec![actual],
+                },
+            )),
+            (None, expected) => Ok(expected),
+            (actual, _) => Ok(actual),
+        }
+    }
+
+    fn pop_all(&mut self, offset: usize, types: &[ValType]) -> Result<(), Failure> {
+        for ty in types.iter().rev() {
+            self.pop(offset, Some(*ty))?;
+        }
+        Ok(())
+    }
+
+    /// Checks that the operands of the innermost frame are exactly `types`.
+    fn check_exact(&self, offset: usize, types: &[ValType]) -> Result<(), Failure> {
+        let frame = self.frames.last().ok_or((offset, FindingKind::Invalid))?;
+        let operands = &self.operands[frame.height..];
+        let matches = match frame.unreachable {
+            true => {
+                operands.len() <= types.len()
+                    && operands
+                        .iter()
+                        .rev()
+                        .zip(types.iter().rev())
+                        .all(|(op, ty)| op.map_or(true, |op| op == *ty))
+            }
+            false => {
+                operands.len() == types.len()
+                    && operands.iter().zip(types).all(|(op, ty)| *op == Some(*ty))
+            }
+        };
+        match matches {
+            true => Ok(()),
+            false => Err((
+                offset,
+                FindingKind::TypeMismatch {
+                    expected: types.to_vec(),
+                    actual: operands.iter().flatten().copied().collect(),
+                },
+            )),
+        }
+    }
+
+    fn set_unreachable(&mut self) {
+        if let Some(frame) = self.frames.last_mut() {
+            self.operands.truncate(frame.height);
+            frame.unreachable = true;
+        }
+    }
+
+    fn label(&self, offset: usize, depth: u32) -> Result<&Frame, Failure> {
+        let len = self.frames.len();
+        match (depth as usize) < len {
+            true => Ok(&self.frames[len - 1 - depth as usize]),
+            false => Err((offset, FindingKind::Invalid)),
+        }
+    }
+}
+
+struct Checker<'a> {
+    info: &'a ModuleInfo,
+    enabled: &'a [Proposal],
+    func_type: &'a FuncType,
+}
+impl<'a> Checker<'a> {
+    fn require(&self, offset: usize, proposal: Proposal) -> Result<(), Failure> {
+        match self.enabled.contains(&proposal) {
+            true => Ok(()),
+            false => Err((offset, FindingKind::Proposal(proposal))),
+        }
+    }
+
+    fn block_type(
+        &self,
+        offset: usize,
+        ty: BlockType,
+    ) -> Result<(Vec<ValType>, Vec<ValType>), Failure> {
+        match ty {
+            BlockType::Empty => Ok((vec![], vec![])),
+            BlockType::Value(ty) => Ok((vec![], vec![ty])),
+            BlockType::Func(idx) => {
+                let ty = self
+                    .info
+                    .types
+                    .get(idx as usize)
+                    .ok_or((offset, FindingKind::Invalid))?;
+                if !ty.params.is_empty() || ty.returns.len() > 1 {
+                    self.require(offset, Proposal::MultiValue)?;
+                }
+                Ok((ty.params.clone(), ty.returns.clone()))
+            }
+        }
+    }
+
+    fn local(&self, offset: usize, body: &FunctionBody, idx: u32) -> Result<ValType, Failure> {
+        let mut idx = idx as u64;
+        if let Some(ty) = self.func_type.params.get(idx as usize) {
+            return Ok(*ty);
+        }
+        idx -= self.func_type.params.len() as u64;
+        for (count, ty) in body.locals.iter() {
+            if idx < u64::from(*count) {
+                return Ok(*ty);
+            }
+            idx -= u64::from(*count);
+        }
+        Err((offset, FindingKind::Invalid))
+    }
+
+    fn table(&self, offset: usize, idx: u32) -> Result<ValType, Failure> {
+        match self.info.tables.get(idx as usize) {
+            Some(table) => Ok(ref_val_type(table.ref_type)),
+            None => Err((offset, FindingKind::Invalid)),
+        }
+    }
+
+    fn check(&self, bytes: &[u8], body: &FunctionBody) -> Result<(), Failure> {
+        let start = body.code.start;
+        if self.func_type.returns.len() > 1 {
+            self.require(start, Proposal::MultiValue)?;
+        }
+        let mut reader = Reader::new(bytes.get(body.code.clone()).unwrap_or_default(), start);
+        let mut state = State::default();
+        state.frames.push(Frame {
+            opcode: 0x02,
+            params: vec![],
+            results: self.func_type.returns.clone(),
+            height: 0,
+            unreachable: false,
+        });
+        while !state.frames.is_empty() {
+            let instr =
+                Instr::read(&mut reader).map_err(|_| (reader.offset(), FindingKind::Invalid))?;
+            let offset = instr.offset;
+            if instr.is_simd() {
+                self.require(offset, Proposal::Simd)?;
+                // SIMD operand types are not modelled
+                return Ok(());
+            }
+            let op = instr.info().ok_or((offset, FindingKind::Invalid))?;
+            if let Some(proposal) = op.proposal {
+                self.require(offset, proposal)?;
+            }
+            if let Sig::Fixed(params, results) = op.sig {
+                state.pop_all(offset, params)?;
+                state.push_all(results);
+                continue;
+            }
+            self.special(&mut state, body, &instr)?;
+        }
+        match reader.is_empty() {
+            true => Ok(()),
+            false => Err((reader.offset(), FindingKind::Invalid)),
+        }
+    }
+
+    /// Checks the instructions whose operand types depend on their immediates or context.
+    fn special(
+        &self,
+        state: &mut State,
+        body: &FunctionBody,
+        instr: &Instr,
+    ) -> Result<(), Failure> {
+        let offset = instr.offset;
+        let invalid = (offset, FindingKind::Invalid);
+        match (instr.code, &instr.imm) {
+            (0x00, _) => state.set_unreachable(),
+            (0x02, Imm::BlockType(ty)) | (0x03, Imm::BlockType(ty)) | (IF, Imm::BlockType(ty)) => {
+                let (params, results) = self.block_type(offset, *ty)?;
+                if instr.code == IF {
+                    state.pop(offset, Some(ValType::I32))?;
+                }
+                state.pop_all(offset, &params)?;
+                state.frames.push(Frame {
+                    opcode: instr.code,
+                    params: params.clone(),
+                    results,
+                    height: state.operands.len(),
+                    unreachable: false,
+                });
+                state.push_all(&params);
+            }
+            (0x05, _) => {
+                let frame = state.frames.last().ok_or(invalid.clone())?;
+                if frame.opcode != IF {
+                    return Err(invalid);
+                }
+                let results = frame.results.clone();
+                state.check_exact(offset, &results)?;
+                let frame = state.frames.last_mut().ok_or(invalid)?;
+                frame.opcode = 0x05;
+                frame.unreachable = false;
+                let (height, params) = (frame.height, frame.params.clone());
+                state.operands.truncate(height);
+                state.push_all(&params);
+            }
+            (0x0B, _) => {
+                let frame = state.frames.last().ok_or(invalid.clone())?;
+                let results = frame.results.clone();
+                // An `if` without `else` passes its parameters through the missing branch
+                if frame.opcode == IF && frame.params != frame.results {
+                    return Err((
+                        offset,
+                        FindingKind::TypeMismatch {
+                            expected: frame.results.clone(),
+                            actual: frame.params.clone(),
+                        },
+                    ));
+                }
+                state.check_exact(offset, &results)?;
+                let frame = state.frames.pop().ok_or(invalid)?;
+                state.operands.truncate(frame.height);
+                state.push_all(&results);
+            }
+            (0x0C, Imm::Index(depth)) => {
+                let types = state.label(offset, *depth)?.label_types().to_vec();
+                state.pop_all(offset, &types)?;
+                state.set_unreachable();
+            }
+            (0x0D, Imm::Index(depth)) => {
+                state.pop(offset, Some(ValType::I32))?;
+                let types = state.label(offset, *depth)?.label_types().to_vec();
+                state.pop_all(offset, &types)?;
+                state.push_all(&types);
+            }
+            (0x0E, Imm::BrTable(labels, default)) => {
+                state.pop(offset, Some(ValType::I32))?;
+                let types = state.label(offset, *default)?.label_types().to_vec();
+                for depth in labels.iter() {
+                    let label = state.label(offset, *depth)?.label_types();
+                    if label.len() != types.len() {
+                        return Err((
+                            offset,
+                            FindingKind::TypeMismatch {
+                                expected: types,
+                                actual: label.to_vec(),
+                            },
+                        ));
+                    }
+                }
+                state.pop_all(offset, &types)?;
+                state.set_unreachable();
+            }
+            (0x0F, _) => {
+                state.pop_all(offset, &self.func_type.returns)?;
+                state.set_unreachable();
+            }
+            (0x10, Imm::Index(idx)) => {
+                let ty = self.info.funcs.get(*idx as usize).ok_or(invalid)?;
+                state.pop_all(offset, &ty.params)?;
+                state.push_all(&ty.returns);
+            }
+            (0x11, Imm::Pair(type_idx, table_idx)) => {
+                if *table_idx != 0 {
+                    self.require(offset, Proposal::ReferenceTypes)?;
+                }
+                self.table(offset, *table_idx)?;
+                let ty = self.info.types.get(*type_idx as usize).ok_or(invalid)?;
+                state.pop(offset, Some(ValType::I32))?;
+                state.pop_all(offset, &ty.params)?;
+                state.push_all(&ty.returns);
+            }
+            (0x1A, _) => {
+                state.pop(offset, None)?;
+            }
+            (0x1B, _) => {
+                state.pop(offset, Some(ValType::I32))?;
+                let first = state.pop(offset, None)?;
+                let second = state.pop(offset, first)?;
+                match first.or(second) {
+                    Some(ty) => state.push(ty),
+                    None => state.operands.push(None),
+                }
+            }
+            (0x1C, Imm::Types(types)) => {
+                let ty = match types.as_slice() {
+                    [ty] => *ty,
+                    _ => return Err(invalid),
+                };
+                state.pop(offset, Some(ValType::I32))?;
+                state.pop_all(offset, &[ty, ty])?;
+                state.push(ty);
+            }
+            (0x20, Imm::Index(idx)) => {
+                let ty = self.local(offset, body, *idx)?;
+                state.push(ty);
+            }
+            (0x21, Imm::Index(idx)) => {
+                let ty = self.local(offset, body, *idx)?;
+                state.pop(offset, Some(ty))?;
+            }
+            (0x22, Imm::Index(idx)) => {
+                let ty = self.local(offset, body, *idx)?;
+                state.pop(offset, Some(ty))?;
+                state.push(ty);
+            }
+            (0x23, Imm::Index(idx)) => {
+                let global = self.info.globals.get(*idx as usize).ok_or(invalid)?;
+                state.push(global.val_type);
+            }
+            (0x24, Imm::Index(idx)) => {
+                let global = self.info.globals.get(*idx as usize).ok_or(invalid)?;
+                state.pop(offset, Some(global.val_type))?;
+            }
+            (0x25, Imm::Index(idx)) => {
+                let ty = self.table(offset, *idx)?;
+                state.pop(offset, Some(ValType::I32))?;
+                state.push(ty);
+            }
+            (0x26, Imm::Index(idx)) => {
+                let ty = self.table(offset, *idx)?;
+                state.pop_all(offset, &[ValType::I32, ty])?;
+            }
+            (0xD0, Imm::RefType(ty)) => state.push(ref_val_type(*ty)),
+            (0xD1, _) => {
+                match state.pop(offset, None)? {
+                    Some(ValType::FuncRef) | Some(ValType::ExternRef) | None => {}
+                    Some(actual) => {
+                        return Err((
+                            offset,
+                            FindingKind::TypeMismatch {
+                                expected: vec![ValType::FuncRef],
+                                actual: vec![actual],
+                            },
+                        ))
+                    }
+                }
+                state.push(ValType::I32);
+            }
+            (0xFC0F, Imm::Index(idx)) => {
+                let ty = self.table(offset, *idx)?;
+                state.pop_all(offset, &[ty, ValType::I32])?;
+                state.push(ValType::I32);
+            }
+            (0xFC11, Imm::Index(idx)) => {
+                let ty = self.table(offset, *idx)?;
+                state.pop_all(offset, &[ValType::I32, ty, ValType::I32])?;
+            }
+            _ => return Err(invalid),
+        }
+        Ok(())
+    }
+}
+
+fn ref_val_type(ty: RefType) -> ValType {
+    match ty {
+        RefType::FuncRef => ValType::FuncRef,
+        RefType::ExternRef => ValType::ExternRef,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a module with one function of type `[] -> [result]` and the given body.
+    fn module(result: u8, code: &[u8]) -> Vec<u8> {
+        let mut wasm = vec![0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00];
+        wasm.extend_from_slice(&[0x01, 0x05, 0x01, 0x60, 0x00, 0x01, result]);
+        wasm.extend_from_slice(&[0x03, 0x02, 0x01, 0x00]);
+        let body_len = code.len() as u8 + 1;
+        wasm.extend_from_slice(&[0x0A, body_len + 2, 0x01, body_len, 0x00]);
+        wasm.extend_from_slice(code);
+        wasm
+    }
+
+    #[test]
+    fn test_typecheck_valid() {
+        let all: Vec<_> = Proposal::all().collect();
+        // i32.const 1, i32.const 2, i32.add
+        let wasm = module(0x7F, &[0x41, 0x01, 0x41, 0x02, 0x6A, 0x0B]);
+        assert_eq!(check_module(&wasm, &all), None);
+        // block (result i32) i32.const 1 br 0 end
+        let wasm = module(0x7F, &[0x02, 0x7F, 0x41, 0x01, 0x0C, 0x00, 0x0B, 0x0B]);
+        assert_eq!(check_module(&wasm, &all), None);
+        // unreachable makes the stack polymorphic
+        let wasm = module(0x7F, &[0x00, 0x6A, 0x0B]);
+        assert_eq!(check_module(&wasm, &all), None);
+    }
+
+    #[test]
+    fn test_typecheck_mismatch() {
+        let all: Vec<_> = Proposal::all().collect();
+        // i32.const 1, f32.const 0, i32.add
+        let wasm = module(
+            0x7F,
+            &[0x41, 0x01, 0x43, 0x00, 0x00, 0x00, 0x00, 0x6A, 0x0B],
+        );
+        let finding = check_module(&wasm, &all).unwrap();
+        assert_eq!(finding.func, 0);
+        assert_eq!(finding.offset, 31);
+        assert_eq!(
+            finding.kind,
+            FindingKind::TypeMismatch {
+                expected: vec![ValType::I32],
+                actual: vec![ValType::F32],
+            }
+        );
+
+        // The function returns i64 but leaves an i32
+        let wasm = module(0x7E, &[0x41, 0x01, 0x0B]);
+        let finding = check_module(&wasm, &all).unwrap();
+        assert_eq!(finding.offset, 26);
+        assert_eq!(
+            finding.kind,
+            FindingKind::TypeMismatch {
+                expected: vec![ValType::I64],
+                actual: vec![ValType::I32],
+            }
+        );
+
+        // local.get of a local that does not exist
+        let wasm = module(0x7F, &[0x20, 0x03, 0x0B]);
+        assert_eq!(
+            check_module(&wasm, &all).unwrap().kind,
+            FindingKind::Invalid
+        );
+    }
+
+    #[test]
+    fn test_typecheck_proposal() {
+        // i32.const 1, i32.extend8_s
+        let wasm = module(0x7F, &[0x41, 0x01, 0xC0, 0x0B]);
+        let finding = check_module(&wasm, &[]).unwrap();
+        assert_eq!(finding.offset, 26);
+        assert_eq!(
+            finding.kind,
+            FindingKind::Proposal(Proposal::SignExtensionOperators)
+        );
+        assert_eq!(
+            check_module(&wasm, &[Proposal::SignExtensionOperators]),
+            None
+        );
+    }
+}
@@ -1,42 +1,304 @@
-use crate::error::WasmEdgeResult;
-use std::ffi::{CStr, CString};
+use crate::error::{WasmEdgeError, WasmEdgeResult};
+use std::borrow::Cow;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::str::FromStr;
+use std::str::Utf8Error;
 use wasmedge_sys::ffi as we_ffi;
 
 pub type WasmEdgeValue = we_ffi::WasmEdge_Value;
-pub type WasmEdgeValType = we_ffi::WasmEdge_ValType;
-pub type WasmEdgeProposal = we_ffi::WasmEdge_Proposal;
-pub type HostRegistration = we_ffi::WasmEdge_HostRegistration;
 pub type WasmEdgeLimit = we_ffi::WasmEdge_Limit;
-pub type WasmEdgeRefType = we_ffi::WasmEdge_RefType;
-pub type WasmEdgeMutability = we_ffi::WasmEdge_Mutability;
 pub type CompilerOptimizationLevel = we_ffi::WasmEdge_CompilerOptimizationLevel;
 pub type HostFunc = we_ffi::WasmEdge_HostFunc_t;
 pub type WrapFunc = we_ffi::WasmEdge_WrapFunc_t;
 
+/// Defines a crate-owned enum mirroring a C API enum.
+///
+/// Each variant maps one-to-one onto a variant of the sys enum, and carries the text used by
+/// `Display` and accepted by `FromStr`.
+macro_rules! ffi_enum {
+    (
+        $(#[$meta:meta])*
+        $name:ident($raw:ident, $kind:expr) {
+            $($variant:ident => $raw_variant:ident, $text:literal;)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($variant,)*
+        }
+        impl $name {
+            const ALL: &'static [$name] = &[$($name::$variant,)*];
+
+            /// Returns an iterator over all variants, in declaration order.
+            pub fn all() -> impl Iterator<Item = $name> {
+                Self::ALL.iter().copied()
+            }
+        }
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                let text = match self {
+                    $($name::$variant => $text,)*
+                };
+                f.write_str(text)
+            }
+        }
+        impl FromStr for $name {
+            type Err = WasmEdgeError;
+
+            fn from_str(s: &str) -> WasmEdgeResult<Self> {
+                match s {
+                    $($text => Ok($name::$variant),)*
+                    _ => Err(WasmEdgeError::from(format!("Unknown {}: '{}'", $kind, s))),
+                }
+            }
+        }
+        impl From<$name> for we_ffi::$raw {
+            fn from(v: $name) -> Self {
+                match v {
+                    $($name::$variant => we_ffi::$raw::$raw_variant,)*
+                }
+            }
+        }
+        impl From<we_ffi::$raw> for $name {
+            fn from(raw: we_ffi::$raw) -> Self {
+                match raw {
+                    $(we_ffi::$raw::$raw_variant => $name::$variant,)*
+                }
+            }
+        }
+    };
+}
+
+ffi_enum! {
+    /// The type of a WebAssembly value.
+    ValType(WasmEdge_ValType, "value type") {
+        I32 => WasmEdge_ValType_I32, "i32";
+        I64 => WasmEdge_ValType_I64, "i64";
+        F32 => WasmEdge_ValType_F32, "f32";
+        F64 => WasmEdge_ValType_F64, "f64";
+        V128 => WasmEdge_ValType_V128, "v128";
+        FuncRef => WasmEdge_ValType_FuncRef, "funcref";
+        ExternRef => WasmEdge_ValType_ExternRef, "externref";
+    }
+}
+
+ffi_enum! {
+    /// The type of a WebAssembly reference.
+    RefType(WasmEdge_RefType, "reference type") {
+        FuncRef => WasmEdge_RefType_FuncRef, "funcref";
+        ExternRef => WasmEdge_RefType_ExternRef, "externref";
+    }
+}
+
+ffi_enum! {
+    /// The mutability of a global.
+    Mutability(WasmEdge_Mutability, "mutability") {
+        Const => WasmEdge_Mutability_Const, "const";
+        Var => WasmEdge_Mutability_Var, "var";
+    }
+}
+
+ffi_enum! {
+    /// A WebAssembly proposal that can be turned on in a `ConfigureContext`.
+    Proposal(WasmEdge_Proposal, "proposal") {
+        ImportExportMutGlobals => WasmEdge_Proposal_ImportExportMutGlobals, "mutable-globals";
+        NonTrapFloatToIntConversions => WasmEdge_Proposal_NonTrapFloatToIntConversions, "saturating-float-to-int";
+        SignExtensionOperators => WasmEdge_Proposal_SignExtensionOperators, "sign-extension";
+        MultiValue => WasmEdge_Proposal_MultiValue, "multi-value";
+        BulkMemoryOperations => WasmEdge_Proposal_BulkMemoryOperations, "bulk-memory";
+        ReferenceTypes => WasmEdge_Proposal_ReferenceTypes, "reference-types";
+        Simd => WasmEdge_Proposal_SIMD, "simd";
+        TailCall => WasmEdge_Proposal_TailCall, "tail-call";
+        Annotations => WasmEdge_Proposal_Annotations, "annotations";
+        Memory64 => WasmEdge_Proposal_Memory64, "memory64";
+        Threads => WasmEdge_Proposal_Threads, "threads";
+        ExceptionHandling => WasmEdge_Proposal_ExceptionHandling, "exceptions";
+        FunctionReferences => WasmEdge_Proposal_FunctionReferences, "function-references";
+    }
+}
+
+ffi_enum! {
+    /// A built-in host module that a `VMContext` can register.
+    HostRegistration(WasmEdge_HostRegistration, "host registration") {
+        Wasi => WasmEdge_HostRegistration_Wasi, "wasi";
+        WasmEdgeProcess => WasmEdge_HostRegistration_WasmEdge_Process, "wasmedge_process";
+    }
+}
+
+/// A string borrowed from WasmEdge, e.g. a name returned by one of the list APIs.
+///
+/// The C string carries an explicit length and is not guaranteed to be NUL-terminated, so it is
+/// always decoded as a byte slice.
+#[derive(Clone, Copy)]
+pub struct WasmEdgeStr<'a> {
+    raw: we_ffi::WasmEdge_String,
+    _marker: PhantomData<&'a [u8]>,
+}
+impl<'a> WasmEdgeStr<'a> {
+    /// Wraps a string owned by WasmEdge.
+    ///
+    /// # Safety
+    ///
+    /// `raw.Buf` must point to `raw.Length` readable bytes that stay valid and unchanged for `'a`.
+    pub(crate) unsafe fn from_raw(raw: we_ffi::WasmEdge_String) -> Self {
+        WasmEdgeStr {
+            raw,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn as_bytes(&self) -> &'a [u8] {
+        match self.raw.Buf.is_null() || self.raw.Length == 0 {
+            true => &[],
+            false => unsafe {
+                std::slice::from_raw_parts(self.raw.Buf as *const u8, self.raw.Length as usize)
+            },
+        }
+    }
+
+    pub fn to_str(&self) -> Result<&'a str, Utf8Error> {
+        std::str::from_utf8(self.as_bytes())
+    }
+
+    pub fn to_string_lossy(&self) -> Cow<'a, str> {
+        String::from_utf8_lossy(self.as_bytes())
+    }
+
+    pub fn len(&self) -> usize {
+        self.as_bytes().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.as_bytes().is_empty()
+    }
+}
+impl fmt::Display for WasmEdgeStr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.to_string_lossy().fmt(f)
+    }
+}
+impl fmt::Debug for WasmEdgeStr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.to_string_lossy().fmt(f)
+    }
+}
+impl PartialEq for WasmEdgeStr<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+impl Eq for WasmEdgeStr<'_> {}
+impl PartialEq<str> for WasmEdgeStr<'_> {
+    fn eq(&self, other: &str) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+impl PartialEq<&str> for WasmEdgeStr<'_> {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+impl Hash for WasmEdgeStr<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_bytes().hash(state)
+    }
+}
+
+/// A string owned by the crate and passed to WasmEdge, e.g. a module or function name.
+///
+/// The string is created from a byte buffer with an explicit length, so it may contain any bytes,
+/// including interior NULs. Creating one fails for a buffer longer than 4 GiB.
 pub struct WasmEdgeString {
     pub(crate) raw: we_ffi::WasmEdge_String,
 }
 impl WasmEdgeString {
-    pub fn from_str(s: &str) -> WasmEdgeResult<WasmEdgeString> {
-        let cstring = CString::new(s)?;
-        Ok(WasmEdgeString {
-            raw: unsafe { we_ffi::WasmEdge_StringCreateByCString(cstring.as_ptr()) },
-        })
+    /// Creates a string from a byte buffer, failing if it is longer than WasmEdge can address.
+    pub fn from_bytes(bytes: &[u8]) -> WasmEdgeResult<WasmEdgeString> {
+        let len = crate::utils::buffer_len(bytes.len())?;
+        Ok(WasmEdgeString::create(bytes, len))
     }
 
-    pub fn from_buffer(buf: &[i8]) -> WasmEdgeString {
+    /// Copies the first `len` bytes of `bytes`, where `len` is already known to fit in `u32`.
+    fn create(bytes: &[u8], len: u32) -> WasmEdgeString {
         WasmEdgeString {
-            raw: unsafe { we_ffi::WasmEdge_StringCreateByBuffer(buf.as_ptr(), buf.len() as u32) },
+            raw: unsafe {
+                we_ffi::WasmEdge_StringCreateByBuffer(
+                    bytes.as_ptr() as *const std::os::raw::c_char,
+                    len,
+                )
+            },
         }
     }
 
-    pub fn to_string_lossy(&self) -> std::borrow::Cow<'_, str> {
-        let cstr = unsafe { CStr::from_ptr(self.raw.Buf) };
-        cstr.to_string_lossy()
+    /// Borrows the string.
+    pub fn as_wasmedge_str(&self) -> WasmEdgeStr<'_> {
+        unsafe { WasmEdgeStr::from_raw(self.raw) }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        self.as_wasmedge_str().as_bytes()
     }
 
-    pub fn into_raw(&self) -> we_ffi::WasmEdge_String {
-        self.raw
+    pub fn to_str(&self) -> Result<&str, Utf8Error> {
+        self.as_wasmedge_str().to_str()
+    }
+
+    pub fn to_string_lossy(&self) -> Cow<'_, str> {
+        self.as_wasmedge_str().to_string_lossy()
+    }
+
+    /// Releases ownership of the underlying C string.
+    ///
+    /// The caller becomes responsible for freeing it with `WasmEdge_StringDelete`.
+    pub fn into_raw(self) -> we_ffi::WasmEdge_String {
+        let raw = self.raw;
+        std::mem::forget(self);
+        raw
+    }
+}
+impl From<WasmEdgeStr<'_>> for WasmEdgeString {
+    fn from(s: WasmEdgeStr<'_>) -> Self {
+        // The length of a WasmEdge string always fits
+        WasmEdgeString::create(s.as_bytes(), s.raw.Length)
+    }
+}
+impl Clone for WasmEdgeString {
+    fn clone(&self) -> Self {
+        WasmEdgeString::create(self.as_bytes(), self.raw.Length)
+    }
+}
+impl fmt::Display for WasmEdgeString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.as_wasmedge_str(), f)
+    }
+}
+impl fmt::Debug for WasmEdgeString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.as_wasmedge_str(), f)
+    }
+}
+impl PartialEq for WasmEdgeString {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+impl Eq for WasmEdgeString {}
+impl PartialEq<str> for WasmEdgeString {
+    fn eq(&self, other: &str) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+impl PartialEq<&str> for WasmEdgeString {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+impl Hash for WasmEdgeString {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_bytes().hash(state)
     }
 }
 impl Drop for WasmEdgeString {
@@ -44,3 +306,83 @@ impl Drop for WasmEdgeString {
         unsafe { we_ffi::WasmEdge_StringDelete(self.raw) }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_types_val_type() {
+        assert_eq!(ValType::all().count(), 7);
+        for ty in ValType::all() {
+            assert_eq!(ty.to_string().parse::<ValType>().unwrap(), ty);
+            assert_eq!(ValType::from(we_ffi::WasmEdge_ValType::from(ty)), ty);
+        }
+        assert_eq!(ValType::V128.to_string(), "v128");
+        assert_eq!(
+            we_ffi::WasmEdge_ValType::from(ValType::ExternRef),
+            we_ffi::WasmEdge_ValType::WasmEdge_ValType_ExternRef
+        );
+        assert!("i16".parse::<ValType>().is_err());
+    }
+
+    #[test]
+    fn test_types_enums() {
+        for ty in RefType::all() {
+            assert_eq!(ty.to_string().parse::<RefType>().unwrap(), ty);
+            assert_eq!(RefType::from(we_ffi::WasmEdge_RefType::from(ty)), ty);
+        }
+        for m in Mutability::all() {
+            assert_eq!(m.to_string().parse::<Mutability>().unwrap(), m);
+            assert_eq!(Mutability::from(we_ffi::WasmEdge_Mutability::from(m)), m);
+        }
+        for prop in Proposal::all() {
+            assert_eq!(prop.to_string().parse::<Proposal>().unwrap(), prop);
+            assert_eq!(Proposal::from(we_ffi::WasmEdge_Proposal::from(prop)), prop);
+        }
+        for reg in HostRegistration::all() {
+            assert_eq!(reg.to_string().parse::<HostRegistration>().unwrap(), reg);
+            let raw = we_ffi::WasmEdge_HostRegistration::from(reg);
+            assert_eq!(HostRegistration::from(raw), reg);
+        }
+        assert_eq!(Proposal::Simd.to_string(), "simd");
+        assert_eq!(
+            "bulk-memory".parse::<Proposal>().unwrap(),
+            Proposal::BulkMemoryOperations
+        );
+        assert!("SIMD".parse::<Proposal>().is_err());
+    }
+
+    #[test]
+    fn test_types_string() {
+        let s = WasmEdgeString::from_bytes(b"func-add").unwrap();
+        assert_eq!(s.to_str().unwrap(), "func-add");
+        assert_eq!(s, "func-add");
+        assert_eq!(s.to_string(), "func-add");
+        assert_eq!(s.clone(), s);
+
+        // Interior NULs and invalid UTF-8 are kept as-is
+        let s = WasmEdgeString::from_bytes(b"a\0b\xff").unwrap();
+        assert_eq!(s.as_bytes(), b"a\0b\xff");
+        assert!(s.to_str().is_err());
+        assert_eq!(s.to_string_lossy(), "a\0b\u{fffd}");
+
+        let s = WasmEdgeString::from_bytes(b"").unwrap();
+        assert!(s.as_wasmedge_str().is_empty());
+        assert_eq!(s, "");
+
+        let mut set = std::collections::HashSet::new();
+        set.insert(WasmEdgeString::from_bytes(b"name").unwrap());
+        assert!(set.contains(&WasmEdgeString::from_bytes(b"name").unwrap()));
+
+        // A borrowed string is decoded by length, not up to a NUL terminator
+        let owned = WasmEdgeString::from_bytes(b"module-name").unwrap();
+        let raw = we_ffi::WasmEdge_String {
+            Length: 6,
+            Buf: owned.raw.Buf,
+        };
+        let borrowed = unsafe { WasmEdgeStr::from_raw(raw) };
+        assert_eq!(borrowed, "module");
+        assert_eq!(WasmEdgeString::from(borrowed), "module");
+    }
+}
@@ -1,6 +1,11 @@
 use crate::error::{WasmEdgeError, WasmEdgeResult};
-use std::ffi::{CStr, CString};
-use std::path::Path;
+use std::convert::TryFrom;
+use std::ffi::CString;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
 use wasmedge_sys::ffi as we_ffi;
 
 #[cfg(unix)]
@@ -22,25 +27,173 @@ pub fn path_to_cstring(path: &Path) -> WasmEdgeResult<CString> {
 }
 
 pub fn check(result: we_ffi::WasmEdge_Result) -> WasmEdgeResult<()> {
-    unsafe {
-        if !we_ffi::WasmEdge_ResultOK(result) {
-            let code = we_ffi::WasmEdge_ResultGetCode(result) as usize;
-            let message = CStr::from_ptr(we_ffi::WasmEdge_ResultGetMessage(result))
-                .to_string_lossy()
-                .into_owned();
-            return Err(WasmEdgeError { code, message });
+    match unsafe { we_ffi::WasmEdge_ResultOK(result) } {
+        true => Ok(()),
+        false => Err(WasmEdgeError::from(result)),
+    }
+}
+
+/// Converts the length of a module or buffer passed to WasmEdge, which takes `u32` lengths.
+pub(crate) fn buffer_len(len: usize) -> WasmEdgeResult<u32> {
+    match u32::try_from(len) {
+        Ok(len) => Ok(len),
+        Err(_) => Err(WasmEdgeError::from(format!(
+            "The buffer of {} bytes exceeds the 4 GiB limit of WasmEdge",
+            len
+        ))),
+    }
+}
+
+/// A private directory that is removed with its contents when dropped.
+///
+/// WasmEdge only compiles from and to paths and only loads native artifacts from paths, so
+/// buffers are passed through files in such a directory. The directory is only accessible to the
+/// current user and the crate creates every file in it, so WasmEdge never reads or writes a path
+/// another process could have prepared.
+pub(crate) struct TempDir {
+    path: PathBuf,
+}
+impl TempDir {
+    /// Creates a directory in the system temporary directory.
+    pub(crate) fn new() -> WasmEdgeResult<Self> {
+        TempDir::new_in(&std::env::temp_dir())
+    }
+
+    /// Creates a directory in `parent`, e.g. to rename a file into place there.
+    pub(crate) fn new_in(parent: &Path) -> WasmEdgeResult<Self> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let mut builder = fs::DirBuilder::new();
+        builder.recursive(false);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::DirBuilderExt;
+            builder.mode(0o700);
+        }
+        let mut attempts = 0;
+        loop {
+            let nanos = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.subsec_nanos());
+            let path = parent.join(format!(
+                "wasmedge-{}-{}-{:08x}",
+                std::process::id(),
+                COUNTER.fetch_add(1, Ordering::Relaxed),
+                nanos
+            ));
+            // Creating the directory fails if the name is taken, so an existing directory is
+            // never reused
+            match builder.create(&path) {
+                Ok(()) => return Ok(TempDir { path }),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists && attempts < 16 => {
+                    attempts += 1
+                }
+                Err(e) => {
+                    return Err(WasmEdgeError::from(format!(
+                        "Failed to create a temporary directory in '{}': {}",
+                        parent.display(),
+                        e
+                    )))
+                }
+            }
+        }
+    }
+
+    /// Creates the file `name` in the directory with `contents`, readable only by the current
+    /// user, and returns its path.
+    pub(crate) fn create_file(&self, name: &str, contents: &[u8]) -> WasmEdgeResult<PathBuf> {
+        let path = self.path.join(name);
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        match options
+            .open(&path)
+            .and_then(|mut file| file.write_all(contents))
+        {
+            Ok(()) => Ok(path),
+            Err(e) => Err(WasmEdgeError::from(format!(
+                "Failed to write temporary file '{}': {}",
+                path.display(),
+                e
+            ))),
+        }
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+}
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// An array of C strings that owns its strings for as long as the array pointer is in use.
+pub(crate) struct CStringArray {
+    _strings: Vec<CString>,
+    ptrs: Vec<*const std::os::raw::c_char>,
+}
+impl CStringArray {
+    pub(crate) fn new<S: AsRef<str>>(strings: &[S]) -> WasmEdgeResult<Self> {
+        let mut owned = Vec::with_capacity(strings.len());
+        for s in strings {
+            match CString::new(s.as_ref()) {
+                Ok(s) => owned.push(s),
+                Err(_) => {
+                    return Err(WasmEdgeError::from(format!(
+                        "The string '{}' contains an interior NUL byte",
+                        s.as_ref().escape_default()
+                    )))
+                }
+            }
+        }
+        let ptrs = owned.iter().map(|s| s.as_ptr()).collect();
+        Ok(CStringArray {
+            _strings: owned,
+            ptrs,
+        })
+    }
+
+    pub(crate) fn as_ptr(&self) -> *const *const std::os::raw::c_char {
+        match self.ptrs.is_empty() {
+            true => std::ptr::null(),
+            false => self.ptrs.as_ptr(),
         }
     }
-    Ok(())
+
+    pub(crate) fn len(&self) -> u32 {
+        self.ptrs.len() as u32
+    }
 }
 
-pub fn string_to_c_array(args: &[&str]) -> *const *const std::os::raw::c_char {
-    let mut v = vec![];
-    for &arg in args {
-        let s = CString::new(arg).unwrap();
-        v.push(s.as_ptr());
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_utils_temp_dir() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.create_file("module.wasm", b"\0asm").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"\0asm");
+        // An existing file is never reused
+        assert!(dir.create_file("module.wasm", b"").is_err());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+            assert_eq!(mode(dir.path()), 0o700);
+            assert_eq!(mode(&path), 0o600);
+        }
+
+        let other = TempDir::new().unwrap();
+        assert_ne!(other.path(), dir.path());
+        let dir_path = dir.path().to_path_buf();
+        drop(dir);
+        assert!(!dir_path.exists());
     }
-    let p = v.as_ptr();
-    std::mem::forget(v);
-    p
 }
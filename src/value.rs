@@ -1,57 +1,344 @@
-use crate::types::WasmEdgeValue;
+use crate::error::{WasmEdgeError, WasmEdgeResult};
+use crate::externref::ExternHandle;
+use crate::types::{RefType, ValType, WasmEdgeValue};
+use std::convert::TryFrom;
 use wasmedge_sys::ffi as we_ffi;
 
-#[allow(non_snake_case)]
-pub fn WasmEdgeValueGenI32(val: i32) -> WasmEdgeValue {
-    unsafe { we_ffi::WasmEdge_ValueGenI32(val) }
+/// A WebAssembly value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Val {
+    I32(i32),
+    I64(i64),
+    F32(f32),
+    F64(f64),
+    V128(V128),
+    /// A function reference holding the function index, or `None` for `ref.null func`.
+    FuncRef(Option<usize>),
+    /// An external reference to a host value, or `None` for `ref.null extern`.
+    ///
+    /// Use `externref::ExternRef` to create one and to get the host value back.
+    ExternRef(Option<ExternHandle>),
 }
+impl Val {
+    /// Returns the value type of this value.
+    pub fn ty(&self) -> ValType {
+        match self {
+            Val::I32(_) => ValType::I32,
+            Val::I64(_) => ValType::I64,
+            Val::F32(_) => ValType::F32,
+            Val::F64(_) => ValType::F64,
+            Val::V128(_) => ValType::V128,
+            Val::FuncRef(_) => ValType::FuncRef,
+            Val::ExternRef(_) => ValType::ExternRef,
+        }
+    }
 
-#[allow(non_snake_case)]
-pub fn WasmEdgeValueGenI64(val: i64) -> WasmEdgeValue {
-    unsafe { we_ffi::WasmEdge_ValueGenI64(val) }
-}
+    pub fn i32(&self) -> Option<i32> {
+        match self {
+            Val::I32(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn i64(&self) -> Option<i64> {
+        match self {
+            Val::I64(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn f32(&self) -> Option<f32> {
+        match self {
+            Val::F32(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn f64(&self) -> Option<f64> {
+        match self {
+            Val::F64(v) => Some(*v),
+            _ => None,
+        }
+    }
 
-#[allow(non_snake_case)]
-pub fn WasmEdgeValueGenExternRef(ptr: *mut ::std::os::raw::c_void) -> WasmEdgeValue {
-    unsafe { we_ffi::WasmEdge_ValueGenExternRef(ptr) }
+    pub fn v128(&self) -> Option<V128> {
+        match self {
+            Val::V128(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if this is a null `funcref` or `externref`.
+    pub fn is_null_ref(&self) -> bool {
+        matches!(self, Val::FuncRef(None) | Val::ExternRef(None))
+    }
+}
+impl From<WasmEdgeValue> for Val {
+    fn from(raw: WasmEdgeValue) -> Self {
+        unsafe {
+            match ValType::from(raw.Type) {
+                ValType::I32 => Val::I32(we_ffi::WasmEdge_ValueGetI32(raw)),
+                ValType::I64 => Val::I64(we_ffi::WasmEdge_ValueGetI64(raw)),
+                ValType::F32 => Val::F32(we_ffi::WasmEdge_ValueGetF32(raw)),
+                ValType::F64 => Val::F64(we_ffi::WasmEdge_ValueGetF64(raw)),
+                ValType::V128 => Val::V128(V128(we_ffi::WasmEdge_ValueGetV128(raw) as u128)),
+                ValType::FuncRef => match we_ffi::WasmEdge_ValueIsNullRef(raw) {
+                    true => Val::FuncRef(None),
+                    false => Val::FuncRef(Some(we_ffi::WasmEdge_ValueGetFuncIdx(raw) as usize)),
+                },
+                ValType::ExternRef => match we_ffi::WasmEdge_ValueIsNullRef(raw) {
+                    true => Val::ExternRef(None),
+                    false => Val::ExternRef(ExternHandle::from_raw(
+                        we_ffi::WasmEdge_ValueGetExternRef(raw),
+                    )),
+                },
+            }
+        }
+    }
+}
+impl From<Val> for WasmEdgeValue {
+    fn from(val: Val) -> Self {
+        unsafe {
+            match val {
+                Val::I32(v) => we_ffi::WasmEdge_ValueGenI32(v),
+                Val::I64(v) => we_ffi::WasmEdge_ValueGenI64(v),
+                Val::F32(v) => we_ffi::WasmEdge_ValueGenF32(v),
+                Val::F64(v) => we_ffi::WasmEdge_ValueGenF64(v),
+                Val::V128(v) => we_ffi::WasmEdge_ValueGenV128(v.0 as i128),
+                Val::FuncRef(Some(idx)) => we_ffi::WasmEdge_ValueGenFuncRef(idx as u32),
+                Val::FuncRef(None) => we_ffi::WasmEdge_ValueGenNullRef(RefType::FuncRef.into()),
+                Val::ExternRef(Some(handle)) => we_ffi::WasmEdge_ValueGenExternRef(handle.as_raw()),
+                Val::ExternRef(None) => we_ffi::WasmEdge_ValueGenNullRef(RefType::ExternRef.into()),
+            }
+        }
+    }
 }
+impl From<i32> for Val {
+    fn from(v: i32) -> Self {
+        Val::I32(v)
+    }
+}
+impl From<i64> for Val {
+    fn from(v: i64) -> Self {
+        Val::I64(v)
+    }
+}
+impl From<f32> for Val {
+    fn from(v: f32) -> Self {
+        Val::F32(v)
+    }
+}
+impl From<f64> for Val {
+    fn from(v: f64) -> Self {
+        Val::F64(v)
+    }
+}
+impl From<V128> for Val {
+    fn from(v: V128) -> Self {
+        Val::V128(v)
+    }
+}
+impl From<u128> for Val {
+    fn from(v: u128) -> Self {
+        Val::V128(V128(v))
+    }
+}
+
+macro_rules! impl_try_from_val {
+    ($ty:ty, $variant:ident, $name:expr) => {
+        impl TryFrom<Val> for $ty {
+            type Error = WasmEdgeError;
 
-#[allow(non_snake_case)]
-pub fn WasmEdgeValueGenFuncRef(idx: usize) -> WasmEdgeValue {
-    unsafe { we_ffi::WasmEdge_ValueGenFuncRef(idx as u32) }
+            fn try_from(val: Val) -> WasmEdgeResult<$ty> {
+                match val {
+                    Val::$variant(v) => Ok(v),
+                    _ => Err(WasmEdgeError::from(format!(
+                        "Mismatched value type. Expected: {}, Got: {}",
+                        $name,
+                        val.ty()
+                    ))),
+                }
+            }
+        }
+    };
 }
+impl_try_from_val!(i32, I32, "i32");
+impl_try_from_val!(i64, I64, "i64");
+impl_try_from_val!(f32, F32, "f32");
+impl_try_from_val!(f64, F64, "f64");
+impl_try_from_val!(V128, V128, "v128");
+impl TryFrom<Val> for u128 {
+    type Error = WasmEdgeError;
 
-#[allow(non_snake_case)]
-pub fn WasmEdgeValueGenF32(val: f32) -> WasmEdgeValue {
-    unsafe { we_ffi::WasmEdge_ValueGenF32(val) }
+    fn try_from(val: Val) -> WasmEdgeResult<u128> {
+        V128::try_from(val).map(|v| v.as_u128())
+    }
 }
 
-#[allow(non_snake_case)]
-pub fn WasmEdgeValueGenF64(val: f64) -> WasmEdgeValue {
-    unsafe { we_ffi::WasmEdge_ValueGenF64(val) }
+macro_rules! impl_v128_lanes {
+    ($($from:ident, $to:ident, $ty:ty, $lanes:expr;)*) => {
+        impl V128 {
+            $(
+                pub fn $from(lanes: [$ty; $lanes]) -> Self {
+                    let mut bytes = [0u8; 16];
+                    for (chunk, lane) in bytes.chunks_exact_mut(16 / $lanes).zip(lanes.iter()) {
+                        chunk.copy_from_slice(&lane.to_le_bytes());
+                    }
+                    V128(u128::from_le_bytes(bytes))
+                }
+
+                pub fn $to(&self) -> [$ty; $lanes] {
+                    let bytes = self.0.to_le_bytes();
+                    let mut lanes = [<$ty>::default(); $lanes];
+                    for (lane, chunk) in lanes.iter_mut().zip(bytes.chunks_exact(16 / $lanes)) {
+                        let mut buf = [0u8; 16 / $lanes];
+                        buf.copy_from_slice(chunk);
+                        *lane = <$ty>::from_le_bytes(buf);
+                    }
+                    lanes
+                }
+            )*
+        }
+
+        $(
+            impl From<[$ty; $lanes]> for V128 {
+                fn from(lanes: [$ty; $lanes]) -> Self {
+                    V128::$from(lanes)
+                }
+            }
+            impl From<V128> for [$ty; $lanes] {
+                fn from(v: V128) -> Self {
+                    v.$to()
+                }
+            }
+            impl From<[$ty; $lanes]> for Val {
+                fn from(lanes: [$ty; $lanes]) -> Self {
+                    Val::V128(V128::$from(lanes))
+                }
+            }
+        )*
+    };
 }
 
-#[allow(non_snake_case)]
-pub fn WasmEdgeValueGetI32(val: WasmEdgeValue) -> i32 {
-    unsafe { we_ffi::WasmEdge_ValueGetI32(val) }
+/// A 128-bit SIMD vector.
+///
+/// Lanes are laid out in little-endian order, so lane 0 occupies the lowest bits, matching the
+/// `v128` lane numbering of the SIMD proposal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct V128(u128);
+impl V128 {
+    pub fn from_u128(v: u128) -> Self {
+        V128(v)
+    }
+
+    pub fn as_u128(&self) -> u128 {
+        self.0
+    }
+}
+impl_v128_lanes! {
+    from_i8x16, to_i8x16, i8, 16;
+    from_i16x8, to_i16x8, i16, 8;
+    from_i32x4, to_i32x4, i32, 4;
+    from_i64x2, to_i64x2, i64, 2;
+    from_f32x4, to_f32x4, f32, 4;
+    from_f64x2, to_f64x2, f64, 2;
+}
+impl From<u128> for V128 {
+    fn from(v: u128) -> Self {
+        V128(v)
+    }
+}
+impl From<V128> for u128 {
+    fn from(v: V128) -> Self {
+        v.0
+    }
 }
 
-#[allow(non_snake_case)]
-pub fn WasmEdgeValueGetI64(val: WasmEdgeValue) -> i64 {
-    unsafe { we_ffi::WasmEdge_ValueGetI64(val) }
+/// Converts the given values into the raw values expected by the C API.
+pub(crate) fn to_raw_vec(vals: &[Val]) -> Vec<WasmEdgeValue> {
+    vals.iter().map(|v| WasmEdgeValue::from(*v)).collect()
 }
 
-#[allow(non_snake_case)]
-pub fn WasmEdgeValueGetExternRef(val: WasmEdgeValue) -> *mut ::std::os::raw::c_void {
-    unsafe { we_ffi::WasmEdge_ValueGetExternRef(val) }
+/// Creates a buffer of `len` raw values for the C API to write returns into.
+pub(crate) fn raw_buffer(len: usize) -> Vec<WasmEdgeValue> {
+    vec![WasmEdgeValue::from(Val::I32(0)); len]
 }
 
-#[allow(non_snake_case)]
-pub fn WasmEdgeValueGetF32(val: WasmEdgeValue) -> f32 {
-    unsafe { we_ffi::WasmEdge_ValueGetF32(val) }
+/// Converts the raw values written by the C API back into values.
+pub(crate) fn from_raw_slice(raw: &[WasmEdgeValue]) -> Vec<Val> {
+    raw.iter().map(|v| Val::from(*v)).collect()
 }
 
-#[allow(non_snake_case)]
-pub fn WasmEdgeValueGetF64(val: WasmEdgeValue) -> f64 {
-    unsafe { we_ffi::WasmEdge_ValueGetF64(val) }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::store::StoreContext;
+    use crate::externref::ExternRef;
+
+    #[test]
+    fn test_value_roundtrip() {
+        let vals = [
+            Val::I32(-1234),
+            Val::I64(55555555555),
+            Val::F32(12.345),
+            Val::F64(-0.5),
+            Val::V128(V128::from_u128(0x0102_0304_0506_0708_090a_0b0c_0d0e_0f10)),
+            Val::FuncRef(Some(3)),
+            Val::FuncRef(None),
+            Val::ExternRef(None),
+        ];
+        for val in vals.iter() {
+            let raw = WasmEdgeValue::from(*val);
+            assert_eq!(ValType::from(raw.Type), val.ty());
+            assert_eq!(Val::from(raw), *val);
+        }
+
+        let store = StoreContext::create();
+        let r = ExternRef::new(&store, 1234);
+        let val = Val::from(WasmEdgeValue::from(Val::from(&r)));
+        assert_eq!(val, Val::ExternRef(Some(r.handle())));
+        assert!(!val.is_null_ref());
+        assert!(Val::FuncRef(None).is_null_ref());
+    }
+
+    #[test]
+    fn test_value_conversions() {
+        assert_eq!(Val::from(1i32), Val::I32(1));
+        assert_eq!(Val::from(2i64), Val::I64(2));
+        assert_eq!(Val::from(1.5f32), Val::F32(1.5));
+        assert_eq!(Val::from(2.5f64), Val::F64(2.5));
+
+        assert_eq!(i32::try_from(Val::I32(7)).unwrap(), 7);
+        assert!(i64::try_from(Val::I32(7)).is_err());
+        assert!(f32::try_from(Val::F64(1.0)).is_err());
+        assert_eq!(Val::F64(1.0).f64(), Some(1.0));
+        assert_eq!(Val::F64(1.0).i32(), None);
+    }
+
+    #[test]
+    fn test_value_v128_lanes() {
+        let v = V128::from_u128(0x0f0e_0d0c_0b0a_0908_0706_0504_0302_0100);
+        let bytes = v.to_i8x16();
+        for (i, b) in bytes.iter().enumerate() {
+            assert_eq!(*b, i as i8);
+        }
+        assert_eq!(V128::from_i8x16(bytes), v);
+        assert_eq!(v.to_i16x8()[0], 0x0100);
+        assert_eq!(v.to_i32x4()[3], 0x0f0e_0d0c);
+        assert_eq!(v.to_i64x2()[1], 0x0f0e_0d0c_0b0a_0908);
+
+        let lanes = [1.5f32, -2.0, 0.0, f32::MAX];
+        assert_eq!(V128::from_f32x4(lanes).to_f32x4(), lanes);
+        let lanes = [std::f64::consts::PI, -1.0];
+        assert_eq!(V128::from(lanes).to_f64x2(), lanes);
+        let lanes = [-1i16, 2, -3, 4, -5, 6, -7, 8];
+        assert_eq!(<[i16; 8]>::from(V128::from(lanes)), lanes);
+
+        let val = Val::from([1i32, 2, 3, 4]);
+        assert_eq!(val.ty(), ValType::V128);
+        let raw = WasmEdgeValue::from(val);
+        let v = V128::try_from(Val::from(raw)).unwrap();
+        assert_eq!(v.to_i32x4(), [1, 2, 3, 4]);
+        assert!(V128::try_from(Val::I32(1)).is_err());
+    }
 }
@@ -0,0 +1,220 @@
+use crate::error::{WasmEdgeError, WasmEdgeResult};
+use crate::utils::CStringArray;
+use std::path::{Path, PathBuf};
+
+/// A host directory made visible to the guest under a guest path.
+///
+/// The guest gets full rights on the directory: WasmEdge 0.8 has no read-only preopens.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreopenDir {
+    pub host: PathBuf,
+    pub guest: String,
+}
+impl PreopenDir {
+    /// Renders the directory as a `guest:host` mapping.
+    fn mapping(&self) -> WasmEdgeResult<String> {
+        let host = match self.host.to_str() {
+            Some(host) => host,
+            None => {
+                return Err(WasmEdgeError::from(format!(
+                    "Couldn't convert path '{}' to UTF-8",
+                    self.host.display()
+                )))
+            }
+        };
+        if self.guest.contains(':') {
+            return Err(WasmEdgeError::from(format!(
+                "The guest path '{}' must not contain ':'",
+                self.guest
+            )));
+        }
+        Ok(format!("{}:{}", self.guest, host))
+    }
+}
+
+/// The arguments, environment variables and preopened directories of a WASI module.
+///
+/// WasmEdge 0.8 preopens every directory read-write and cannot restrict one to reading, so
+/// expose only directories the guest may modify.
+///
+/// ```ignore
+/// let mut config = WasiConfig::new();
+/// config
+///     .arg("app.wasm")
+///     .env("LOG", "debug")
+///     .preopen_dir("/srv/data", "/data");
+/// let wasi = ImportObjectContext::create_wasi(&config)?;
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WasiConfig {
+    args: Vec<String>,
+    envs: Vec<(String, String)>,
+    inherit_env: bool,
+    preopens: Vec<PreopenDir>,
+}
+impl WasiConfig {
+    pub fn new() -> Self {
+        WasiConfig::default()
+    }
+
+    pub fn arg(&mut self, arg: impl Into<String>) -> &mut Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    pub fn args<I, S>(&mut self, args: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// Sets an environment variable, overriding an inherited one of the same name.
+    pub fn env(&mut self, key: impl Into<String>, value: impl Into<String>) -> &mut Self {
+        self.envs.push((key.into(), value.into()));
+        self
+    }
+
+    /// Passes the environment of the host process to the guest.
+    ///
+    /// The environment is read when the config is applied, before the variables set with `env`.
+    pub fn inherit_env(&mut self) -> &mut Self {
+        self.inherit_env = true;
+        self
+    }
+
+    /// Makes the host directory `host` visible to the guest as `guest`, with write access.
+    pub fn preopen_dir(&mut self, host: impl AsRef<Path>, guest: impl Into<String>) -> &mut Self {
+        self.preopens.push(PreopenDir {
+            host: host.as_ref().to_path_buf(),
+            guest: guest.into(),
+        });
+        self
+    }
+
+    pub fn get_args(&self) -> &[String] {
+        &self.args
+    }
+
+    pub fn get_preopens(&self) -> &[PreopenDir] {
+        &self.preopens
+    }
+
+    /// Returns the environment variables in `KEY=VALUE` form, in the order they are passed on.
+    pub fn get_envs(&self) -> Vec<String> {
+        let mut envs: Vec<(String, String)> = vec![];
+        if self.inherit_env {
+            // Variables that are not valid UTF-8 are skipped rather than passed on mangled
+            envs.extend(std::env::vars_os().filter_map(|(key, value)| {
+                Some((key.into_string().ok()?, value.into_string().ok()?))
+            }));
+        }
+        for (key, value) in self.envs.iter() {
+            envs.retain(|(k, _)| k != key);
+            envs.push((key.clone(), value.clone()));
+        }
+        envs.into_iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect()
+    }
+
+    /// Marshals the configuration into C string arrays that live as long as the returned value.
+    pub(crate) fn to_c_arrays(&self) -> WasmEdgeResult<WasiCArrays> {
+        for (key, _) in self.envs.iter() {
+            if key.is_empty() || key.contains('=') {
+                return Err(WasmEdgeError::from(format!(
+                    "Invalid environment variable name '{}'",
+                    key.escape_default()
+                )));
+            }
+        }
+        let dirs = self
+            .preopens
+            .iter()
+            .map(PreopenDir::mapping)
+            .collect::<WasmEdgeResult<Vec<_>>>()?;
+        let no_preopens: [&str; 0] = [];
+        Ok(WasiCArrays {
+            args: CStringArray::new(&self.args)?,
+            envs: CStringArray::new(&self.get_envs())?,
+            dirs: CStringArray::new(&dirs)?,
+            preopens: CStringArray::new(&no_preopens)?,
+        })
+    }
+}
+
+pub(crate) struct WasiCArrays {
+    pub(crate) args: CStringArray,
+    pub(crate) envs: CStringArray,
+    pub(crate) dirs: CStringArray,
+    pub(crate) preopens: CStringArray,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wasi_config() {
+        let mut config = WasiConfig::new();
+        config
+            .arg("app.wasm")
+            .args(vec!["--verbose", "input.txt"])
+            .env("KEY1", "VAL1")
+            .env("KEY2", "a=b")
+            .env("KEY1", "VAL3")
+            .preopen_dir("/tmp", "/sandbox")
+            .preopen_dir(".", ".");
+        assert_eq!(config.get_args(), &["app.wasm", "--verbose", "input.txt"]);
+        assert_eq!(config.get_envs(), vec!["KEY2=a=b", "KEY1=VAL3"]);
+        assert_eq!(config.get_preopens().len(), 2);
+        assert_eq!(config.get_preopens()[0].mapping().unwrap(), "/sandbox:/tmp");
+        assert_eq!(config.get_preopens()[1].mapping().unwrap(), ".:.");
+
+        let arrays = config.to_c_arrays().unwrap();
+        assert_eq!(arrays.args.len(), 3);
+        assert_eq!(arrays.envs.len(), 2);
+        assert_eq!(arrays.dirs.len(), 2);
+        assert_eq!(arrays.preopens.len(), 0);
+        assert!(arrays.preopens.as_ptr().is_null());
+    }
+
+    #[test]
+    fn test_wasi_config_inherit_env() {
+        // Use a variable the process already has, as tests run in parallel and must not modify
+        // the shared environment
+        let (key, value) = match std::env::vars().find(|(key, _)| !key.is_empty()) {
+            Some(var) => var,
+            None => return,
+        };
+        let inherited = format!("{}={}", key, value);
+        let mut config = WasiConfig::new();
+        config.inherit_env();
+        assert!(config.get_envs().contains(&inherited));
+        config.env(key.as_str(), format!("{}-overridden", value));
+        let envs = config.get_envs();
+        assert!(envs.contains(&format!("{}-overridden", inherited)));
+        assert!(!envs.contains(&inherited));
+    }
+
+    #[test]
+    fn test_wasi_config_invalid() {
+        let mut config = WasiConfig::new();
+        config.arg("arg\0");
+        assert!(config.to_c_arrays().is_err());
+
+        let mut config = WasiConfig::new();
+        config.env("KEY=1", "VAL");
+        assert!(config.to_c_arrays().is_err());
+
+        let mut config = WasiConfig::new();
+        config.env("KEY", "VAL\0");
+        assert!(config.to_c_arrays().is_err());
+
+        let mut config = WasiConfig::new();
+        config.preopen_dir("/tmp", "guest:dir");
+        assert!(config.to_c_arrays().is_err());
+    }
+}
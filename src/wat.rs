@@ -0,0 +1,50 @@
+//! Conversion of the WebAssembly text format into the binary format.
+
+use crate::error::{WasmEdgeError, WasmEdgeResult};
+use wast::parser::{self, ParseBuffer};
+use wast::Wat;
+
+/// Assembles a module in the text format into the binary format.
+///
+/// Errors carry the 1-based line and column of the offending token in `text`.
+pub(crate) fn wat_to_wasm(text: &str) -> WasmEdgeResult<Vec<u8>> {
+    let to_error = |err: wast::Error| {
+        let (line, column) = err.span().linecol_in(text);
+        WasmEdgeError::Text {
+            line: line + 1,
+            column: column + 1,
+            message: err.message(),
+        }
+    };
+    let buf = ParseBuffer::new(text).map_err(to_error)?;
+    let mut wat = parser::parse::<Wat>(&buf).map_err(to_error)?;
+    wat.module.encode().map_err(to_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorPhase;
+
+    #[test]
+    fn test_wat_to_wasm() {
+        let wasm = wat_to_wasm("(module)").unwrap();
+        assert_eq!(wasm, [0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00]);
+
+        let err = wat_to_wasm("(module\n  (func (result i32)\n    i32.const x))").unwrap_err();
+        assert!(matches!(
+            err,
+            WasmEdgeError::Text {
+                line: 3,
+                column: 15,
+                ..
+            }
+        ));
+        assert_eq!(err.phase(), ErrorPhase::Load);
+        assert_eq!(err.code(), None);
+        assert!(err.to_string().contains("line 3, column 15"));
+
+        let err = wat_to_wasm("(module").unwrap_err();
+        assert!(matches!(err, WasmEdgeError::Text { line: 1, .. }));
+    }
+}
@@ -0,0 +1,143 @@
+//! Names and arguments are user input: none of these calls may panic.
+
+use std::ptr;
+use wasmedge::{
+    context::{
+        configure::ConfigureContext, import_object::ImportObjectContext,
+        interpreter::InterpreterContext, store::StoreContext, vm::VMContext,
+    },
+    instance::function::{FunctionTypeContext, HostFunctionContext},
+    types::*,
+    value::Val,
+    wasi::WasiConfig,
+};
+
+const HOSTILE_NAMES: [&str; 5] = ["", "\0", "func\0add", "\u{fffd}\u{0}\u{7f}", "名前"];
+
+const ADD_WASM: &[u8] = &[
+    /* WASM header */
+    0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00, /* Type section */
+    0x01, 0x07, 0x01, /* function type {i32, i32} -> {i32} */
+    0x60, 0x02, 0x7F, 0x7F, 0x01, 0x7F, /* Function section */
+    0x03, 0x02, 0x01, 0x00, /* Export section */
+    0x07, 0x07, 0x01, /* export name: "add" */
+    0x03, 0x61, 0x64, 0x64, /* export desc: func 0 */
+    0x00, 0x00, /* Code section */
+    0x0A, 0x09, 0x01, /* code body */
+    0x07, 0x00, 0x20, 0x00, 0x20, 0x01, 0x6A, 0x0B,
+];
+
+#[test]
+fn test_hostile_names_vm() {
+    let mut vm = VMContext::create(None, None).unwrap();
+    for name in HOSTILE_NAMES.iter() {
+        assert!(vm.execute(name, &[], 0).is_err());
+        assert!(vm.execute_registered(name, name, &[], 0).is_err());
+        assert!(vm.function_type(name).is_none());
+        assert!(vm.function_type_registered(name, name).is_none());
+        assert!(vm.register_module_from_buffer(name, &[0x00]).is_err());
+        assert!(vm.register_module_from_file(name, name).is_err());
+        assert!(vm.run_wasm_from_file(name, name, &[], 0).is_err());
+    }
+
+    // A valid module is still not confused by a name with a NUL after a valid prefix
+    let params = [Val::I32(1), Val::I32(2)];
+    let result = vm.run_wasm_from_buffer(ADD_WASM, "add\0", &params, 1);
+    assert!(result.is_err());
+    let result = vm.run_wasm_from_buffer(ADD_WASM, "add", &params, 1);
+    assert_eq!(result.unwrap(), vec![Val::I32(3)]);
+}
+
+#[test]
+fn test_hostile_names_store() {
+    let mut vm = VMContext::create(None, None).unwrap();
+    let params = [Val::I32(1), Val::I32(2)];
+    assert!(vm.run_wasm_from_buffer(ADD_WASM, "add", &params, 1).is_ok());
+    let store = vm.store_context().unwrap();
+    for name in HOSTILE_NAMES.iter() {
+        assert!(store.find_function(name).is_none());
+        assert!(store.find_function_registered(name, name).is_none());
+        assert!(store.find_table(name).is_none());
+        assert!(store.find_table_registered(name, name).is_none());
+        assert!(store.find_memory(name).is_none());
+        assert!(store.find_memory_registered(name, name).is_none());
+        assert!(store.find_global(name).is_none());
+        assert!(store.find_global_registered(name, name).is_none());
+        assert_eq!(store.list_function_registered_len(name), 0);
+        assert_eq!(store.list_table_registered_len(name), 0);
+        assert_eq!(store.list_memory_registered_len(name), 0);
+        assert_eq!(store.list_global_registered_len(name), 0);
+    }
+    assert!(store.find_function("add\0").is_none());
+    assert!(store.find_function("add").is_some());
+}
+
+#[test]
+fn test_hostile_names_interpreter() {
+    let interp = InterpreterContext::create(None, None).unwrap();
+    let mut store = StoreContext::create();
+    for name in HOSTILE_NAMES.iter() {
+        assert!(interp.invoke(&mut store, name, None, 1).is_err());
+        assert!(interp
+            .invoke_registered(&mut store, name, name, None, 1)
+            .is_err());
+    }
+}
+
+#[test]
+fn test_hostile_names_import_object() {
+    unsafe extern "C" fn noop(
+        _data: *mut std::os::raw::c_void,
+        _mem_ctx: *mut wasmedge_sys::ffi::WasmEdge_MemoryInstanceContext,
+        _params: *const WasmEdgeValue,
+        _returns: *mut WasmEdgeValue,
+    ) -> wasmedge_sys::ffi::WasmEdge_Result {
+        wasmedge_sys::ffi::WasmEdge_Result { Code: 0 }
+    }
+
+    let func_type = FunctionTypeContext::create(None, None).unwrap();
+    for name in HOSTILE_NAMES.iter() {
+        let result = ImportObjectContext::create(name, ptr::null_mut());
+        assert!(result.is_ok());
+        let mut imp_obj = result.unwrap();
+        let mut host_func = HostFunctionContext::create(&func_type, Some(noop), 0).unwrap();
+        assert!(imp_obj.add_host_function(name, &mut host_func).is_ok());
+    }
+}
+
+#[test]
+fn test_hostile_wasi_arguments() {
+    let hostile = ["\0", "arg\0", "KEY=\0VALUE"];
+    for arg in hostile.iter() {
+        let mut config = WasiConfig::new();
+        config.arg(*arg);
+        assert!(ImportObjectContext::create_wasi(&config).is_err());
+
+        let mut config = WasiConfig::new();
+        config.env("KEY", *arg);
+        assert!(ImportObjectContext::create_wasi(&config).is_err());
+
+        let mut config = WasiConfig::new();
+        config.env(*arg, "VALUE");
+        assert!(ImportObjectContext::create_wasi(&config).is_err());
+
+        let mut config = WasiConfig::new();
+        config.preopen_dir(*arg, ".");
+        assert!(ImportObjectContext::create_wasi(&config).is_err());
+
+        let mut config = WasiConfig::new();
+        config.preopen_dir(".", *arg);
+        assert!(ImportObjectContext::create_wasi(&config).is_err());
+    }
+
+    let mut conf = ConfigureContext::create();
+    conf.add_host_registration(HostRegistration::Wasi);
+    let vm = VMContext::create(Some(&conf), None).unwrap();
+    let wasi = vm.import_object(HostRegistration::Wasi).unwrap();
+    let mut config = WasiConfig::new();
+    config.args(vec!["prog", "arg\0"]);
+    assert!(wasi.init_wasi(&config).is_err());
+    let mut config = WasiConfig::new();
+    config.arg("prog");
+    assert!(wasi.init_wasi(&config).is_ok());
+}
@@ -11,8 +11,8 @@ use wasmedge::{
 #[test]
 fn test_wasmedge_tensorflow() {
     let mut conf_ctx = ConfigureContext::create();
-    conf_ctx.add_host_registration(HostRegistration::WasmEdge_HostRegistration_Wasi);
-    conf_ctx.add_host_registration(HostRegistration::WasmEdge_HostRegistration_WasmEdge_Process);
+    conf_ctx.add_host_registration(HostRegistration::Wasi);
+    conf_ctx.add_host_registration(HostRegistration::WasmEdgeProcess);
     let result = VMContext::create(Some(&conf_ctx), None);
     assert!(result.is_some());
     let mut vm = result.unwrap();
@@ -62,8 +62,8 @@ fn test_wasmedge_tensorflow() {
     );
 
     // get import-objects
-    let wasi_mod = vm.import_object(HostRegistration::WasmEdge_HostRegistration_Wasi);
-    let proc_mod = vm.import_object(HostRegistration::WasmEdge_HostRegistration_WasmEdge_Process);
+    let wasi_mod = vm.import_object(HostRegistration::Wasi);
+    let proc_mod = vm.import_object(HostRegistration::WasmEdgeProcess);
 
     // // init wasi_mod
     // let args = vec!["using_add.wasm"];
@@ -112,8 +112,8 @@ fn test_wasmedge_tensorflow() {
 #[test]
 fn test_wasmedge_run_wasm() {
     let mut conf_ctx = ConfigureContext::create();
-    conf_ctx.add_host_registration(HostRegistration::WasmEdge_HostRegistration_Wasi);
-    conf_ctx.add_host_registration(HostRegistration::WasmEdge_HostRegistration_WasmEdge_Process);
+    conf_ctx.add_host_registration(HostRegistration::Wasi);
+    conf_ctx.add_host_registration(HostRegistration::WasmEdgeProcess);
     let result = VMContext::create(Some(&conf_ctx), None);
     assert!(result.is_some());
     let mut vm = result.unwrap();